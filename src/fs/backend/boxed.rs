@@ -0,0 +1,353 @@
+use std::{
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use futures::{
+    FutureExt, StreamExt, TryStreamExt,
+    future::LocalBoxFuture,
+    io::{AsyncRead, AsyncSeek, AsyncWrite},
+    stream::LocalBoxStream,
+};
+
+use super::{Backend, FsDirEntry, FsMetadata};
+use crate::fs::{OpenOptions, Permissions};
+
+/// A file opened through a [`DynBackend`].
+pub trait FsFile: AsyncRead + AsyncWrite + AsyncSeek + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + AsyncSeek + Unpin + ?Sized> FsFile for T {}
+
+pub type BoxFile = Box<dyn FsFile>;
+pub type BoxMetadata = Box<dyn FsMetadata>;
+pub type BoxDirEntry = Box<dyn FsDirEntry>;
+pub type BoxReadDir = LocalBoxStream<'static, io::Result<BoxDirEntry>>;
+
+/// The object-safe form of [`Backend`], for choosing a filesystem at runtime.
+///
+/// Every [`Backend`] implements it, so `Box::new(InMemoryFs::new())` and
+/// `Box::new(DefaultFs)` both coerce to `Box<dyn DynBackend>`. The box in turn
+/// implements [`Backend`], so it can be handed to code generic over `impl Backend`.
+///
+/// Both traits have the same method names; import only the one you call through.
+///
+/// Like [`Backend`], its futures and streams are not `Send` (`LocalBoxFuture`,
+/// `LocalBoxStream`), so a `Box<dyn DynBackend>` cannot be used from tasks
+/// spawned on a multi-threaded runtime.
+pub trait DynBackend {
+    fn read<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<Vec<u8>>>;
+    fn read_to_string<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<String>>;
+    fn write<'a>(&'a self, path: &'a Path, content: &'a [u8])
+    -> LocalBoxFuture<'a, io::Result<()>>;
+    fn copy<'a>(&'a self, from: &'a Path, to: &'a Path) -> LocalBoxFuture<'a, io::Result<u64>>;
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> LocalBoxFuture<'a, io::Result<()>>;
+    fn read_dir<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<BoxReadDir>>;
+    fn create_dir<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>>;
+    fn create_dir_all<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>>;
+    fn remove_file<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>>;
+    fn remove_dir<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>>;
+    fn remove_dir_all<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>>;
+    fn metadata<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<BoxMetadata>>;
+    fn try_exists<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<bool>>;
+    fn canonicalize<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<PathBuf>>;
+    fn hard_link<'a>(
+        &'a self,
+        original: &'a Path,
+        link: &'a Path,
+    ) -> LocalBoxFuture<'a, io::Result<()>>;
+    fn symlink<'a>(
+        &'a self,
+        original: &'a Path,
+        link: &'a Path,
+    ) -> LocalBoxFuture<'a, io::Result<()>>;
+    fn read_link<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<PathBuf>>;
+    fn symlink_metadata<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> LocalBoxFuture<'a, io::Result<BoxMetadata>>;
+    fn set_permissions<'a>(
+        &'a self,
+        path: &'a Path,
+        perm: Permissions,
+    ) -> LocalBoxFuture<'a, io::Result<()>>;
+    fn open_with<'a>(
+        &'a self,
+        path: &'a Path,
+        options: &'a OpenOptions,
+    ) -> LocalBoxFuture<'a, io::Result<BoxFile>>;
+    fn open<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<BoxFile>>;
+    fn create<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<BoxFile>>;
+}
+
+impl<B> DynBackend for B
+where
+    B: Backend,
+    B::File: 'static,
+    B::Metadata: 'static,
+    B::DirEntry: 'static,
+    B::ReadDir: 'static,
+{
+    fn read<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<Vec<u8>>> {
+        Backend::read(self, path).boxed_local()
+    }
+
+    fn read_to_string<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<String>> {
+        Backend::read_to_string(self, path).boxed_local()
+    }
+
+    fn write<'a>(
+        &'a self,
+        path: &'a Path,
+        content: &'a [u8],
+    ) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::write(self, path, content).boxed_local()
+    }
+
+    fn copy<'a>(&'a self, from: &'a Path, to: &'a Path) -> LocalBoxFuture<'a, io::Result<u64>> {
+        Backend::copy(self, from, to).boxed_local()
+    }
+
+    fn rename<'a>(&'a self, from: &'a Path, to: &'a Path) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::rename(self, from, to).boxed_local()
+    }
+
+    fn read_dir<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<BoxReadDir>> {
+        Backend::read_dir(self, path)
+            .map(|r| r.map(|rd| rd.map_ok(|e| Box::new(e) as BoxDirEntry).boxed_local()))
+            .boxed_local()
+    }
+
+    fn create_dir<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::create_dir(self, path).boxed_local()
+    }
+
+    fn create_dir_all<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::create_dir_all(self, path).boxed_local()
+    }
+
+    fn remove_file<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::remove_file(self, path).boxed_local()
+    }
+
+    fn remove_dir<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::remove_dir(self, path).boxed_local()
+    }
+
+    fn remove_dir_all<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::remove_dir_all(self, path).boxed_local()
+    }
+
+    fn metadata<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<BoxMetadata>> {
+        Backend::metadata(self, path)
+            .map(|r| r.map(|m| Box::new(m) as BoxMetadata))
+            .boxed_local()
+    }
+
+    fn try_exists<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<bool>> {
+        Backend::try_exists(self, path).boxed_local()
+    }
+
+    fn canonicalize<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<PathBuf>> {
+        Backend::canonicalize(self, path).boxed_local()
+    }
+
+    fn hard_link<'a>(
+        &'a self,
+        original: &'a Path,
+        link: &'a Path,
+    ) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::hard_link(self, original, link).boxed_local()
+    }
+
+    fn symlink<'a>(
+        &'a self,
+        original: &'a Path,
+        link: &'a Path,
+    ) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::symlink(self, original, link).boxed_local()
+    }
+
+    fn read_link<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<PathBuf>> {
+        Backend::read_link(self, path).boxed_local()
+    }
+
+    fn symlink_metadata<'a>(
+        &'a self,
+        path: &'a Path,
+    ) -> LocalBoxFuture<'a, io::Result<BoxMetadata>> {
+        Backend::symlink_metadata(self, path)
+            .map(|r| r.map(|m| Box::new(m) as BoxMetadata))
+            .boxed_local()
+    }
+
+    fn set_permissions<'a>(
+        &'a self,
+        path: &'a Path,
+        perm: Permissions,
+    ) -> LocalBoxFuture<'a, io::Result<()>> {
+        Backend::set_permissions(self, path, perm).boxed_local()
+    }
+
+    fn open_with<'a>(
+        &'a self,
+        path: &'a Path,
+        options: &'a OpenOptions,
+    ) -> LocalBoxFuture<'a, io::Result<BoxFile>> {
+        Backend::open_with(self, path, options)
+            .map(|r| r.map(|f| Box::new(f) as BoxFile))
+            .boxed_local()
+    }
+
+    fn open<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<BoxFile>> {
+        Backend::open(self, path)
+            .map(|r| r.map(|f| Box::new(f) as BoxFile))
+            .boxed_local()
+    }
+
+    fn create<'a>(&'a self, path: &'a Path) -> LocalBoxFuture<'a, io::Result<BoxFile>> {
+        Backend::create(self, path)
+            .map(|r| r.map(|f| Box::new(f) as BoxFile))
+            .boxed_local()
+    }
+}
+
+impl Backend for Box<dyn DynBackend> {
+    type File = BoxFile;
+    type Metadata = BoxMetadata;
+    type DirEntry = BoxDirEntry;
+    type ReadDir = BoxReadDir;
+
+    async fn read(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        (**self).read(path.as_ref()).await
+    }
+
+    async fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        (**self).read_to_string(path.as_ref()).await
+    }
+
+    async fn write(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()> {
+        (**self).write(path.as_ref(), content.as_ref()).await
+    }
+
+    async fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<u64> {
+        (**self).copy(from.as_ref(), to.as_ref()).await
+    }
+
+    async fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        (**self).rename(from.as_ref(), to.as_ref()).await
+    }
+
+    async fn read_dir(&self, path: impl AsRef<Path>) -> io::Result<BoxReadDir> {
+        (**self).read_dir(path.as_ref()).await
+    }
+
+    async fn create_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        (**self).create_dir(path.as_ref()).await
+    }
+
+    async fn create_dir_all(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        (**self).create_dir_all(path.as_ref()).await
+    }
+
+    async fn remove_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        (**self).remove_file(path.as_ref()).await
+    }
+
+    async fn remove_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        (**self).remove_dir(path.as_ref()).await
+    }
+
+    async fn remove_dir_all(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        (**self).remove_dir_all(path.as_ref()).await
+    }
+
+    async fn metadata(&self, path: impl AsRef<Path>) -> io::Result<BoxMetadata> {
+        (**self).metadata(path.as_ref()).await
+    }
+
+    async fn try_exists(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        (**self).try_exists(path.as_ref()).await
+    }
+
+    async fn canonicalize(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        (**self).canonicalize(path.as_ref()).await
+    }
+
+    async fn hard_link(
+        &self,
+        original: impl AsRef<Path>,
+        link: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        (**self).hard_link(original.as_ref(), link.as_ref()).await
+    }
+
+    async fn symlink(&self, original: impl AsRef<Path>, link: impl AsRef<Path>) -> io::Result<()> {
+        (**self).symlink(original.as_ref(), link.as_ref()).await
+    }
+
+    async fn read_link(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        (**self).read_link(path.as_ref()).await
+    }
+
+    async fn symlink_metadata(&self, path: impl AsRef<Path>) -> io::Result<BoxMetadata> {
+        (**self).symlink_metadata(path.as_ref()).await
+    }
+
+    async fn set_permissions(&self, path: impl AsRef<Path>, perm: Permissions) -> io::Result<()> {
+        (**self).set_permissions(path.as_ref(), perm).await
+    }
+
+    async fn open_with(
+        &self,
+        path: impl AsRef<Path>,
+        options: &OpenOptions,
+    ) -> io::Result<BoxFile> {
+        (**self).open_with(path.as_ref(), options).await
+    }
+
+    async fn open(&self, path: impl AsRef<Path>) -> io::Result<BoxFile> {
+        (**self).open(path.as_ref()).await
+    }
+
+    async fn create(&self, path: impl AsRef<Path>) -> io::Result<BoxFile> {
+        (**self).create(path.as_ref()).await
+    }
+}
+
+impl<M: FsMetadata + ?Sized> FsMetadata for Box<M> {
+    fn is_dir(&self) -> bool {
+        (**self).is_dir()
+    }
+
+    fn is_file(&self) -> bool {
+        (**self).is_file()
+    }
+
+    fn is_symlink(&self) -> bool {
+        (**self).is_symlink()
+    }
+
+    fn len(&self) -> u64 {
+        (**self).len()
+    }
+
+    fn modified(&self) -> io::Result<SystemTime> {
+        (**self).modified()
+    }
+
+    fn readonly(&self) -> bool {
+        (**self).readonly()
+    }
+}
+
+impl<E: FsDirEntry + ?Sized> FsDirEntry for Box<E> {
+    fn path(&self) -> PathBuf {
+        (**self).path()
+    }
+
+    fn file_name(&self) -> OsString {
+        (**self).file_name()
+    }
+}
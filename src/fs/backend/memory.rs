@@ -0,0 +1,801 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    ffi::OsString,
+    io::{self, SeekFrom},
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard},
+    task::{Context, Poll},
+    time::SystemTime,
+};
+
+use futures::{
+    Stream,
+    io::{AsyncRead, AsyncSeek, AsyncWrite},
+};
+
+use super::{Backend, FsDirEntry, FsMetadata};
+use crate::fs::{OpenOptions, Permissions};
+
+/// Links followed while resolving one path before giving up, like the wasm backend.
+const MAX_LINK_FOLLOWS: usize = 40;
+
+/// A filesystem that lives entirely in memory.
+///
+/// It follows the OPFS semantics of the wasm backend (a single root, relative
+/// paths resolved against `/`, emulated links) and works on every target, which
+/// makes it suitable for unit-testing code written against [`Backend`].
+/// Symlinks are followed in every component of a path, hard links share the
+/// file contents, and a read-only file refuses writes, truncation and removal
+/// like it does on OPFS.
+///
+/// Clones share the same tree.
+#[derive(Debug, Clone)]
+pub struct InMemoryFs {
+    tree: Arc<Mutex<Tree>>,
+}
+
+#[derive(Debug)]
+struct Tree {
+    nodes: BTreeMap<PathBuf, Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    File(Arc<Mutex<FileData>>),
+    Dir { mtime: SystemTime, readonly: bool },
+    Symlink { target: PathBuf, mtime: SystemTime },
+}
+
+#[derive(Debug)]
+struct FileData {
+    bytes: Vec<u8>,
+    mtime: SystemTime,
+    readonly: bool,
+}
+
+impl InMemoryFs {
+    pub fn new() -> Self {
+        let mut nodes = BTreeMap::new();
+        nodes.insert(PathBuf::from("/"), new_dir());
+        Self {
+            tree: Arc::new(Mutex::new(Tree { nodes })),
+        }
+    }
+
+    fn tree(&self) -> io::Result<MutexGuard<'_, Tree>> {
+        self.tree
+            .lock()
+            .map_err(|_| io::Error::from(io::ErrorKind::Deadlock))
+    }
+}
+
+impl Default for InMemoryFs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tree {
+    /// Normalizes `path` and follows the symlinks in it. The last component
+    /// is only followed with `follow_last`, for operations that act on the
+    /// link itself.
+    fn resolve(&self, path: impl AsRef<Path>, follow_last: bool) -> io::Result<PathBuf> {
+        let mut pending: VecDeque<OsString> = components(&normalize(path)).collect();
+        let mut resolved = PathBuf::from("/");
+        let mut follows = 0;
+
+        while let Some(name) = pending.pop_front() {
+            if name == ".." {
+                resolved.pop();
+                continue;
+            }
+            let candidate = resolved.join(&name);
+            match self.nodes.get(&candidate) {
+                Some(Node::Symlink { target, .. }) if follow_last || !pending.is_empty() => {
+                    follows += 1;
+                    if follows > MAX_LINK_FOLLOWS {
+                        return Err(io::Error::other("too many levels of symbolic links"));
+                    }
+                    if target.is_absolute() {
+                        resolved = PathBuf::from("/");
+                    }
+                    // `..` is kept so it applies to the directory the link lives in.
+                    for c in components(target).collect::<Vec<_>>().into_iter().rev() {
+                        pending.push_front(c);
+                    }
+                }
+                _ => resolved = candidate,
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn get(&self, path: &Path) -> io::Result<&Node> {
+        self.nodes
+            .get(path)
+            .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+    }
+
+    fn check_parent(&self, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) => match self.get(parent)? {
+                Node::Dir { .. } => Ok(()),
+                Node::File(_) | Node::Symlink { .. } => {
+                    Err(io::Error::from(io::ErrorKind::NotADirectory))
+                }
+            },
+            None => Err(io::Error::from(io::ErrorKind::InvalidFilename)),
+        }
+    }
+
+    fn children(&self, path: &Path) -> impl Iterator<Item = (&PathBuf, &Node)> {
+        self.nodes
+            .range(path.to_path_buf()..)
+            .skip(1)
+            .take_while(move |(k, _)| k.starts_with(path))
+            .filter(move |(k, _)| k.parent() == Some(path))
+    }
+
+    fn touch_parent(&mut self, path: &Path) {
+        if let Some(Node::Dir { mtime, .. }) = path.parent().and_then(|p| self.nodes.get_mut(p)) {
+            *mtime = now();
+        }
+    }
+
+    fn descendants(&self, path: &Path) -> Vec<PathBuf> {
+        self.nodes
+            .range(path.to_path_buf()..)
+            .take_while(|(k, _)| k.starts_with(path))
+            .map(|(k, _)| k.clone())
+            .collect()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<MemMetadata> {
+        match self.get(path)? {
+            Node::File(data) => Ok(lock_data(data)?.metadata()),
+            Node::Dir { mtime, readonly } => Ok(MemMetadata {
+                file_type: MemFileType::Directory,
+                len: 0,
+                modified: *mtime,
+                readonly: *readonly,
+            }),
+            Node::Symlink { target, mtime } => Ok(MemMetadata {
+                file_type: MemFileType::Symlink,
+                len: target.as_os_str().len() as u64,
+                modified: *mtime,
+                readonly: false,
+            }),
+        }
+    }
+}
+
+impl Backend for InMemoryFs {
+    type File = MemFile;
+    type Metadata = MemMetadata;
+    type DirEntry = MemDirEntry;
+    type ReadDir = MemReadDir;
+
+    async fn read(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        let tree = self.tree()?;
+        match tree.get(&tree.resolve(path, true)?)? {
+            Node::File(data) => Ok(lock_data(data)?.bytes.clone()),
+            Node::Dir { .. } => Err(io::Error::from(io::ErrorKind::IsADirectory)),
+            Node::Symlink { .. } => unreachable!("links are followed"),
+        }
+    }
+
+    async fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        String::from_utf8(self.read(path).await?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    async fn write(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let path = tree.resolve(path, true)?;
+        match tree.nodes.get(&path) {
+            Some(Node::File(data)) => {
+                let mut data = lock_data(data)?;
+                ensure_writable(&data)?;
+                data.bytes = content.as_ref().to_vec();
+                data.mtime = now();
+            }
+            Some(Node::Dir { .. }) => return Err(io::Error::from(io::ErrorKind::IsADirectory)),
+            Some(Node::Symlink { .. }) => unreachable!("links are followed"),
+            None => {
+                tree.check_parent(&path)?;
+                tree.nodes
+                    .insert(path.clone(), new_file(content.as_ref().to_vec()));
+                tree.touch_parent(&path);
+            }
+        }
+        Ok(())
+    }
+
+    async fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<u64> {
+        let contents = self.read(from).await?;
+        self.write(to, &contents).await?;
+        Ok(contents.len() as u64)
+    }
+
+    async fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let from = tree.resolve(from, false)?;
+        let to = tree.resolve(to, false)?;
+
+        let node = tree.get(&from)?.clone();
+        if from == to {
+            return Ok(());
+        }
+        tree.check_parent(&to)?;
+
+        let is_dir = |node: &Node| matches!(node, Node::Dir { .. });
+        match tree.nodes.get(&to) {
+            Some(existing) if !is_dir(&node) && is_dir(existing) => {
+                return Err(io::Error::from(io::ErrorKind::IsADirectory));
+            }
+            Some(existing) if is_dir(&node) && !is_dir(existing) => {
+                return Err(io::Error::from(io::ErrorKind::NotADirectory));
+            }
+            Some(existing) if is_dir(existing) && tree.children(&to).next().is_some() => {
+                return Err(io::Error::from(io::ErrorKind::DirectoryNotEmpty));
+            }
+            _ if is_dir(&node) && to.starts_with(&from) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "cannot move a directory into itself",
+                ));
+            }
+            _ => {}
+        }
+
+        tree.nodes.remove(&to);
+        for old in tree.descendants(&from) {
+            let node = tree.nodes.remove(&old).expect("descendant exists");
+            let new = to.join(old.strip_prefix(&from).expect("descendant of `from`"));
+            tree.nodes.insert(new, node);
+        }
+        tree.touch_parent(&from);
+        tree.touch_parent(&to);
+        Ok(())
+    }
+
+    async fn read_dir(&self, path: impl AsRef<Path>) -> io::Result<MemReadDir> {
+        let tree = self.tree()?;
+        let path = tree.resolve(path, true)?;
+        if !matches!(tree.get(&path)?, Node::Dir { .. }) {
+            return Err(io::Error::from(io::ErrorKind::NotADirectory));
+        }
+        let entries = tree
+            .children(&path)
+            .map(|(k, node)| MemDirEntry {
+                path: k.clone(),
+                name: k.file_name().unwrap_or_default().to_os_string(),
+                file_type: node.file_type(),
+            })
+            .collect();
+        Ok(MemReadDir { entries })
+    }
+
+    async fn create_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let path = tree.resolve(path, false)?;
+        if tree.nodes.contains_key(&path) {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        tree.check_parent(&path)?;
+        tree.nodes.insert(path.clone(), new_dir());
+        tree.touch_parent(&path);
+        Ok(())
+    }
+
+    async fn create_dir_all(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let path = tree.resolve(path, true)?;
+        let mut ancestors: Vec<&Path> = path.ancestors().collect();
+        ancestors.reverse();
+        for dir in ancestors {
+            match tree.nodes.get(dir) {
+                Some(Node::Dir { .. }) => {}
+                Some(_) => return Err(io::Error::from(io::ErrorKind::NotADirectory)),
+                None => {
+                    tree.nodes.insert(dir.to_path_buf(), new_dir());
+                    tree.touch_parent(dir);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn remove_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let path = tree.resolve(path, false)?;
+        match tree.get(&path)? {
+            Node::Dir { .. } => return Err(io::Error::from(io::ErrorKind::IsADirectory)),
+            Node::File(data) => ensure_writable(&*lock_data(data)?)?,
+            Node::Symlink { .. } => {}
+        }
+        tree.nodes.remove(&path);
+        tree.touch_parent(&path);
+        Ok(())
+    }
+
+    async fn remove_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let path = tree.resolve(path, false)?;
+        if !matches!(tree.get(&path)?, Node::Dir { .. }) {
+            return Err(io::Error::from(io::ErrorKind::NotADirectory));
+        }
+        if path.parent().is_none() {
+            return Err(io::Error::from(io::ErrorKind::ResourceBusy));
+        }
+        if tree.children(&path).next().is_some() {
+            return Err(io::Error::from(io::ErrorKind::DirectoryNotEmpty));
+        }
+        tree.nodes.remove(&path);
+        tree.touch_parent(&path);
+        Ok(())
+    }
+
+    /// Removes a symlink at `path` itself rather than what it points to.
+    async fn remove_dir_all(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let path = tree.resolve(path, false)?;
+        match tree.get(&path)? {
+            Node::File(_) => return Err(io::Error::from(io::ErrorKind::NotADirectory)),
+            Node::Dir { .. } | Node::Symlink { .. } => {}
+        }
+        if path.parent().is_none() {
+            return Err(io::Error::from(io::ErrorKind::ResourceBusy));
+        }
+        for k in tree.descendants(&path) {
+            tree.nodes.remove(&k);
+        }
+        tree.touch_parent(&path);
+        Ok(())
+    }
+
+    async fn metadata(&self, path: impl AsRef<Path>) -> io::Result<MemMetadata> {
+        let tree = self.tree()?;
+        tree.metadata(&tree.resolve(path, true)?)
+    }
+
+    async fn try_exists(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        let tree = self.tree()?;
+        let path = tree.resolve(path, true)?;
+        Ok(tree.nodes.contains_key(&path))
+    }
+
+    async fn canonicalize(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let tree = self.tree()?;
+        let path = tree.resolve(path, true)?;
+        tree.get(&path)?;
+        Ok(path)
+    }
+
+    /// Follows a symlink at `original`, like the wasm backend.
+    async fn hard_link(
+        &self,
+        original: impl AsRef<Path>,
+        link: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let original = tree.resolve(original, true)?;
+        let link = tree.resolve(link, false)?;
+        let node = match tree.get(&original)? {
+            node @ Node::File(_) => node.clone(),
+            Node::Dir { .. } => return Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+            Node::Symlink { .. } => unreachable!("links are followed"),
+        };
+        if tree.nodes.contains_key(&link) {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        tree.check_parent(&link)?;
+        tree.nodes.insert(link.clone(), node);
+        tree.touch_parent(&link);
+        Ok(())
+    }
+
+    /// Stores `original` as given; relative targets are resolved against the
+    /// directory containing `link` when followed.
+    async fn symlink(&self, original: impl AsRef<Path>, link: impl AsRef<Path>) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let link = tree.resolve(link, false)?;
+        if tree.nodes.contains_key(&link) {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        tree.check_parent(&link)?;
+        tree.nodes.insert(
+            link.clone(),
+            Node::Symlink {
+                target: original.as_ref().to_path_buf(),
+                mtime: now(),
+            },
+        );
+        tree.touch_parent(&link);
+        Ok(())
+    }
+
+    async fn read_link(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let tree = self.tree()?;
+        match tree.get(&tree.resolve(path, false)?)? {
+            Node::Symlink { target, .. } => Ok(target.clone()),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "not a symlink")),
+        }
+    }
+
+    async fn symlink_metadata(&self, path: impl AsRef<Path>) -> io::Result<MemMetadata> {
+        let tree = self.tree()?;
+        tree.metadata(&tree.resolve(path, false)?)
+    }
+
+    async fn set_permissions(&self, path: impl AsRef<Path>, perm: Permissions) -> io::Result<()> {
+        let mut tree = self.tree()?;
+        let path = tree.resolve(path, true)?;
+        match tree.nodes.get_mut(&path) {
+            Some(Node::File(data)) => lock_data(data)?.readonly = perm.readonly(),
+            Some(Node::Dir { readonly, .. }) => *readonly = perm.readonly(),
+            Some(Node::Symlink { .. }) => unreachable!("links are followed"),
+            None => return Err(io::Error::from(io::ErrorKind::NotFound)),
+        }
+        Ok(())
+    }
+
+    async fn open_with(
+        &self,
+        path: impl AsRef<Path>,
+        options: &OpenOptions,
+    ) -> io::Result<MemFile> {
+        let flags = options.open_flags();
+        flags.validate()?;
+
+        let mut tree = self.tree()?;
+        let path = tree.resolve(path, true)?;
+        let data = match tree.nodes.get(&path) {
+            Some(_) if flags.create_new => {
+                return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+            }
+            Some(Node::Dir { .. }) => return Err(io::Error::from(io::ErrorKind::IsADirectory)),
+            Some(Node::Symlink { .. }) => unreachable!("links are followed"),
+            Some(Node::File(data)) => {
+                let data = data.clone();
+                if flags.write || flags.append || flags.truncate {
+                    ensure_writable(&*lock_data(&data)?)?;
+                }
+                if flags.truncate {
+                    let mut guard = lock_data(&data)?;
+                    guard.bytes.clear();
+                    guard.mtime = now();
+                }
+                data
+            }
+            None if flags.create || flags.create_new => {
+                tree.check_parent(&path)?;
+                let node = new_file(Vec::new());
+                let Node::File(data) = &node else {
+                    unreachable!()
+                };
+                let data = data.clone();
+                tree.nodes.insert(path.clone(), node);
+                tree.touch_parent(&path);
+                data
+            }
+            None => return Err(io::Error::from(io::ErrorKind::NotFound)),
+        };
+
+        Ok(MemFile {
+            data,
+            pos: 0,
+            read: flags.read,
+            write: flags.write || flags.append,
+            append: flags.append,
+        })
+    }
+}
+
+impl Node {
+    fn file_type(&self) -> MemFileType {
+        match self {
+            Node::File(_) => MemFileType::File,
+            Node::Dir { .. } => MemFileType::Directory,
+            Node::Symlink { .. } => MemFileType::Symlink,
+        }
+    }
+}
+
+/// An open file of an [`InMemoryFs`].
+///
+/// The contents are shared with the filesystem, so writes are visible to
+/// other handles and to [`Backend::read`] immediately.
+#[derive(Debug)]
+pub struct MemFile {
+    data: Arc<Mutex<FileData>>,
+    pos: u64,
+    read: bool,
+    write: bool,
+    append: bool,
+}
+
+impl MemFile {
+    pub fn metadata(&self) -> io::Result<MemMetadata> {
+        Ok(lock_data(&self.data)?.metadata())
+    }
+
+    /// Truncates or extends the file, updating the size of this file to become `size`.
+    ///
+    /// The file's cursor is not changed.
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        if !self.write {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file is opened in read-only mode",
+            ));
+        }
+        let mut data = lock_data(&self.data)?;
+        data.bytes.resize(size as usize, 0);
+        data.mtime = now();
+        Ok(())
+    }
+}
+
+impl AsyncRead for MemFile {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if !self.read {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file is opened in write-only mode",
+            )));
+        }
+        let n = {
+            let data = lock_data(&self.data)?;
+            let start = (self.pos as usize).min(data.bytes.len());
+            let n = buf.len().min(data.bytes.len() - start);
+            buf[..n].copy_from_slice(&data.bytes[start..start + n]);
+            n
+        };
+        self.pos += n as u64;
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for MemFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        if !self.write {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file is opened in read-only mode",
+            )));
+        }
+        let end = {
+            let mut data = lock_data(&self.data)?;
+            let start = if self.append {
+                data.bytes.len()
+            } else {
+                self.pos as usize
+            };
+            let end = start + buf.len();
+            if data.bytes.len() < end {
+                data.bytes.resize(end, 0);
+            }
+            data.bytes[start..end].copy_from_slice(buf);
+            data.mtime = now();
+            end
+        };
+        self.pos = end as u64;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for MemFile {
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        position: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        let pos = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => {
+                (lock_data(&self.data)?.bytes.len() as u64).checked_add_signed(offset)
+            }
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        };
+        self.pos = pos.ok_or(io::Error::from(io::ErrorKind::InvalidInput))?;
+        Poll::Ready(Ok(self.pos))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemFileType {
+    File,
+    Directory,
+    Symlink,
+}
+
+impl MemFileType {
+    pub fn is_dir(&self) -> bool {
+        *self == Self::Directory
+    }
+
+    pub fn is_file(&self) -> bool {
+        *self == Self::File
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        *self == Self::Symlink
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemMetadata {
+    file_type: MemFileType,
+    len: u64,
+    modified: SystemTime,
+    readonly: bool,
+}
+
+impl MemMetadata {
+    pub fn file_type(&self) -> MemFileType {
+        self.file_type
+    }
+}
+
+impl FsMetadata for MemMetadata {
+    fn is_dir(&self) -> bool {
+        self.file_type.is_dir()
+    }
+
+    fn is_file(&self) -> bool {
+        self.file_type.is_file()
+    }
+
+    fn is_symlink(&self) -> bool {
+        self.file_type.is_symlink()
+    }
+
+    fn len(&self) -> u64 {
+        self.len
+    }
+
+    fn modified(&self) -> io::Result<SystemTime> {
+        Ok(self.modified)
+    }
+
+    fn readonly(&self) -> bool {
+        self.readonly
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MemDirEntry {
+    path: PathBuf,
+    name: OsString,
+    file_type: MemFileType,
+}
+
+impl MemDirEntry {
+    pub fn file_type(&self) -> io::Result<MemFileType> {
+        Ok(self.file_type)
+    }
+}
+
+impl FsDirEntry for MemDirEntry {
+    fn path(&self) -> PathBuf {
+        self.path.clone()
+    }
+
+    fn file_name(&self) -> OsString {
+        self.name.clone()
+    }
+}
+
+/// A snapshot of a directory's entries, returned in name order.
+#[derive(Debug)]
+pub struct MemReadDir {
+    entries: VecDeque<MemDirEntry>,
+}
+
+impl MemReadDir {
+    pub async fn next_entry(&mut self) -> io::Result<Option<MemDirEntry>> {
+        Ok(self.entries.pop_front())
+    }
+}
+
+impl Stream for MemReadDir {
+    type Item = io::Result<MemDirEntry>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.entries.pop_front().map(Ok))
+    }
+}
+
+impl FileData {
+    fn metadata(&self) -> MemMetadata {
+        MemMetadata {
+            file_type: MemFileType::File,
+            len: self.bytes.len() as u64,
+            modified: self.mtime,
+            readonly: self.readonly,
+        }
+    }
+}
+
+fn new_file(bytes: Vec<u8>) -> Node {
+    Node::File(Arc::new(Mutex::new(FileData {
+        bytes,
+        mtime: now(),
+        readonly: false,
+    })))
+}
+
+fn new_dir() -> Node {
+    Node::Dir {
+        mtime: now(),
+        readonly: false,
+    }
+}
+
+fn ensure_writable(data: &FileData) -> io::Result<()> {
+    if data.readonly {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "file is read-only",
+        ));
+    }
+    Ok(())
+}
+
+fn lock_data(data: &Mutex<FileData>) -> io::Result<MutexGuard<'_, FileData>> {
+    data.lock()
+        .map_err(|_| io::Error::from(io::ErrorKind::Deadlock))
+}
+
+/// Resolves `path` against `/`, removing `.` and `..` components.
+fn normalize(path: impl AsRef<Path>) -> PathBuf {
+    let mut out = PathBuf::from("/");
+    for comp in path.as_ref().components() {
+        match comp {
+            Component::Normal(c) => out.push(c),
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::RootDir | Component::CurDir | Component::Prefix(_) => {}
+        }
+    }
+    out
+}
+
+/// The names in `path`, keeping `..` so it can be applied after links are followed.
+fn components(path: &Path) -> impl Iterator<Item = OsString> + '_ {
+    path.components().filter_map(|comp| match comp {
+        Component::Normal(c) => Some(c.to_os_string()),
+        Component::ParentDir => Some(OsString::from("..")),
+        Component::RootDir | Component::CurDir | Component::Prefix(_) => None,
+    })
+}
+
+// `SystemTime::now` panics on `wasm32-unknown-unknown`.
+fn now() -> SystemTime {
+    cfg_if::cfg_if! {
+        if #[cfg(all(target_family = "wasm", target_os = "unknown"))] {
+            SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(js_sys::Date::now() as u64)
+        } else {
+            SystemTime::now()
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    ffi::OsString,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use futures::{
+    Stream,
+    io::{AsyncRead, AsyncSeek, AsyncWrite},
+};
+
+use crate::fs::{DirEntry, File, Metadata, OpenOptions, Permissions, ReadDirStream};
+
+mod boxed;
+mod memory;
+
+pub use boxed::{BoxDirEntry, BoxFile, BoxMetadata, BoxReadDir, DynBackend, FsFile};
+pub use memory::{InMemoryFs, MemDirEntry, MemFile, MemFileType, MemMetadata, MemReadDir};
+
+/// A filesystem implementation that can be swapped at runtime.
+///
+/// The methods mirror the free functions exported by this crate, so code written
+/// against `impl Backend` runs unchanged on top of [`DefaultFs`] (`tokio::fs` on
+/// native, OPFS on `wasm32-unknown-unknown`) or on top of [`InMemoryFs`].
+///
+/// The trait is not object-safe; use [`DynBackend`] to pick a backend at runtime.
+///
+/// The returned futures are not `Send`, since the OPFS handles behind them are
+/// not. On native, drive them on a current-thread runtime or a
+/// `tokio::task::LocalSet` rather than with `tokio::spawn`.
+#[allow(async_fn_in_trait)]
+pub trait Backend {
+    type File: AsyncRead + AsyncWrite + AsyncSeek + Unpin;
+    type Metadata: FsMetadata;
+    type DirEntry: FsDirEntry;
+    type ReadDir: Stream<Item = io::Result<Self::DirEntry>> + Unpin;
+
+    async fn read(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>>;
+    async fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String>;
+    async fn write(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()>;
+    async fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<u64>;
+    async fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()>;
+    async fn read_dir(&self, path: impl AsRef<Path>) -> io::Result<Self::ReadDir>;
+    async fn create_dir(&self, path: impl AsRef<Path>) -> io::Result<()>;
+    async fn create_dir_all(&self, path: impl AsRef<Path>) -> io::Result<()>;
+    async fn remove_file(&self, path: impl AsRef<Path>) -> io::Result<()>;
+    async fn remove_dir(&self, path: impl AsRef<Path>) -> io::Result<()>;
+    async fn remove_dir_all(&self, path: impl AsRef<Path>) -> io::Result<()>;
+    async fn metadata(&self, path: impl AsRef<Path>) -> io::Result<Self::Metadata>;
+    async fn try_exists(&self, path: impl AsRef<Path>) -> io::Result<bool>;
+    async fn canonicalize(&self, path: impl AsRef<Path>) -> io::Result<PathBuf>;
+    async fn hard_link(&self, original: impl AsRef<Path>, link: impl AsRef<Path>)
+    -> io::Result<()>;
+    async fn symlink(&self, original: impl AsRef<Path>, link: impl AsRef<Path>) -> io::Result<()>;
+    async fn read_link(&self, path: impl AsRef<Path>) -> io::Result<PathBuf>;
+    async fn symlink_metadata(&self, path: impl AsRef<Path>) -> io::Result<Self::Metadata>;
+    async fn set_permissions(&self, path: impl AsRef<Path>, perm: Permissions) -> io::Result<()>;
+
+    /// Opens a file with the given [`OpenOptions`].
+    async fn open_with(
+        &self,
+        path: impl AsRef<Path>,
+        options: &OpenOptions,
+    ) -> io::Result<Self::File>;
+
+    /// Opens a file in read-only mode, like [`File::open`].
+    async fn open(&self, path: impl AsRef<Path>) -> io::Result<Self::File> {
+        self.open_with(path, OpenOptions::new().read(true)).await
+    }
+
+    /// Opens a file in write-only mode, creating or truncating it, like [`File::create`].
+    async fn create(&self, path: impl AsRef<Path>) -> io::Result<Self::File> {
+        self.open_with(
+            path,
+            OpenOptions::new().write(true).create(true).truncate(true),
+        )
+        .await
+    }
+}
+
+/// The subset of `std::fs::Metadata` every backend can report.
+#[allow(clippy::len_without_is_empty)]
+pub trait FsMetadata {
+    fn is_dir(&self) -> bool;
+    fn is_file(&self) -> bool;
+    fn is_symlink(&self) -> bool;
+    fn len(&self) -> u64;
+    fn modified(&self) -> io::Result<SystemTime>;
+    fn readonly(&self) -> bool;
+}
+
+/// The subset of `DirEntry` every backend can report without further I/O.
+pub trait FsDirEntry {
+    fn path(&self) -> PathBuf;
+    fn file_name(&self) -> OsString;
+}
+
+/// The flags set on an [`OpenOptions`], readable by non-default backends.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OpenFlags {
+    pub(crate) read: bool,
+    pub(crate) write: bool,
+    pub(crate) append: bool,
+    pub(crate) truncate: bool,
+    pub(crate) create: bool,
+    pub(crate) create_new: bool,
+}
+
+impl OpenFlags {
+    /// Rejects the combinations `std::fs::OpenOptions` rejects, with the same errors.
+    pub(crate) fn validate(&self) -> io::Result<()> {
+        let creating = self.create || self.create_new || self.truncate;
+        if !(self.read || self.write || self.append || creating) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "must specify at least one of read, write, or append access",
+            ));
+        }
+        let no_write_access = !(self.write || self.append) && creating;
+        let truncate_append = self.append && self.truncate && !self.create_new;
+        if no_write_access || truncate_append {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "creating or truncating a file requires write or append access",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The platform filesystem: `tokio::fs` on native, OPFS on `wasm32-unknown-unknown`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultFs;
+
+impl Backend for DefaultFs {
+    type File = File;
+    type Metadata = Metadata;
+    type DirEntry = DirEntry;
+    type ReadDir = ReadDirStream;
+
+    async fn read(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        crate::read(path).await
+    }
+
+    async fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        crate::read_to_string(path).await
+    }
+
+    async fn write(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()> {
+        crate::write(path, content).await
+    }
+
+    async fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<u64> {
+        crate::copy(from, to).await
+    }
+
+    async fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        crate::rename(from, to).await
+    }
+
+    async fn read_dir(&self, path: impl AsRef<Path>) -> io::Result<ReadDirStream> {
+        Ok(ReadDirStream::new(crate::read_dir(path).await?))
+    }
+
+    async fn create_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        crate::create_dir(path).await
+    }
+
+    async fn create_dir_all(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        crate::create_dir_all(path).await
+    }
+
+    async fn remove_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        crate::remove_file(path).await
+    }
+
+    async fn remove_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        crate::remove_dir(path).await
+    }
+
+    async fn remove_dir_all(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        crate::remove_dir_all(path).await
+    }
+
+    async fn metadata(&self, path: impl AsRef<Path>) -> io::Result<Metadata> {
+        crate::metadata(path).await
+    }
+
+    async fn try_exists(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        crate::try_exists(path).await
+    }
+
+    async fn canonicalize(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        crate::canonicalize(path).await
+    }
+
+    async fn hard_link(
+        &self,
+        original: impl AsRef<Path>,
+        link: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        crate::hard_link(original, link).await
+    }
+
+    async fn symlink(&self, original: impl AsRef<Path>, link: impl AsRef<Path>) -> io::Result<()> {
+        cfg_if::cfg_if! {
+            if #[cfg(target_family = "windows")] {
+                // Windows needs to know the kind of the target; a dangling link is made a file link.
+                let link = link.as_ref();
+                let target = link.parent().unwrap_or(Path::new("")).join(original.as_ref());
+                if crate::metadata(target).await.is_ok_and(|m| m.is_dir()) {
+                    crate::symlink_dir(original, link).await
+                } else {
+                    crate::symlink_file(original, link).await
+                }
+            } else {
+                crate::symlink(original, link).await
+            }
+        }
+    }
+
+    async fn read_link(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        crate::read_link(path).await
+    }
+
+    async fn symlink_metadata(&self, path: impl AsRef<Path>) -> io::Result<Metadata> {
+        crate::symlink_metadata(path).await
+    }
+
+    async fn set_permissions(&self, path: impl AsRef<Path>, perm: Permissions) -> io::Result<()> {
+        crate::set_permissions(path, perm).await
+    }
+
+    async fn open_with(&self, path: impl AsRef<Path>, options: &OpenOptions) -> io::Result<File> {
+        options.open(path).await
+    }
+}
+
+impl FsMetadata for Metadata {
+    fn is_dir(&self) -> bool {
+        Metadata::is_dir(self)
+    }
+
+    fn is_file(&self) -> bool {
+        Metadata::is_file(self)
+    }
+
+    fn is_symlink(&self) -> bool {
+        Metadata::is_symlink(self)
+    }
+
+    fn len(&self) -> u64 {
+        Metadata::len(self)
+    }
+
+    fn modified(&self) -> io::Result<SystemTime> {
+        Metadata::modified(self)
+    }
+
+    fn readonly(&self) -> bool {
+        self.permissions().readonly()
+    }
+}
+
+impl FsDirEntry for DirEntry {
+    fn path(&self) -> PathBuf {
+        DirEntry::path(self)
+    }
+
+    fn file_name(&self) -> OsString {
+        DirEntry::file_name(self)
+    }
+}
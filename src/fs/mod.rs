@@ -1,21 +1,43 @@
 use cfg_if::cfg_if;
+
+pub mod backend;
+pub mod storage;
+
+#[cfg(any(
+    target_family = "unix",
+    all(target_family = "wasm", target_os = "unknown")
+))]
+mod open_options_ext;
+
+#[cfg(any(
+    target_family = "unix",
+    all(target_family = "wasm", target_os = "unknown")
+))]
+pub use open_options_ext::OpenOptionsExt;
+
 cfg_if! {
     if #[cfg(all(target_family = "wasm", target_os = "unknown"))] {
 
         mod wasm;
 
-        pub use wasm::{ File, OpenOptions, current_dir, set_current_dir };
+        pub use wasm::{ BlobReader, File, OpenOptions, WritableFile, current_dir, set_current_dir, write_atomic };
 
         pub use wasm::{
-            DirBuilder, DirEntry, ReadDir, canonicalize, copy, create_dir, create_dir_all,
+            DirBuilder, DirEntry, ReadDir, canonicalize, copy, create_dir, create_dir_all, hard_link,
             metadata, read, read_dir, read_link, read_to_string, remove_dir, remove_dir_all, remove_file,
-            rename, symlink_metadata, try_exists, write,
+            rename, set_permissions, symlink_metadata, try_exists, write,
         };
 
-        pub use wasm::{Metadata, symlink};
+        pub use wasm::{Metadata, Permissions, symlink};
+
+        pub use std::fs::TryLockError;
 
         pub use wasm::ReadDirStream;
 
+        pub use wasm::{LockMode, lock_mode, set_lock_mode};
+
+        pub use wasm::{RetryPolicy, retry_policy, set_retry_policy};
+
         pub use wasm::FileType;
 
         #[cfg(feature = "opfs_offload")]
@@ -28,17 +50,17 @@ cfg_if! {
 
         mod native;
 
-        pub use native::{ File, OpenOptions, current_dir, set_current_dir };
+        pub use native::{ BlobReader, File, OpenOptions, WritableFile, current_dir, set_current_dir, write_atomic };
 
         pub use tokio::fs::{
-            DirBuilder, DirEntry, ReadDir, canonicalize, copy, create_dir, create_dir_all,
+            DirBuilder, DirEntry, ReadDir, canonicalize, copy, create_dir, create_dir_all, hard_link,
             metadata, read, read_dir, read_link, read_to_string, remove_dir, remove_dir_all, remove_file,
-            rename, symlink_metadata, try_exists, write,
+            rename, set_permissions, symlink_metadata, try_exists, write,
         };
 
         pub use tokio_stream::wrappers::ReadDirStream;
 
-        pub use std::fs::Metadata;
+        pub use std::fs::{Metadata, Permissions, TryLockError};
 
         // Specific symlink exports based on OS
         cfg_if! {
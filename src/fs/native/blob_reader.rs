@@ -0,0 +1,135 @@
+use std::{
+    io::{self, SeekFrom},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::io::{AsyncBufRead, AsyncRead, AsyncSeek, BufReader};
+
+use super::File;
+
+/// A buffered, read-only view of a file.
+///
+/// This mirrors the wasm `BlobReader`, which streams a snapshot of the file without taking a
+/// `SyncAccessHandle`. Native files can be read and written concurrently, so here it is a
+/// [`BufReader`] over a file opened read-only, and reads see later changes to the file.
+#[derive(Debug)]
+pub struct BlobReader {
+    inner: BufReader<File>,
+    len: u64,
+    /// Seek started with `tokio::io::AsyncSeek::start_seek`.
+    #[cfg(feature = "tokio-io")]
+    seek: Option<SeekFrom>,
+}
+
+impl BlobReader {
+    /// Opens the file at `path` for reading.
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<BlobReader> {
+        let file = File::open(path).await?;
+        let len = file.metadata().await?.len();
+        Ok(BlobReader {
+            inner: BufReader::new(file),
+            len,
+            #[cfg(feature = "tokio-io")]
+            seek: None,
+        })
+    }
+
+    /// Returns the size of the file in bytes when it was opened.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the file was empty when it was opened.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl File {
+    /// Opens a [`BlobReader`] on the file at `path`.
+    pub async fn open_streaming(path: impl AsRef<Path>) -> io::Result<BlobReader> {
+        BlobReader::open(path).await
+    }
+}
+
+impl AsyncRead for BlobReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl AsyncBufRead for BlobReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Pin::new(&mut self.get_mut().inner).poll_fill_buf(cx)
+    }
+
+    fn consume(mut self: Pin<&mut Self>, amt: usize) {
+        Pin::new(&mut self.inner).consume(amt)
+    }
+}
+
+impl AsyncSeek for BlobReader {
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        Pin::new(&mut self.inner).poll_seek(cx, pos)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncRead for BlobReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let available = std::task::ready!(self.as_mut().poll_fill_buf(cx))?;
+        let n = available.len().min(buf.remaining());
+        buf.put_slice(&available[..n]);
+        AsyncBufRead::consume(self, n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncBufRead for BlobReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        AsyncBufRead::poll_fill_buf(self, cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        AsyncBufRead::consume(self, amt)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncSeek for BlobReader {
+    fn start_seek(mut self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        if self.seek.is_some() {
+            return Err(io::Error::other(
+                "other file operation is pending, call poll_complete before start_seek",
+            ));
+        }
+        self.seek = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        match self.seek {
+            Some(pos) => {
+                let res = std::task::ready!(Pin::new(&mut self.inner).poll_seek(cx, pos));
+                self.seek = None;
+                Poll::Ready(res)
+            }
+            None => Pin::new(&mut self.inner).poll_seek(cx, SeekFrom::Current(0)),
+        }
+    }
+}
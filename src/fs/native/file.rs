@@ -1,13 +1,14 @@
 use std::{
-    fs::Metadata,
+    fs::{Metadata, TryLockError},
     io,
     path::Path,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll, ready},
 };
 
 use pin_project_lite::pin_project;
-use tokio::{fs::OpenOptions, io::AsyncSeek};
+use tokio::{fs::OpenOptions, io::AsyncSeek, sync::OnceCell, task::spawn_blocking};
 
 pin_project! {
     #[derive(Debug)]
@@ -15,22 +16,19 @@ pin_project! {
         #[pin]
         pub(crate) inner: tokio::fs::File,
         pub(crate) seek_pos: Option<io::SeekFrom>,
+        // Duplicate of the file descriptor used for positional I/O, which
+        // must not go through the cursor of `inner`.
+        pub(crate) std: OnceCell<Arc<std::fs::File>>,
     }
 }
 
 impl File {
     pub async fn create(path: impl AsRef<Path>) -> io::Result<File> {
-        Ok(File {
-            inner: tokio::fs::File::create(path).await?,
-            seek_pos: None,
-        })
+        Ok(File::new(tokio::fs::File::create(path).await?))
     }
 
     pub async fn create_new<P: AsRef<Path>>(path: P) -> std::io::Result<File> {
-        Ok(File {
-            inner: tokio::fs::File::create_new(path).await?,
-            seek_pos: None,
-        })
+        Ok(File::new(tokio::fs::File::create_new(path).await?))
     }
 
     pub async fn metadata(&self) -> io::Result<Metadata> {
@@ -38,10 +36,7 @@ impl File {
     }
 
     pub async fn open(path: impl AsRef<Path>) -> io::Result<File> {
-        Ok(File {
-            inner: tokio::fs::File::open(path).await?,
-            seek_pos: None,
-        })
+        Ok(File::new(tokio::fs::File::open(path).await?))
     }
 
     #[must_use]
@@ -68,6 +63,220 @@ impl File {
     pub async fn set_len(&self, size: u64) -> io::Result<()> {
         self.inner.set_len(size).await
     }
+
+    /// Creates a new `File` instance that shares the same underlying file handle as the
+    /// existing `File` instance. Reads, writes, and seeks will affect both `File` instances
+    /// simultaneously.
+    ///
+    /// The clones also share advisory locks: a lock taken through one is held by, and released
+    /// through, the other. On wasm each clone has a lock of its own instead.
+    pub async fn try_clone(&self) -> io::Result<File> {
+        Ok(File::new(self.inner.try_clone().await?))
+    }
+
+    /// Converts a [`std::fs::File`] to a [`File`].
+    pub fn from_std(std: std::fs::File) -> File {
+        File::new(tokio::fs::File::from_std(std))
+    }
+
+    /// Destructures `File` into a [`std::fs::File`]. This function is async to allow any
+    /// in-flight operations to complete.
+    pub async fn into_std(self) -> std::fs::File {
+        self.inner.into_std().await
+    }
+
+    /// Tries to immediately destructure `File` into a [`std::fs::File`], handing the `File`
+    /// back if operations are still in flight.
+    #[allow(clippy::result_large_err)]
+    pub fn try_into_std(self) -> Result<std::fs::File, File> {
+        self.inner.try_into_std().map_err(File::new)
+    }
+
+    /// Returns the wrapped [`tokio::fs::File`].
+    pub fn into_inner(self) -> tokio::fs::File {
+        self.inner
+    }
+
+    /// Acquires an exclusive advisory lock on the file, waiting until no other handle holds a
+    /// lock on it.
+    ///
+    /// See [`std::fs::File::lock`]. The lock is taken on a blocking task, which keeps waiting for
+    /// it even if this future is dropped. It is released by [`File::unlock`] or when the `File`
+    /// and every clone made with [`File::try_clone`] are dropped, since clones share it.
+    pub async fn lock(&self) -> io::Result<()> {
+        self.blocking(|file| file.lock()).await
+    }
+
+    /// Acquires a shared advisory lock on the file, waiting until no other handle holds an
+    /// exclusive lock on it.
+    ///
+    /// See [`std::fs::File::lock_shared`].
+    pub async fn lock_shared(&self) -> io::Result<()> {
+        self.blocking(|file| file.lock_shared()).await
+    }
+
+    /// Tries to acquire an exclusive advisory lock without waiting, failing with
+    /// [`TryLockError::WouldBlock`] if another handle holds a lock on the file.
+    ///
+    /// See [`std::fs::File::try_lock`].
+    pub async fn try_lock(&self) -> Result<(), TryLockError> {
+        self.blocking(|file| Ok(file.try_lock()))
+            .await
+            .map_err(TryLockError::Error)?
+    }
+
+    /// Tries to acquire a shared advisory lock without waiting, failing with
+    /// [`TryLockError::WouldBlock`] if another handle holds an exclusive lock on the file.
+    ///
+    /// See [`std::fs::File::try_lock_shared`].
+    pub async fn try_lock_shared(&self) -> Result<(), TryLockError> {
+        self.blocking(|file| Ok(file.try_lock_shared()))
+            .await
+            .map_err(TryLockError::Error)?
+    }
+
+    /// Releases the advisory lock held on the file, if any.
+    ///
+    /// See [`std::fs::File::unlock`].
+    pub async fn unlock(&self) -> io::Result<()> {
+        self.blocking(|file| file.unlock()).await
+    }
+
+    /// Sets the maximum buffer size for the underlying `AsyncRead` / `AsyncWrite` operation.
+    ///
+    /// See [`tokio::fs::File::set_max_buf_size`].
+    pub fn set_max_buf_size(&mut self, max_buf_size: usize) {
+        self.inner.set_max_buf_size(max_buf_size);
+    }
+
+    /// Reads a number of bytes starting from a given offset, without moving the cursor.
+    ///
+    /// Returns the number of bytes read, which is 0 at or past the end of the file. Runs on a
+    /// blocking task; data written through the cursor must be flushed before it is visible here.
+    ///
+    /// On Windows the OS cursor may be moved, like with `std::os::windows::fs::FileExt::seek_read`.
+    pub async fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        let len = buf.len();
+        let (n, data) = self
+            .blocking(move |file| {
+                let mut data = vec![0; len];
+                let n = read_at(file, &mut data, offset)?;
+                Ok((n, data))
+            })
+            .await?;
+        buf[..n].copy_from_slice(&data[..n]);
+        Ok(n)
+    }
+
+    /// Reads the exact number of bytes required to fill `buf` starting from a given offset,
+    /// without moving the cursor.
+    ///
+    /// Fails with `UnexpectedEof` if the file ends before `buf` is filled.
+    pub async fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let len = buf.len();
+        let data = self
+            .blocking(move |file| {
+                let mut data = vec![0; len];
+                let mut filled = 0;
+                while filled < len {
+                    match read_at(file, &mut data[filled..], offset + filled as u64) {
+                        Ok(0) => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                        Ok(n) => filled += n,
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(data)
+            })
+            .await?;
+        buf.copy_from_slice(&data);
+        Ok(())
+    }
+
+    /// Writes a number of bytes starting from a given offset, without moving the cursor.
+    ///
+    /// Returns the number of bytes written. Runs on a blocking task.
+    ///
+    /// On Windows the OS cursor may be moved, like with `std::os::windows::fs::FileExt::seek_write`.
+    pub async fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        let data = buf.to_vec();
+        self.blocking(move |file| write_at(file, &data, offset))
+            .await
+    }
+
+    /// Attempts to write an entire buffer starting from a given offset, without moving the
+    /// cursor.
+    ///
+    /// Fails with `WriteZero` if the file stops accepting data.
+    pub async fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let data = buf.to_vec();
+        self.blocking(move |file| {
+            let mut written = 0;
+            while written < data.len() {
+                match write_at(file, &data[written..], offset + written as u64) {
+                    Ok(0) => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+                    Ok(n) => written += n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Ok(())
+        })
+        .await
+    }
+}
+
+impl File {
+    pub(crate) fn new(inner: tokio::fs::File) -> File {
+        File {
+            inner,
+            seek_pos: None,
+            std: OnceCell::new(),
+        }
+    }
+
+    async fn blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&std::fs::File) -> io::Result<T> + Send + 'static,
+    ) -> io::Result<T> {
+        let file = self
+            .std
+            .get_or_try_init(|| async {
+                let file = self.inner.try_clone().await?.into_std().await;
+                io::Result::Ok(Arc::new(file))
+            })
+            .await?
+            .clone();
+        spawn_blocking(move || f(&file))
+            .await
+            .map_err(io::Error::other)?
+    }
+}
+
+#[cfg(unix)]
+fn read_at(file: &std::fs::File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn read_at(file: &std::fs::File, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+#[cfg(unix)]
+fn write_at(file: &std::fs::File, buf: &[u8], offset: u64) -> io::Result<usize> {
+    std::os::unix::fs::FileExt::write_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+fn write_at(file: &std::fs::File, buf: &[u8], offset: u64) -> io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_write(file, buf, offset)
+}
+
+impl From<tokio::fs::File> for File {
+    fn from(inner: tokio::fs::File) -> Self {
+        File::new(inner)
+    }
 }
 
 impl futures::io::AsyncRead for File {
@@ -120,3 +329,49 @@ impl futures::io::AsyncSeek for File {
         Poll::Ready(res)
     }
 }
+
+// -- tokio::io --------------------------------------------------------------
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncRead for File {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        tokio::io::AsyncRead::poll_read(self.project().inner, cx, buf)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncWrite for File {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        tokio::io::AsyncWrite::poll_write(self.project().inner, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_flush(self.project().inner, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        tokio::io::AsyncWrite::poll_shutdown(self.project().inner, cx)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncSeek for File {
+    fn start_seek(self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        let this = self.project();
+        // A seek started through `futures::io::AsyncSeek` is superseded.
+        *this.seek_pos = None;
+        this.inner.start_seek(position)
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        self.project().inner.poll_complete(cx)
+    }
+}
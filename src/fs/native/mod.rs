@@ -1,7 +1,14 @@
+mod blob_reader;
 mod current_dir;
 mod file;
 mod open_options;
+pub(crate) mod storage;
+mod writable_file;
+mod write_atomic;
 
+pub use blob_reader::BlobReader;
 pub use current_dir::{current_dir, set_current_dir};
 pub use file::File;
 pub use open_options::OpenOptions;
+pub use writable_file::WritableFile;
+pub use write_atomic::write_atomic;
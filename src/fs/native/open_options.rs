@@ -1,50 +1,77 @@
 use std::{io, path::Path};
 
-use crate::fs::File;
+use crate::fs::{File, backend::OpenFlags};
 
-pub struct OpenOptions(tokio::fs::OpenOptions);
+pub struct OpenOptions {
+    inner: tokio::fs::OpenOptions,
+    flags: OpenFlags,
+}
 
 impl OpenOptions {
     pub fn new() -> OpenOptions {
-        OpenOptions(tokio::fs::OpenOptions::new())
+        OpenOptions {
+            inner: tokio::fs::OpenOptions::new(),
+            flags: OpenFlags::default(),
+        }
     }
 
     pub fn read(&mut self, read: bool) -> &mut OpenOptions {
-        self.0.read(read);
+        self.inner.read(read);
+        self.flags.read = read;
         self
     }
 
     pub fn write(&mut self, write: bool) -> &mut OpenOptions {
-        self.0.write(write);
+        self.inner.write(write);
+        self.flags.write = write;
         self
     }
 
     pub fn append(&mut self, append: bool) -> &mut OpenOptions {
-        self.0.append(append);
+        self.inner.append(append);
+        self.flags.append = append;
         self
     }
 
     pub fn truncate(&mut self, truncate: bool) -> &mut OpenOptions {
-        self.0.truncate(truncate);
+        self.inner.truncate(truncate);
+        self.flags.truncate = truncate;
         self
     }
 
     pub fn create(&mut self, create: bool) -> &mut OpenOptions {
-        self.0.create(create);
+        self.inner.create(create);
+        self.flags.create = create;
         self
     }
 
     pub fn create_new(&mut self, create_new: bool) -> &mut OpenOptions {
-        self.0.create_new(create_new);
+        self.inner.create_new(create_new);
+        self.flags.create_new = create_new;
         self
     }
 
     pub async fn open(&self, path: impl AsRef<Path>) -> io::Result<File> {
-        let inner = self.0.open(path).await?;
-        Ok(File {
-            inner,
-            seek_pos: None,
-        })
+        Ok(File::new(self.inner.open(path).await?))
+    }
+}
+
+impl OpenOptions {
+    pub(crate) fn open_flags(&self) -> OpenFlags {
+        self.flags
+    }
+}
+
+#[cfg(unix)]
+impl crate::OpenOptionsExt for OpenOptions {
+    fn mode(&mut self, mode: u32) -> &mut OpenOptions {
+        self.inner.mode(mode);
+        self
+    }
+
+    fn custom_flags(&mut self, flags: i32) -> &mut OpenOptions {
+        self.inner.custom_flags(flags);
+        self
     }
 }
 
@@ -0,0 +1,78 @@
+use std::{io, path::Path};
+
+use tokio::task::spawn_blocking;
+
+use crate::storage::StorageEstimate;
+
+/// Returns how much of the filesystem holding the current directory is used,
+/// and how much is available.
+pub async fn estimate() -> io::Result<StorageEstimate> {
+    spawn_blocking(|| {
+        let dir = std::env::current_dir()?;
+        let (total, free, available) = disk_space(&dir)?;
+        let usage = total.saturating_sub(free);
+        Ok(StorageEstimate::new(usage, usage + available))
+    })
+    .await
+    .map_err(io::Error::other)?
+}
+
+/// Native storage is never evicted, so this always returns `true`.
+pub async fn persist() -> io::Result<bool> {
+    Ok(true)
+}
+
+/// Native storage is never evicted, so this always returns `true`.
+pub async fn persisted() -> io::Result<bool> {
+    Ok(true)
+}
+
+/// Returns the total, free and available bytes of the filesystem holding
+/// `path`. Available bytes exclude those reserved for privileged users.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // The field types differ between platforms.
+fn disk_space(path: &Path) -> io::Result<(u64, u64, u64)> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: `statvfs` only writes to `stat`, and `path` is NUL-terminated.
+    let stat = unsafe {
+        let mut stat: libc::statvfs = std::mem::zeroed();
+        if libc::statvfs(path.as_ptr(), &mut stat) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stat
+    };
+    let block = stat.f_frsize as u64;
+    Ok((
+        stat.f_blocks as u64 * block,
+        stat.f_bfree as u64 * block,
+        stat.f_bavail as u64 * block,
+    ))
+}
+
+/// Returns the total, free and available bytes of the volume holding `path`.
+/// Available bytes take the caller's disk quota into account.
+#[cfg(windows)]
+fn disk_space(path: &Path) -> io::Result<(u64, u64, u64)> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetDiskFreeSpaceExW(
+            directory_name: *const u16,
+            free_bytes_available_to_caller: *mut u64,
+            total_number_of_bytes: *mut u64,
+            total_number_of_free_bytes: *mut u64,
+        ) -> i32;
+    }
+
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let (mut available, mut total, mut free) = (0, 0, 0);
+    // SAFETY: `path` is NUL-terminated and the out pointers are valid.
+    let ok = unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut available, &mut total, &mut free) };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok((total, free, available))
+}
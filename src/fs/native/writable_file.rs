@@ -0,0 +1,180 @@
+use std::{
+    fmt,
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures::io::{AsyncSeek, AsyncWrite};
+use tokio::task::spawn_blocking;
+
+use super::{
+    File,
+    write_atomic::{commit_temp, create_temp_sibling, follow_links},
+};
+
+/// A file written as a stream and only put in place once closed.
+///
+/// Data is written to a temporary file next to the destination, which replaces the destination
+/// atomically when the writer is closed with `close()` (or `shutdown()` with the `tokio-io`
+/// feature). Dropping the writer without closing it discards everything written, leaving the
+/// destination untouched.
+///
+/// Links are handled like [`write_atomic`](crate::write_atomic): a symbolic link is followed, and
+/// other hard links to the destination keep the old contents.
+pub struct WritableFile {
+    path: PathBuf,
+    tmp_path: PathBuf,
+    file: Option<File>,
+    commit: Option<Pin<Box<dyn Future<Output = io::Result<()>> + Send>>>,
+    done: bool,
+}
+
+impl WritableFile {
+    /// Starts writing a new version of the file at `path`, which is created on commit if it does
+    /// not exist.
+    pub async fn create(path: impl AsRef<Path>) -> io::Result<WritableFile> {
+        let path = path.as_ref().to_path_buf();
+        let (path, tmp_path, tmp) = spawn_blocking(move || {
+            let path = follow_links(&path)?;
+            let (tmp_path, tmp) = create_temp_sibling(&path)?;
+            io::Result::Ok((path, tmp_path, tmp))
+        })
+        .await
+        .map_err(io::Error::other)??;
+        Ok(WritableFile {
+            path,
+            tmp_path,
+            file: Some(File::from_std(tmp)),
+            commit: None,
+            done: false,
+        })
+    }
+
+    /// Truncates or extends the data written so far to `size` bytes. The cursor is not moved.
+    pub async fn set_len(&self, size: u64) -> io::Result<()> {
+        self.file()?.set_len(size).await
+    }
+
+    fn file(&self) -> io::Result<&File> {
+        self.file.as_ref().ok_or_else(closed_err)
+    }
+
+    fn file_mut(&mut self) -> io::Result<Pin<&mut File>> {
+        self.file.as_mut().map(Pin::new).ok_or_else(closed_err)
+    }
+}
+
+impl File {
+    /// Opens a [`WritableFile`] that replaces the file at `path` atomically once closed.
+    pub async fn create_streaming(path: impl AsRef<Path>) -> io::Result<WritableFile> {
+        WritableFile::create(path).await
+    }
+}
+
+fn closed_err() -> io::Error {
+    io::Error::other("the writable file is closed")
+}
+
+impl fmt::Debug for WritableFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WritableFile")
+            .field("path", &self.path)
+            .field("tmp_path", &self.tmp_path)
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for WritableFile {
+    fn drop(&mut self) {
+        if !self.done {
+            drop(self.file.take());
+            let _ = std::fs::remove_file(&self.tmp_path);
+        }
+    }
+}
+
+impl AsyncWrite for WritableFile {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.file_mut()?.poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.file.as_mut() {
+            Some(file) => Pin::new(file).poll_flush(cx),
+            None => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = &mut *self;
+        if this.done {
+            return Poll::Ready(Ok(()));
+        }
+        if this.commit.is_none() {
+            ready!(this.file_mut()?.poll_flush(cx))?;
+            let file = this.file.take().ok_or_else(closed_err)?;
+            let (tmp_path, path) = (this.tmp_path.clone(), this.path.clone());
+            this.commit = Some(Box::pin(async move {
+                let tmp = file.into_inner().into_std().await;
+                spawn_blocking(move || commit_temp(tmp, &tmp_path, &path))
+                    .await
+                    .map_err(io::Error::other)?
+            }));
+        }
+
+        let commit = this.commit.as_mut().ok_or_else(closed_err)?;
+        let res = ready!(commit.as_mut().poll(cx));
+        // A failed commit already removed the temporary file.
+        this.commit = None;
+        this.done = true;
+        Poll::Ready(res)
+    }
+}
+
+impl AsyncSeek for WritableFile {
+    fn poll_seek(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: io::SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        self.file_mut()?.poll_seek(cx, pos)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncWrite for WritableFile {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(self, cx)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncSeek for WritableFile {
+    fn start_seek(mut self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        tokio::io::AsyncSeek::start_seek(self.file_mut()?, position)
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        tokio::io::AsyncSeek::poll_complete(self.file_mut()?, cx)
+    }
+}
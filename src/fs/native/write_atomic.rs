@@ -0,0 +1,107 @@
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use tokio::task::spawn_blocking;
+
+/// Writes `contents` to a file atomically, creating it if it does not exist.
+///
+/// Unlike [`write`](crate::write), readers see either the old or the new contents, even if the
+/// process or the machine crashes halfway. The data is written to a temporary file next to `path`,
+/// synced to disk and renamed over `path`. The permissions of an existing file are kept.
+///
+/// If `path` is a symbolic link, the file it points to is replaced and the link is kept, as on
+/// wasm. Other hard links to the file are not updated: they keep the old contents, since the new
+/// data lands in a new file. On wasm, where hard links share their data, they see the new
+/// contents.
+pub async fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref().to_path_buf();
+    let contents = contents.as_ref().to_vec();
+    spawn_blocking(move || write_atomic_blocking(&path, &contents))
+        .await
+        .map_err(io::Error::other)?
+}
+
+fn write_atomic_blocking(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let path = follow_links(path)?;
+    let (tmp_path, mut tmp) = create_temp_sibling(&path)?;
+    if let Err(e) = tmp.write_all(contents) {
+        drop(tmp);
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    commit_temp(tmp, &tmp_path, &path)
+}
+
+/// Returns the path `path` leads to once every symbolic link at its end is followed, so the
+/// rename replaces the target rather than the link. The target need not exist.
+pub(super) fn follow_links(path: &Path) -> io::Result<PathBuf> {
+    // The same limit as Linux.
+    const MAX_LINK_FOLLOWS: usize = 40;
+
+    let mut path = path.to_path_buf();
+    for _ in 0..=MAX_LINK_FOLLOWS {
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                path = parent_dir(&path).join(fs::read_link(&path)?);
+            }
+            _ => return Ok(path),
+        }
+    }
+    Err(io::Error::other("too many levels of symbolic links"))
+}
+
+/// Puts the temporary file `tmp`, found at `tmp_path`, in place of `path`. The temporary file is
+/// removed if that fails.
+pub(super) fn commit_temp(tmp: fs::File, tmp_path: &Path, path: &Path) -> io::Result<()> {
+    let res = (|| {
+        if let Ok(meta) = fs::metadata(path) {
+            tmp.set_permissions(meta.permissions())?;
+        }
+        tmp.sync_all()?;
+        drop(tmp);
+        fs::rename(tmp_path, path)
+    })();
+    if res.is_err() {
+        let _ = fs::remove_file(tmp_path);
+        return res;
+    }
+
+    // The rename itself is only durable once the directory is synced.
+    #[cfg(unix)]
+    fs::File::open(parent_dir(path))?.sync_all()?;
+    Ok(())
+}
+
+pub(super) fn create_temp_sibling(path: &Path) -> io::Result<(PathBuf, fs::File)> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidFilename))?;
+    loop {
+        let mut tmp_name = std::ffi::OsString::from(".");
+        tmp_name.push(name);
+        tmp_name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tmp_path = parent_dir(path).join(tmp_name);
+        match fs::File::create_new(&tmp_path) {
+            Ok(file) => return Ok((tmp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
@@ -0,0 +1,18 @@
+/// Unix-style extensions to [`OpenOptions`](crate::OpenOptions), available on Unix and on
+/// `wasm32-unknown-unknown`.
+pub trait OpenOptionsExt {
+    /// Sets the mode bits a file is created with. Defaults to `0o666`.
+    ///
+    /// On Unix this is passed to `open(2)`, which masks it with the process umask. On wasm it is
+    /// kept in the sidecar metadata store and reported by `Permissions::mode`; a mode without
+    /// write bits makes the new file read-only, although the `File` that created it can still
+    /// write to it. Files that already exist keep their mode.
+    fn mode(&mut self, mode: u32) -> &mut Self;
+
+    /// Passes custom flags to the `flags` argument of `open(2)`. The access mode bits are
+    /// ignored, since they are set by the other options.
+    ///
+    /// OPFS has no open flags beyond the ones set by the other options, so they are ignored on
+    /// wasm.
+    fn custom_flags(&mut self, flags: i32) -> &mut Self;
+}
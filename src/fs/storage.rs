@@ -0,0 +1,86 @@
+//! How much storage is used and how much is left.
+//!
+//! On `wasm32-unknown-unknown` the figures come from `navigator.storage`, and cover everything
+//! the origin stores, not only OPFS. On native they come from the filesystem holding the
+//! current directory.
+
+use std::{io, path::Path};
+
+use cfg_if::cfg_if;
+
+use crate::{read_dir, symlink_metadata};
+
+cfg_if! {
+    if #[cfg(all(target_family = "wasm", target_os = "unknown"))] {
+        pub use super::wasm::storage::{estimate, persist, persisted};
+    } else {
+        pub use super::native::storage::{estimate, persist, persisted};
+    }
+}
+
+/// Storage usage and quota, in bytes, as returned by [`estimate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StorageEstimate {
+    usage: u64,
+    quota: u64,
+}
+
+impl StorageEstimate {
+    pub(crate) fn new(usage: u64, quota: u64) -> Self {
+        Self { usage, quota }
+    }
+
+    /// Returns the number of bytes in use.
+    pub fn usage(&self) -> u64 {
+        self.usage
+    }
+
+    /// Returns the number of bytes that can be used in total, including [`usage`](Self::usage).
+    ///
+    /// Browsers pick the quota themselves and may round it. On native this is the usage plus
+    /// the space available to unprivileged users.
+    pub fn quota(&self) -> u64 {
+        self.quota
+    }
+
+    /// Returns the number of bytes that can still be written before writes fail with
+    /// `StorageFull`.
+    pub fn available(&self) -> u64 {
+        self.quota.saturating_sub(self.usage)
+    }
+}
+
+/// Returns the total size of the files at or below `path`.
+///
+/// Symbolic links are not followed, and entries removed during the walk are skipped. Sizes are
+/// the lengths reported by [`metadata`](crate::metadata), so a file with several hard links is
+/// counted once per link.
+pub async fn disk_usage(path: impl AsRef<Path>) -> io::Result<u64> {
+    let meta = symlink_metadata(&path).await?;
+    if !meta.is_dir() {
+        return Ok(if meta.is_file() { meta.len() } else { 0 });
+    }
+
+    let mut total = 0;
+    let mut dirs = vec![path.as_ref().to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = match read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let meta = match symlink_metadata(entry.path()).await {
+                Ok(meta) => meta,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            };
+            if meta.is_dir() {
+                dirs.push(entry.path());
+            } else if meta.is_file() {
+                total += meta.len();
+            }
+        }
+    }
+    Ok(total)
+}
@@ -0,0 +1,234 @@
+use std::{
+    future::Future,
+    io::{self, SeekFrom},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures::io::{AsyncBufRead, AsyncRead, AsyncSeek};
+use js_sys::{Reflect, Uint8Array};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::ReadableStreamDefaultReader;
+
+use super::{
+    File,
+    opfs::{CreateFileMode, SyncAccessMode, lock_and_handle, opfs_err},
+};
+
+/// A read-only view of a file that streams its contents through `Blob.stream()`.
+///
+/// Unlike [`File::open`], no `SyncAccessHandle` is taken, so other workers and tabs can keep
+/// writing to the file. The reader works on a snapshot taken when it is opened; once the file is
+/// modified, further reads fail with the error the browser reports for a stale `Blob`.
+///
+/// Seeking slices the snapshot and starts a new stream, except within the chunk already read.
+#[derive(Debug)]
+pub struct BlobReader {
+    blob: web_sys::File,
+    len: u64,
+    /// Position of the next byte returned.
+    pos: u64,
+    reader: Option<ReadableStreamDefaultReader>,
+    /// Read requested from `reader` and not yet answered.
+    pending: Option<JsFuture>,
+    /// The last chunk read from `reader`, of which `consumed` bytes were returned.
+    chunk: Vec<u8>,
+    consumed: usize,
+}
+
+impl BlobReader {
+    /// Opens a snapshot of the file at `path` for reading.
+    pub async fn open(path: impl AsRef<Path>) -> io::Result<BlobReader> {
+        // Use Shared lock to wait for exclusive writers while taking the snapshot.
+        let (guard, _sync_handle, file_handle) = lock_and_handle(
+            &path,
+            Some(SyncAccessMode::Readonly),
+            CreateFileMode::NotCreate,
+        )
+        .await?;
+        let blob: web_sys::File = JsFuture::from(file_handle.get_file())
+            .await
+            .map_err(opfs_err)?
+            .unchecked_into();
+        drop(guard);
+
+        Ok(BlobReader {
+            len: blob.size() as u64,
+            blob,
+            pos: 0,
+            reader: None,
+            pending: None,
+            chunk: Vec::new(),
+            consumed: 0,
+        })
+    }
+
+    /// Returns the size of the snapshot in bytes.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if the snapshot is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.consumed == self.chunk.len() && self.pos < self.len {
+            let reader = match &self.reader {
+                Some(reader) => reader,
+                None => {
+                    let slice = self
+                        .blob
+                        .slice_with_f64(self.pos as f64)
+                        .map_err(opfs_err)?;
+                    self.reader
+                        .insert(slice.stream().get_reader().unchecked_into())
+                }
+            };
+            let pending = self
+                .pending
+                .get_or_insert_with(|| JsFuture::from(reader.read()));
+            let res = ready!(Pin::new(pending).poll(cx));
+            self.pending = None;
+
+            let res = res.map_err(opfs_err)?;
+            let done = Reflect::get(&res, &"done".into()).map_err(opfs_err)?;
+            if done.is_truthy() {
+                // The snapshot ended early, so it no longer matches `len`.
+                self.len = self.pos;
+                break;
+            }
+            let value = Reflect::get(&res, &"value".into()).map_err(opfs_err)?;
+            self.chunk = value.unchecked_into::<Uint8Array>().to_vec();
+            self.consumed = 0;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn seek_to(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        // Within the current chunk, only the read position moves.
+        let chunk_start = self.pos - self.consumed as u64;
+        if (chunk_start..=chunk_start + self.chunk.len() as u64).contains(&target) {
+            self.consumed = (target - chunk_start) as usize;
+        } else {
+            self.discard_stream();
+        }
+        self.pos = target;
+        Ok(target)
+    }
+
+    fn discard_stream(&mut self) {
+        self.pending = None;
+        self.chunk.clear();
+        self.consumed = 0;
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.cancel();
+        }
+    }
+}
+
+impl File {
+    /// Opens a [`BlobReader`] on the file at `path`, which reads a snapshot without taking a
+    /// `SyncAccessHandle`.
+    pub async fn open_streaming(path: impl AsRef<Path>) -> io::Result<BlobReader> {
+        BlobReader::open(path).await
+    }
+}
+
+impl Drop for BlobReader {
+    fn drop(&mut self) {
+        self.discard_stream();
+    }
+}
+
+impl AsyncRead for BlobReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let available = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncBufRead for BlobReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let this = self.get_mut();
+        ready!(this.poll_fill(cx))?;
+        Poll::Ready(Ok(&this.chunk[this.consumed..]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let this = self.get_mut();
+        let amt = amt.min(this.chunk.len() - this.consumed);
+        this.consumed += amt;
+        this.pos += amt as u64;
+    }
+}
+
+impl AsyncSeek for BlobReader {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        Poll::Ready(self.get_mut().seek_to(pos))
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncRead for BlobReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let available = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let n = available.len().min(buf.remaining());
+        buf.put_slice(&available[..n]);
+        AsyncBufRead::consume(self, n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncBufRead for BlobReader {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        AsyncBufRead::poll_fill_buf(self, cx)
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        AsyncBufRead::consume(self, amt)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncSeek for BlobReader {
+    // Seeking never waits, so it completes right away.
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.get_mut().seek_to(position).map(|_| ())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
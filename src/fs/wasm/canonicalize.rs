@@ -3,8 +3,8 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use super::opfs::virtualize;
-
+/// Returns the canonical, absolute form of a path with all intermediate
+/// components normalized and symbolic links resolved.
 pub async fn canonicalize(path: impl AsRef<Path>) -> io::Result<PathBuf> {
-    virtualize(path)
+    super::opfs::canonicalize(path).await
 }
@@ -1,21 +1,29 @@
 use std::{
     cell::RefCell,
     collections::VecDeque,
+    fs::TryLockError,
     future::Future,
     io::{self, SeekFrom},
     path::{Path, PathBuf},
     pin::Pin,
     task::{Context, Poll, Waker},
+    time::Duration,
 };
 
-use futures::io::{AsyncRead, AsyncSeek, AsyncWrite};
+use futures::{
+    future::poll_fn,
+    io::{AsyncBufRead, AsyncRead, AsyncSeek, AsyncWrite},
+};
 use rustc_hash::FxHashMap;
 use web_sys::{FileSystemFileHandle, FileSystemReadWriteOptions, FileSystemSyncAccessHandle};
 
 use super::{
-    OpenOptions,
+    LockMode, OpenOptions, lock_mode,
     metadata::{FileType, Metadata},
-    opfs::{SyncAccessMode, open_file, opfs_err},
+    opfs::{
+        SyncAccessMode, meta_store, open_file, opfs_err, virtualize,
+        web_lock::{self, WebLockGuard},
+    },
 };
 
 // ---------------------------------------------------------------------------
@@ -31,6 +39,9 @@ use super::{
 //   4. Close the underlying handle only when the last user drops its guard.
 // ---------------------------------------------------------------------------
 
+/// Default for [`File::set_max_buf_size`].
+pub(super) const DEFAULT_MAX_BUF_SIZE: usize = 1024 * 1024;
+
 thread_local! {
     static LOCKS: RefCell<FxHashMap<PathBuf, LockState>> = RefCell::new(FxHashMap::default());
     static NEXT_ID: RefCell<u64> = const { RefCell::new(0) };
@@ -48,6 +59,30 @@ struct LockState {
     has_exclusive: bool,
     /// Tasks waiting for the lock.
     waiters: VecDeque<Waiter>,
+    /// Number of advisory shared locks taken with `File::lock_shared`.
+    advisory_shared: usize,
+    /// Whether an advisory exclusive lock is taken with `File::lock`.
+    advisory_exclusive: bool,
+    /// Tasks waiting for an advisory lock.
+    advisory_waiters: VecDeque<Waiter>,
+    /// Web Lock taken for the path in `LockMode::WebLocks`.
+    web_lock: WebLockState,
+    /// Tasks waiting for another task to acquire the Web Lock.
+    web_lock_waiters: Vec<Waker>,
+    /// Bumped whenever the file is written through the cached handle, so
+    /// `File`s can tell when the data they read ahead is stale.
+    generation: u64,
+}
+
+#[derive(Default)]
+enum WebLockState {
+    #[default]
+    None,
+    Acquiring,
+    /// Released when the `LockState` is dropped.
+    Held {
+        _guard: WebLockGuard,
+    },
 }
 
 struct Waiter {
@@ -64,6 +99,24 @@ pub struct FileLockGuard {
     pub(super) mode: Option<SyncAccessMode>,
 }
 
+impl FileLockGuard {
+    /// Takes one more shared lock on the same path. The waiter queue is
+    /// skipped since the lock is already held, so this never waits. Returns
+    /// `None` for exclusive guards.
+    fn share(&self) -> Option<FileLockGuard> {
+        let mode = self.mode?;
+        LOCKS.with(|locks| {
+            let mut locks = locks.borrow_mut();
+            let state = locks.get_mut(&self.path)?;
+            state.shared_count += 1;
+            Some(FileLockGuard {
+                path: self.path.clone(),
+                mode: Some(mode),
+            })
+        })
+    }
+}
+
 impl Drop for FileLockGuard {
     fn drop(&mut self) {
         LOCKS.with(|locks| {
@@ -207,22 +260,291 @@ impl Future for FileLockFuture {
 /// Acquire a file lock. Returns a guard and optionally the cached `SyncAccessHandle`.
 /// - `Some(mode)`: Shared lock with the specified access mode.
 /// - `None`: Exclusive lock, blocks all other RO/RW locks.
-pub fn lock_file(path: impl AsRef<Path>, mode: Option<SyncAccessMode>) -> FileLockFuture {
-    let id = NEXT_ID.with(|next_id| {
-        let mut id = next_id.borrow_mut();
-        let current = *id;
-        *id += 1;
-        current
-    });
+///
+/// In `LockMode::WebLocks` the path is also locked for other contexts, which
+/// fails with `TimedOut` if they do not let go of it in time.
+pub async fn lock_file(
+    path: impl AsRef<Path>,
+    mode: Option<SyncAccessMode>,
+) -> io::Result<(FileLockGuard, Option<FileSystemSyncAccessHandle>)> {
+    let (guard, handle) = lock_local(path, mode).await;
+    if let LockMode::WebLocks { timeout } = lock_mode() {
+        hold_web_lock(&guard.path, timeout).await?;
+    }
+    Ok((guard, handle))
+}
 
+fn lock_local(path: impl AsRef<Path>, mode: Option<SyncAccessMode>) -> FileLockFuture {
     FileLockFuture {
         path: path.as_ref().to_path_buf(),
-        id,
+        id: next_id(),
         mode,
         registered: false,
     }
 }
 
+// -- Web Locks --------------------------------------------------------------
+//
+// In `LockMode::WebLocks` the first task to lock a path in this worker also
+// takes its Web Lock, which every later holder shares. It is released with
+// the `LockState` once the last guard is dropped. Read-only handles can be
+// open in several contexts at once, so they only take a shared Web Lock.
+
+/// Makes sure the Web Lock of `path` is held, which must be locked locally.
+async fn hold_web_lock(path: &Path, timeout: Option<Duration>) -> io::Result<()> {
+    let exclusive = poll_fn(|cx| {
+        LOCKS.with(|locks| {
+            let mut locks = locks.borrow_mut();
+            let Some(state) = locks.get_mut(path) else {
+                return Poll::Ready(None);
+            };
+            match state.web_lock {
+                WebLockState::Held { .. } => Poll::Ready(None),
+                WebLockState::Acquiring => {
+                    state.web_lock_waiters.push(cx.waker().clone());
+                    Poll::Pending
+                }
+                WebLockState::None => {
+                    state.web_lock = WebLockState::Acquiring;
+                    Poll::Ready(Some(
+                        state.has_exclusive || state.handle_mode != Some(SyncAccessMode::Readonly),
+                    ))
+                }
+            }
+        })
+    })
+    .await;
+    let Some(exclusive) = exclusive else {
+        return Ok(());
+    };
+
+    // Lets the next waiter take over if this task gives up before the lock
+    // is acquired.
+    let _reset = WebLockReset(path);
+    let (web_lock, res) = match web_lock::request_lock(&virtualize(path)?, exclusive, timeout).await
+    {
+        Ok(_guard) => (WebLockState::Held { _guard }, Ok(())),
+        Err(e) => (WebLockState::None, Err(e)),
+    };
+    LOCKS.with(|locks| {
+        if let Some(state) = locks.borrow_mut().get_mut(path) {
+            state.web_lock = web_lock;
+            for w in state.web_lock_waiters.drain(..) {
+                w.wake();
+            }
+        }
+    });
+    res
+}
+
+struct WebLockReset<'a>(&'a Path);
+
+impl Drop for WebLockReset<'_> {
+    fn drop(&mut self) {
+        LOCKS.with(|locks| {
+            if let Some(state) = locks.borrow_mut().get_mut(self.0)
+                && matches!(state.web_lock, WebLockState::Acquiring)
+            {
+                state.web_lock = WebLockState::None;
+                for w in state.web_lock_waiters.drain(..) {
+                    w.wake();
+                }
+            }
+        });
+    }
+}
+
+// -- Advisory locks ---------------------------------------------------------
+//
+// `File::lock` and friends live in the same `LockState` as the handle cache
+// but are independent of the guards above: every open `File` already holds a
+// shared guard for its handle, so advisory locks only arbitrate between
+// `File`s, like `flock` does between open file descriptions.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AdvisoryMode {
+    Shared,
+    Exclusive,
+}
+
+impl LockState {
+    fn can_lock(&self, mode: AdvisoryMode) -> bool {
+        match mode {
+            AdvisoryMode::Shared => !self.advisory_exclusive,
+            AdvisoryMode::Exclusive => self.advisory_shared == 0 && !self.advisory_exclusive,
+        }
+    }
+
+    fn take_lock(&mut self, mode: AdvisoryMode) {
+        match mode {
+            AdvisoryMode::Shared => self.advisory_shared += 1,
+            AdvisoryMode::Exclusive => self.advisory_exclusive = true,
+        }
+    }
+
+    /// Wakes the first advisory waiter so it can try to take the lock.
+    fn wake_advisory_front(&self) {
+        if let Some(w) = self.advisory_waiters.front() {
+            w.waker.wake_by_ref();
+        }
+    }
+}
+
+/// An advisory lock held by a `File`, released when dropped.
+#[derive(Debug)]
+pub(super) struct AdvisoryGuard {
+    path: PathBuf,
+    mode: AdvisoryMode,
+}
+
+impl Drop for AdvisoryGuard {
+    fn drop(&mut self) {
+        LOCKS.with(|locks| {
+            let mut locks = locks.borrow_mut();
+            let Some(state) = locks.get_mut(&self.path) else {
+                return;
+            };
+            match self.mode {
+                AdvisoryMode::Shared => state.advisory_shared -= 1,
+                AdvisoryMode::Exclusive => state.advisory_exclusive = false,
+            }
+            state.wake_advisory_front();
+        });
+    }
+}
+
+struct AdvisoryLockFuture {
+    path: PathBuf,
+    id: u64,
+    mode: AdvisoryMode,
+    /// Whether this future has inserted itself into the waiter queue.
+    registered: bool,
+}
+
+impl Drop for AdvisoryLockFuture {
+    fn drop(&mut self) {
+        if !self.registered {
+            return;
+        }
+        // Leave the queue if cancelled, letting the next waiter through.
+        LOCKS.with(|locks| {
+            let mut locks = locks.borrow_mut();
+            if let Some(state) = locks.get_mut(&self.path) {
+                state.advisory_waiters.retain(|w| w.id != self.id);
+                state.wake_advisory_front();
+            }
+        });
+    }
+}
+
+impl Future for AdvisoryLockFuture {
+    type Output = AdvisoryGuard;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        LOCKS.with(|locks| {
+            let mut locks = locks.borrow_mut();
+            let state = locks.entry(this.path.clone()).or_default();
+
+            let is_front = state
+                .advisory_waiters
+                .front()
+                .is_none_or(|w| w.id == this.id);
+
+            if state.can_lock(this.mode) && is_front {
+                state.take_lock(this.mode);
+                if this.registered {
+                    state.advisory_waiters.pop_front();
+                    this.registered = false;
+                }
+                // Shared waiters queued right behind may be able to follow.
+                state.wake_advisory_front();
+
+                Poll::Ready(AdvisoryGuard {
+                    path: this.path.clone(),
+                    mode: this.mode,
+                })
+            } else {
+                if let Some(w) = state.advisory_waiters.iter_mut().find(|w| w.id == this.id) {
+                    w.waker = cx.waker().clone();
+                } else {
+                    state.advisory_waiters.push_back(Waiter {
+                        id: this.id,
+                        waker: cx.waker().clone(),
+                    });
+                    this.registered = true;
+                }
+                Poll::Pending
+            }
+        })
+    }
+}
+
+fn lock_advisory(path: &Path, mode: AdvisoryMode) -> AdvisoryLockFuture {
+    AdvisoryLockFuture {
+        path: path.to_path_buf(),
+        id: next_id(),
+        mode,
+        registered: false,
+    }
+}
+
+/// Takes an advisory lock if that is possible without waiting. Queued waiters
+/// go first.
+fn try_lock_advisory(path: &Path, mode: AdvisoryMode) -> Option<AdvisoryGuard> {
+    LOCKS.with(|locks| {
+        let mut locks = locks.borrow_mut();
+        let state = locks.entry(path.to_path_buf()).or_default();
+        if !state.can_lock(mode) || !state.advisory_waiters.is_empty() {
+            return None;
+        }
+        state.take_lock(mode);
+        Some(AdvisoryGuard {
+            path: path.to_path_buf(),
+            mode,
+        })
+    })
+}
+
+/// Takes back an advisory lock that was just released, bypassing the queue.
+fn restore_advisory(path: &Path, mode: AdvisoryMode) -> AdvisoryGuard {
+    LOCKS.with(|locks| {
+        locks
+            .borrow_mut()
+            .entry(path.to_path_buf())
+            .or_default()
+            .take_lock(mode)
+    });
+    AdvisoryGuard {
+        path: path.to_path_buf(),
+        mode,
+    }
+}
+
+fn next_id() -> u64 {
+    NEXT_ID.with(|next_id| {
+        let mut id = next_id.borrow_mut();
+        let current = *id;
+        *id += 1;
+        current
+    })
+}
+
+/// Marks the data every `File` on `path` read ahead as stale. Called after
+/// each write or truncation through the cached handle.
+pub(crate) fn invalidate_read_ahead(path: impl AsRef<Path>) {
+    LOCKS.with(|locks| {
+        if let Some(state) = locks.borrow_mut().get_mut(path.as_ref()) {
+            state.generation += 1;
+        }
+    });
+}
+
+fn generation(path: &Path) -> u64 {
+    LOCKS.with(|locks| locks.borrow().get(path).map_or(0, |state| state.generation))
+}
+
 /// Store a newly created `SyncAccessHandle` in the cache and wake all
 /// waiters so they can share it.
 pub(crate) fn set_lock_handle(path: impl AsRef<Path>, handle: FileSystemSyncAccessHandle) {
@@ -248,13 +570,60 @@ pub(crate) fn set_lock_handle(path: impl AsRef<Path>, handle: FileSystemSyncAcce
 /// The file lock is automatically released when the `File` is dropped.
 #[derive(Debug)]
 pub struct File {
+    /// Virtualized path the file was opened with.
+    pub(super) path: PathBuf,
     pub(super) handle: FileSystemFileHandle,
     pub(super) sync_access_handle: FileSystemSyncAccessHandle,
     pub(super) pos: Option<u64>,
     pub(super) mode: SyncAccessMode,
+    pub(super) read_ahead: RefCell<ReadAhead>,
+    pub(super) write_behind: RefCell<WriteBehind>,
+    /// Size of `read_ahead` and `write_behind`, 0 if buffering is disabled.
+    pub(super) buf_capacity: usize,
+    /// Whether every write goes to the end of the file, like `O_APPEND`.
+    pub(super) append: bool,
+    /// Maximum number of bytes a single `poll_read`/`poll_write` transfers.
+    pub(super) max_buf_size: usize,
+    /// Advisory lock taken with `lock`/`lock_shared`. Declared before `_lock`
+    /// so it is released first.
+    pub(super) advisory: RefCell<Option<AdvisoryGuard>>,
     pub(super) _lock: FileLockGuard,
 }
 
+/// Bytes read ahead of the cursor. Discarded when the cursor leaves them or
+/// the file is written through any `File` sharing the handle.
+#[derive(Debug, Default)]
+pub(super) struct ReadAhead {
+    data: Vec<u8>,
+    consumed: usize,
+    /// File offset `data` was read at.
+    start: u64,
+    /// `LockState::generation` when `data` was read.
+    generation: u64,
+}
+
+impl ReadAhead {
+    /// Amount read by `AsyncBufRead` when buffering is disabled.
+    const CAPACITY: usize = 8 * 1024;
+
+    fn available(&self) -> &[u8] {
+        &self.data[self.consumed..]
+    }
+
+    fn clear(&mut self) {
+        self.data.clear();
+        self.consumed = 0;
+    }
+}
+
+/// Bytes written through the cursor and not yet passed to the handle.
+#[derive(Debug, Default)]
+pub(super) struct WriteBehind {
+    data: Vec<u8>,
+    /// Offset in the file where `data` starts.
+    offset: u64,
+}
+
 impl File {
     pub async fn create(path: impl AsRef<Path>) -> io::Result<File> {
         open_file(
@@ -286,11 +655,11 @@ impl File {
             .as_f64()
             .map(|v| v as u64);
 
-        Ok(Metadata {
-            file_type: FileType::File,
-            file_size: self.size()?,
-            mtime,
-        })
+        // Metadata is still useful without the sidecar fields, so a store
+        // that cannot be read is not an error here.
+        let _ = meta_store::load().await;
+        Ok(Metadata::new(FileType::File, self.size()?, mtime)
+            .with_record(meta_store::get_followed(&self.path).await))
     }
 
     pub async fn open(path: impl AsRef<Path>) -> io::Result<File> {
@@ -317,9 +686,8 @@ impl File {
     }
 
     pub fn size(&self) -> io::Result<u64> {
-        self.sync_access_handle
-            .get_size()
-            .map_or_else(|err| Err(opfs_err(err)), |size| Ok(size as u64))
+        self.flush_write_behind()?;
+        self.handle_size()
     }
 
     /// Truncates or extends the underlying file, updating the size of this file to become `size`.
@@ -334,12 +702,8 @@ impl File {
     /// If the requested length is greater than 9007199254740991 (max safe integer in a floating-point context),
     /// this will produce an error.
     pub async fn set_len(&self, size: u64) -> io::Result<()> {
-        if self.mode == SyncAccessMode::Readonly {
-            return Err(io::Error::new(
-                io::ErrorKind::PermissionDenied,
-                "file is opened in read-only mode",
-            ));
-        }
+        self.ensure_writable()?;
+        meta_store::ensure_writable(&self.path).await?;
 
         const MAX_SAFE_INT: u64 = js_sys::Number::MAX_SAFE_INTEGER as _;
         if size > MAX_SAFE_INT {
@@ -348,24 +712,178 @@ impl File {
                 format!("requested size {size} too large, max allowed is {MAX_SAFE_INT}"),
             ));
         }
+        self.flush_write_behind()?;
         self.sync_access_handle
             .truncate_with_f64(size as _)
-            .map_err(opfs_err)
+            .map_err(opfs_err)?;
+        invalidate_read_ahead(&self._lock.path);
+        Ok(())
+    }
+
+    /// Creates a new `File` on the same file, with its own cursor starting at the
+    /// current position of this one.
+    ///
+    /// Both share the cached `SyncAccessHandle`, which stays open until the last
+    /// `File` using it is dropped. Advisory locks are not shared: the clone starts
+    /// unlocked and contends with this `File` for [`File::lock`]. On native,
+    /// clones share one lock, as they share the open file description.
+    pub async fn try_clone(&self) -> io::Result<File> {
+        self.flush_write_behind()?;
+        let lock = self
+            ._lock
+            .share()
+            .ok_or_else(|| io::Error::other("file is not locked in shared mode"))?;
+        Ok(File {
+            path: self.path.clone(),
+            handle: self.handle.clone(),
+            sync_access_handle: self.sync_access_handle.clone(),
+            pos: self.pos,
+            mode: self.mode,
+            read_ahead: Default::default(),
+            write_behind: Default::default(),
+            buf_capacity: self.buf_capacity,
+            append: self.append,
+            max_buf_size: self.max_buf_size,
+            advisory: Default::default(),
+            _lock: lock,
+        })
+    }
+
+    /// Acquires an exclusive advisory lock on the file, waiting until no other `File` holds a
+    /// lock on it.
+    ///
+    /// The lock belongs to this `File`: other `File`s on the same path contend for it, and unlike
+    /// with `flock` on native, so do clones made with [`File::try_clone`]. A lock already held by
+    /// this `File` is replaced. It is released by [`File::unlock`] or when the `File` is dropped.
+    ///
+    /// Advisory locks do not prevent reads or writes, and they are not seen by other workers.
+    pub async fn lock(&self) -> io::Result<()> {
+        drop(self.advisory.take());
+        let guard = lock_advisory(&self._lock.path, AdvisoryMode::Exclusive).await;
+        *self.advisory.borrow_mut() = Some(guard);
+        Ok(())
+    }
+
+    /// Acquires a shared advisory lock on the file, waiting until no other `File` holds an
+    /// exclusive lock on it.
+    ///
+    /// See [`File::lock`] for how advisory locks behave.
+    pub async fn lock_shared(&self) -> io::Result<()> {
+        drop(self.advisory.take());
+        let guard = lock_advisory(&self._lock.path, AdvisoryMode::Shared).await;
+        *self.advisory.borrow_mut() = Some(guard);
+        Ok(())
+    }
+
+    /// Tries to acquire an exclusive advisory lock without waiting, failing with
+    /// [`TryLockError::WouldBlock`] if another `File` holds a lock on the file.
+    ///
+    /// A lock already held by this `File` is kept if this fails.
+    pub async fn try_lock(&self) -> Result<(), TryLockError> {
+        self.try_lock_advisory(AdvisoryMode::Exclusive)
+    }
+
+    /// Tries to acquire a shared advisory lock without waiting, failing with
+    /// [`TryLockError::WouldBlock`] if another `File` holds an exclusive lock on the file.
+    ///
+    /// A lock already held by this `File` is kept if this fails.
+    pub async fn try_lock_shared(&self) -> Result<(), TryLockError> {
+        self.try_lock_advisory(AdvisoryMode::Shared)
+    }
+
+    /// Releases the advisory lock held by this `File`, if any.
+    pub async fn unlock(&self) -> io::Result<()> {
+        drop(self.advisory.take());
+        Ok(())
+    }
+
+    /// Returns the underlying `FileSystemFileHandle`.
+    pub fn file_handle(&self) -> &FileSystemFileHandle {
+        &self.handle
+    }
+
+    /// Returns the underlying `FileSystemSyncAccessHandle`.
+    ///
+    /// The handle is shared by every `File` open on this path and must not be
+    /// closed; it is closed when the last of them is dropped. Data read or
+    /// written through it bypasses the cursor and read-ahead of this `File`.
+    pub fn sync_access_handle(&self) -> &FileSystemSyncAccessHandle {
+        &self.sync_access_handle
+    }
+
+    /// Sets the maximum number of bytes a single `poll_read` or `poll_write` transfers;
+    /// larger buffers are served by a short count. Defaults to 1 MiB.
+    pub fn set_max_buf_size(&mut self, max_buf_size: usize) {
+        self.max_buf_size = max_buf_size.max(1);
+    }
+
+    /// Buffers reads and writes smaller than `capacity` bytes, so that a run of small ones only
+    /// calls into the `SyncAccessHandle` about once per `capacity` bytes. Buffering is disabled
+    /// by default, or when `capacity` is 0.
+    ///
+    /// Reads are served from data read ahead of the cursor, which is dropped as soon as any
+    /// `File` on the path writes to it. Writes are held until the buffer is full, the cursor
+    /// moves elsewhere, or this `File` is read, flushed, resized, cloned or dropped; until then
+    /// other `File`s and [`read`](super::read) do not see them. Errors writing them on drop are
+    /// ignored, so flush the file first to see them.
+    pub fn set_buffer_capacity(&mut self, capacity: usize) {
+        self.buf_capacity = capacity;
+    }
+
+    /// Returns the capacity set with [`File::set_buffer_capacity`].
+    pub fn buffer_capacity(&self) -> usize {
+        self.buf_capacity
+    }
+
+    /// Reads a number of bytes starting from a given offset, without moving the cursor.
+    ///
+    /// Returns the number of bytes read, which is 0 at or past the end of the file.
+    pub async fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<usize> {
+        self.read_with_offset(buf, offset).map(|n| n as usize)
+    }
+
+    /// Reads the exact number of bytes required to fill `buf` starting from a given offset,
+    /// without moving the cursor.
+    ///
+    /// Fails with `UnexpectedEof` if the file ends before `buf` is filled.
+    pub async fn read_exact_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.read_with_offset(&mut buf[filled..], offset + filled as u64)? {
+                0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+                n => filled += n as usize,
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes a number of bytes starting from a given offset, without moving the cursor.
+    ///
+    /// Returns the number of bytes written.
+    pub async fn write_at(&self, buf: &[u8], offset: u64) -> io::Result<usize> {
+        self.write_with_offset(buf, offset).map(|n| n as usize)
+    }
+
+    /// Attempts to write an entire buffer starting from a given offset, without moving the
+    /// cursor.
+    ///
+    /// Fails with `WriteZero` if the file stops accepting data.
+    pub async fn write_all_at(&self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let mut written = 0;
+        while written < buf.len() {
+            match self.write_with_offset(&buf[written..], offset + written as u64)? {
+                0 => return Err(io::Error::from(io::ErrorKind::WriteZero)),
+                n => written += n as usize,
+            }
+        }
+        Ok(())
     }
 }
 
 impl File {
     pub(crate) fn read_to_buf(&mut self, buf: &mut [u8]) -> io::Result<u64> {
         match self.pos {
-            Some(pos) => {
-                let options = FileSystemReadWriteOptions::new();
-                options.set_at(pos as f64);
-                let size = self
-                    .sync_access_handle
-                    .read_with_u8_array_and_options(buf, &options)
-                    .map_err(opfs_err)? as u64;
-                Ok(size)
-            }
+            Some(pos) => self.read_with_offset(buf, pos),
             None => {
                 let size = self
                     .sync_access_handle
@@ -377,83 +895,283 @@ impl File {
     }
 
     pub(crate) fn write_with_buf(&mut self, buf: impl AsRef<[u8]>) -> io::Result<u64> {
-        if self.mode == SyncAccessMode::Readonly {
-            return Err(io::Error::new(
-                io::ErrorKind::PermissionDenied,
-                "file is opened in read-only mode",
-            ));
+        self.ensure_writable()?;
+
+        if self.append {
+            // Like `O_APPEND`, the cursor moves to the end of the file first,
+            // wherever other `File`s have extended it to.
+            let end = self.size()?;
+            self.pos = Some(end);
+            return self.write_with_offset(buf.as_ref(), end);
         }
 
         match self.pos {
-            Some(pos) => {
-                let options = FileSystemReadWriteOptions::new();
-                options.set_at(pos as f64);
-                let size = self
-                    .sync_access_handle
-                    .write_with_u8_array_and_options(buf.as_ref(), &options)
-                    .map_err(opfs_err)? as u64;
-                Ok(size)
-            }
+            Some(pos) => self.write_with_offset(buf.as_ref(), pos),
             None => {
+                self.flush_write_behind()?;
                 let size = self
                     .sync_access_handle
                     .write_with_u8_array(buf.as_ref())
                     .map_err(opfs_err)? as u64;
+                invalidate_read_ahead(&self._lock.path);
                 Ok(size)
             }
         }
     }
 
+    fn try_lock_advisory(&self, mode: AdvisoryMode) -> Result<(), TryLockError> {
+        // The lock held by this `File` is released first so it does not
+        // conflict with the new one. Nothing else runs before it is restored.
+        let previous = self.advisory.take().map(|guard| guard.mode);
+        let guard = match try_lock_advisory(&self._lock.path, mode) {
+            Some(guard) => Ok(guard),
+            None => match previous {
+                Some(previous) => Err(Some(restore_advisory(&self._lock.path, previous))),
+                None => Err(None),
+            },
+        };
+        match guard {
+            Ok(guard) => {
+                *self.advisory.borrow_mut() = Some(guard);
+                Ok(())
+            }
+            Err(previous) => {
+                *self.advisory.borrow_mut() = previous;
+                Err(TryLockError::WouldBlock)
+            }
+        }
+    }
+
+    fn read_with_offset(&self, buf: &mut [u8], offset: u64) -> io::Result<u64> {
+        self.flush_write_behind()?;
+        let options = FileSystemReadWriteOptions::new();
+        options.set_at(offset as f64);
+        let size = self
+            .sync_access_handle
+            .read_with_u8_array_and_options(buf, &options)
+            .map_err(opfs_err)? as u64;
+        Ok(size)
+    }
+
+    fn write_with_offset(&self, buf: &[u8], offset: u64) -> io::Result<u64> {
+        self.ensure_writable()?;
+        self.flush_write_behind()?;
+        self.write_to_handle(buf, offset)
+    }
+
+    fn write_to_handle(&self, buf: &[u8], offset: u64) -> io::Result<u64> {
+        let options = FileSystemReadWriteOptions::new();
+        options.set_at(offset as f64);
+        let size = self
+            .sync_access_handle
+            .write_with_u8_array_and_options(buf, &options)
+            .map_err(opfs_err)? as u64;
+        invalidate_read_ahead(&self._lock.path);
+        Ok(size)
+    }
+
+    /// Adds `buf` to the write-behind buffer at the cursor, writing out what it
+    /// held first if `buf` does not follow it or does not fit.
+    fn write_buffered(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.ensure_writable()?;
+        let pos = match self.append {
+            // Where the data will land if nothing else extends the file first.
+            true => self.handle_size()? + self.write_behind.get_mut().data.len() as u64,
+            false => self.pos.unwrap_or_default(),
+        };
+        let write_behind = self.write_behind.get_mut();
+        let end = write_behind.offset + write_behind.data.len() as u64;
+        if !write_behind.data.is_empty()
+            && (end != pos || write_behind.data.len() + buf.len() > self.buf_capacity)
+        {
+            self.flush_write_behind()?;
+        }
+
+        let write_behind = self.write_behind.get_mut();
+        if write_behind.data.is_empty() {
+            write_behind.offset = pos;
+        }
+        write_behind.data.extend_from_slice(buf);
+        // What was read ahead would hide the data just written.
+        self.read_ahead.get_mut().clear();
+        self.pos = Some(pos + buf.len() as u64);
+        Ok(buf.len())
+    }
+
+    /// Writes out the write-behind buffer. Data that could not be written is
+    /// kept, so a later flush retries it. In append mode it goes to the end of
+    /// the file as it is then.
+    fn flush_write_behind(&self) -> io::Result<()> {
+        let mut write_behind = self.write_behind.borrow_mut();
+        let mut written = 0;
+        let res = loop {
+            if written == write_behind.data.len() {
+                break Ok(());
+            }
+            let offset = match self.append {
+                true => match self.handle_size() {
+                    Ok(size) => size,
+                    Err(e) => break Err(e),
+                },
+                false => write_behind.offset + written as u64,
+            };
+            match self.write_to_handle(&write_behind.data[written..], offset) {
+                Ok(0) => break Err(io::Error::from(io::ErrorKind::WriteZero)),
+                Ok(n) => written += n as usize,
+                Err(e) => break Err(e),
+            }
+        };
+        write_behind.data.drain(..written);
+        write_behind.offset += written as u64;
+        res
+    }
+
+    fn ensure_writable(&self) -> io::Result<()> {
+        if self.mode == SyncAccessMode::Readonly {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "file is opened in read-only mode",
+            ));
+        }
+        Ok(())
+    }
+
+    fn handle_size(&self) -> io::Result<u64> {
+        self.sync_access_handle
+            .get_size()
+            .map_or_else(|err| Err(opfs_err(err)), |size| Ok(size as u64))
+    }
+
     pub(super) fn flush(&self) -> io::Result<()> {
+        self.flush_write_behind()?;
         self.sync_access_handle.flush().map_err(opfs_err)
     }
+
+    /// Returns the data read ahead, after dropping it if the file was written
+    /// since it was read.
+    fn fresh_read_ahead(&mut self) -> &mut ReadAhead {
+        let generation = generation(&self._lock.path);
+        let read_ahead = self.read_ahead.get_mut();
+        if read_ahead.generation != generation {
+            read_ahead.clear();
+            read_ahead.generation = generation;
+        }
+        read_ahead
+    }
+
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        // Written out first, so that it is read back.
+        self.flush_write_behind()?;
+        if self.fresh_read_ahead().available().is_empty() {
+            let capacity = match self.buf_capacity {
+                0 => ReadAhead::CAPACITY,
+                capacity => capacity,
+            };
+            let start = self.pos.unwrap_or_default();
+            let mut data = std::mem::take(&mut self.read_ahead.get_mut().data);
+            data.resize(capacity, 0);
+            let n = self.read_with_offset(&mut data, start)?;
+            data.truncate(n as usize);
+            let read_ahead = self.read_ahead.get_mut();
+            read_ahead.data = data;
+            read_ahead.consumed = 0;
+            read_ahead.start = start;
+        }
+        Ok(self.read_ahead.get_mut().available())
+    }
+
+    fn consume(&mut self, amt: usize) {
+        let read_ahead = self.read_ahead.get_mut();
+        let amt = amt.min(read_ahead.available().len());
+        read_ahead.consumed += amt;
+        self.pos = Some(self.pos.unwrap_or_default() + amt as u64);
+    }
+
+    fn seek_to(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let pos = match position {
+            SeekFrom::Start(offset) => offset,
+            SeekFrom::End(offset) => self
+                .size()?
+                .checked_add_signed(offset)
+                .ok_or(io::Error::from(io::ErrorKind::InvalidInput))?,
+            SeekFrom::Current(offset) => self
+                .pos
+                .unwrap_or_default()
+                .checked_add_signed(offset)
+                .ok_or(io::Error::from(io::ErrorKind::InvalidInput))?,
+        };
+        self.flush_write_behind()?;
+
+        // Data read ahead is kept if the cursor stays within it.
+        let read_ahead = self.fresh_read_ahead();
+        let start = read_ahead.start;
+        if !read_ahead.data.is_empty()
+            && (start..=start + read_ahead.data.len() as u64).contains(&pos)
+        {
+            read_ahead.consumed = (pos - start) as usize;
+        } else {
+            read_ahead.clear();
+        }
+        self.pos = Some(pos);
+        Ok(pos)
+    }
 }
 
-// NOTE: No manual Drop — closing the SyncAccessHandle is managed by
-// `FileLockGuard::drop` when the last `File` on this path is dropped.
-// Calling `.close()` here would invalidate the handle for all other
+// NOTE: Drop only writes out buffered data — closing the SyncAccessHandle is
+// managed by `FileLockGuard::drop` when the last `File` on this path is
+// dropped. Calling `.close()` here would invalidate the handle for all other
 // `File` objects sharing it.
+impl Drop for File {
+    fn drop(&mut self) {
+        let _ = self.flush_write_behind();
+    }
+}
 
 impl AsyncRead for File {
     fn poll_read(
         mut self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
+        _cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
-        const CHUNK_SIZE: usize = 1024 * 1024;
-        let n = std::cmp::min(buf.len(), CHUNK_SIZE);
+        self.flush_write_behind()?;
+        // Serve what was read ahead first, without another call into JS.
+        let has_buffered = !self.fresh_read_ahead().available().is_empty();
+        if has_buffered || buf.len() < self.buf_capacity {
+            let buffered = self.fill_buf()?;
+            let n = buffered.len().min(buf.len());
+            buf[..n].copy_from_slice(&buffered[..n]);
+            self.consume(n);
+            return Poll::Ready(Ok(n));
+        }
+
+        let n = std::cmp::min(buf.len(), self.max_buf_size);
 
         let offset = self.read_to_buf(&mut buf[..n])?;
         self.pos = Some(self.pos.unwrap_or_default() + offset);
+        // The cursor moved past what was read ahead without consuming it.
+        self.read_ahead.get_mut().clear();
 
-        if offset as usize == n && buf.len() > CHUNK_SIZE {
-            // There is more to read, yield to stay responsive.
-            cx.waker().wake_by_ref();
-            Poll::Pending
-        } else {
-            Poll::Ready(Ok(offset as usize))
-        }
+        // A short read; callers such as `read_exact` poll again for the rest.
+        Poll::Ready(Ok(offset as usize))
     }
 }
 
 impl AsyncWrite for File {
     fn poll_write(
         mut self: Pin<&mut Self>,
-        cx: &mut Context<'_>,
+        _cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<Result<usize, io::Error>> {
-        const CHUNK_SIZE: usize = 1024 * 1024;
-        let n = std::cmp::min(buf.len(), CHUNK_SIZE);
+        if buf.len() < self.buf_capacity {
+            return Poll::Ready(self.write_buffered(buf));
+        }
+
+        let n = std::cmp::min(buf.len(), self.max_buf_size);
 
         let offset = self.write_with_buf(&buf[..n])?;
         self.pos = Some(self.pos.unwrap_or_default() + offset);
 
-        if offset as usize == n && buf.len() > CHUNK_SIZE {
-            cx.waker().wake_by_ref();
-            Poll::Pending
-        } else {
-            Poll::Ready(Ok(offset as usize))
-        }
+        Poll::Ready(Ok(offset as usize))
     }
 
     fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
@@ -476,26 +1194,73 @@ impl AsyncSeek for File {
         _cx: &mut Context<'_>,
         position: SeekFrom,
     ) -> Poll<io::Result<u64>> {
-        match position {
-            SeekFrom::Start(offset) => {
-                self.pos = Some(offset);
-            }
-            SeekFrom::End(offset) => {
-                self.pos = Some(
-                    self.size()?
-                        .checked_add_signed(offset)
-                        .ok_or(io::Error::from(io::ErrorKind::InvalidInput))?,
-                );
-            }
-            SeekFrom::Current(offset) => {
-                self.pos = Some(
-                    self.pos
-                        .unwrap_or_default()
-                        .checked_add_signed(offset)
-                        .ok_or(io::Error::from(io::ErrorKind::InvalidInput))?,
-                );
-            }
-        }
+        Poll::Ready(self.seek_to(position))
+    }
+}
+
+impl AsyncBufRead for File {
+    fn poll_fill_buf(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Poll::Ready(self.get_mut().fill_buf())
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.get_mut().consume(amt);
+    }
+}
+
+// -- tokio::io --------------------------------------------------------------
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncRead for File {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n = std::task::ready!(AsyncRead::poll_read(self, cx, buf.initialize_unfilled()))?;
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncWrite for File {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(self, cx)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncSeek for File {
+    // Seeking never blocks on OPFS, so it completes right away.
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.get_mut().seek_to(position).map(|_| ())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
         Poll::Ready(Ok(self.pos.unwrap_or_default()))
     }
 }
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncBufRead for File {
+    fn poll_fill_buf(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        Poll::Ready(self.get_mut().fill_buf())
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        self.get_mut().consume(amt);
+    }
+}
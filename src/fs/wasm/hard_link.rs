@@ -0,0 +1,28 @@
+use std::{io, path::Path};
+
+use super::{
+    canonicalize,
+    file::lock_file,
+    opfs::{create_hard_link, virtualize},
+    symlink_metadata,
+};
+
+/// Creates a new hard link on the filesystem.
+///
+/// The `link` path will be a link pointing to the `original` path, which must
+/// be a file. OPFS has no native links, so the data of a linked file is kept in
+/// a shared blob: writes through any link are visible through the others, and
+/// the data is only freed once the last link is removed.
+pub async fn hard_link(original: impl AsRef<Path>, link: impl AsRef<Path>) -> io::Result<()> {
+    let original = canonicalize(original).await?;
+    if symlink_metadata(&original).await?.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "hard links to directories are not allowed",
+        ));
+    }
+    let link = virtualize(link)?;
+
+    let (_guard, _) = lock_file(&original, None).await?;
+    create_hard_link(&original, &link).await
+}
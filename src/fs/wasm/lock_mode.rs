@@ -0,0 +1,33 @@
+use std::{
+    sync::{PoisonError, RwLock},
+    time::Duration,
+};
+
+/// How file operations coordinate access to a file with other workers and tabs of the same
+/// origin.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Files are only locked within the current worker. Opening a file that another context has
+    /// open fails with `WouldBlock`.
+    #[default]
+    Local,
+    /// Files are also locked through the [Web Locks API](https://developer.mozilla.org/en-US/docs/Web/API/Web_Locks_API),
+    /// so `File::open`, `read`, `write`, `remove_file` and the like wait for other contexts to let
+    /// go of a file instead of failing.
+    ///
+    /// Waiting fails with `TimedOut` after `timeout`, or never if it is `None`. Only contexts that
+    /// use this mode take part.
+    WebLocks { timeout: Option<Duration> },
+}
+
+static LOCK_MODE: RwLock<LockMode> = RwLock::new(LockMode::Local);
+
+/// Returns the current [`LockMode`].
+pub fn lock_mode() -> LockMode {
+    *LOCK_MODE.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Sets the [`LockMode`] used by file operations started from now on.
+pub fn set_lock_mode(mode: LockMode) {
+    *LOCK_MODE.write().unwrap_or_else(PoisonError::into_inner) = mode;
+}
@@ -6,15 +6,20 @@ use std::{
 
 use web_sys::FileSystemHandleKind;
 
-use super::opfs::{open_dir, opfs_err, resolve_file_handle};
+use super::{
+    Permissions,
+    opfs::{
+        meta_store::{self, MetaRecord},
+        open_dir, opfs_err, resolve_file_handle, virtualize,
+    },
+};
 
-/// Symlink is not supported.
+/// Symlinks are emulated, see [`symlink`](crate::symlink).
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum FileType {
     #[default]
     File,
     Directory,
-    // TODO:
     Symlink,
 }
 
@@ -54,6 +59,10 @@ pub struct Metadata {
     pub(crate) file_size: u64,
     // Modification time in milliseconds since epoch, if available.
     pub(crate) mtime: Option<u64>,
+    // Creation time in milliseconds since epoch, if available.
+    pub(crate) created: Option<u64>,
+    pub(crate) readonly: bool,
+    pub(crate) mode: Option<u32>,
 }
 
 impl Metadata {
@@ -62,11 +71,28 @@ impl Metadata {
             file_type,
             file_size,
             mtime,
+            ..Default::default()
+        }
+    }
+
+    /// Fills in what the sidecar metadata store knows about the entry.
+    pub(crate) fn with_record(mut self, record: Option<MetaRecord>) -> Self {
+        if let Some(record) = record {
+            // Files report the `lastModified` of OPFS, directories only have the record.
+            self.mtime = self.mtime.or(record.modified);
+            self.created = record.created;
+            self.readonly = record.readonly;
+            self.mode = record.mode;
         }
+        self
     }
 }
 
 impl Metadata {
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
     pub fn is_dir(&self) -> bool {
         self.file_type.is_dir()
     }
@@ -84,6 +110,10 @@ impl Metadata {
         self.file_size
     }
 
+    pub fn permissions(&self) -> Permissions {
+        Permissions::from_record(self.readonly, self.mode)
+    }
+
     // Implement analogous to std::fs::Metadata::modified
     // Returns the modification time as a SystemTime if available.
     pub fn modified(&self) -> io::Result<SystemTime> {
@@ -92,10 +122,29 @@ impl Metadata {
             None => Err(io::Error::other("mtime not available")),
         }
     }
+
+    /// OPFS does not track access times, so this is the modification time.
+    pub fn accessed(&self) -> io::Result<SystemTime> {
+        self.modified()
+    }
+
+    /// Only available for entries created through this crate.
+    pub fn created(&self) -> io::Result<SystemTime> {
+        match self.created {
+            Some(ms) => Ok(SystemTime::UNIX_EPOCH + Duration::from_millis(ms)),
+            None => Err(io::Error::other("creation time not available")),
+        }
+    }
 }
 
 pub async fn metadata(path: impl AsRef<Path>) -> io::Result<Metadata> {
-    match resolve_file_handle(&path, super::opfs::CreateFileMode::NotCreate).await {
+    let virt = virtualize(&path)?;
+    // Metadata is still useful without the sidecar fields, so a store that
+    // cannot be read is not an error here.
+    let _ = meta_store::load().await;
+    let record = meta_store::get_followed(&virt).await;
+
+    match resolve_file_handle(&virt, super::opfs::CreateFileMode::NotCreate).await {
         Ok(handle) => {
             let file_val = wasm_bindgen_futures::JsFuture::from(handle.get_file())
                 .await
@@ -111,18 +160,10 @@ pub async fn metadata(path: impl AsRef<Path>) -> io::Result<Metadata> {
                 .as_f64()
                 .map(|v| v as u64);
 
-            Ok(Metadata {
-                file_type: FileType::File,
-                file_size: size,
-                mtime,
-            })
+            Ok(Metadata::new(FileType::File, size, mtime).with_record(record))
         }
-        Err(_) => open_dir(path, super::opfs::OpenDirType::NotCreate)
+        Err(_) => open_dir(&virt, super::opfs::OpenDirType::NotCreate)
             .await
-            .map(|_| Metadata {
-                file_type: FileType::Directory,
-                file_size: 0,
-                mtime: None,
-            }),
+            .map(|_| Metadata::new(FileType::Directory, 0, None).with_record(record)),
     }
 }
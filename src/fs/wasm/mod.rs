@@ -1,3 +1,4 @@
+mod blob_reader;
 mod canonicalize;
 mod copy;
 mod create_dir;
@@ -5,9 +6,12 @@ mod create_dir_all;
 mod current_dir;
 mod dir_builder;
 mod file;
+mod hard_link;
+mod lock_mode;
 mod metadata;
 mod open_options;
 pub(crate) mod opfs;
+mod permissions;
 mod read;
 mod read_dir;
 mod read_dir_stream;
@@ -17,11 +21,17 @@ mod remove_dir;
 mod remove_dir_all;
 mod remove_file;
 mod rename;
+mod retry_policy;
+mod set_permissions;
+pub(crate) mod storage;
 mod symlink;
 mod symlink_metadata;
 mod try_exists;
+mod writable_file;
 mod write;
+mod write_atomic;
 
+pub use blob_reader::BlobReader;
 pub use canonicalize::canonicalize;
 pub use copy::copy;
 pub use create_dir::create_dir;
@@ -29,8 +39,11 @@ pub use create_dir_all::create_dir_all;
 pub use current_dir::{current_dir, set_current_dir};
 pub use dir_builder::DirBuilder;
 pub use file::File;
+pub use hard_link::hard_link;
+pub use lock_mode::{LockMode, lock_mode, set_lock_mode};
 pub use metadata::{FileType, Metadata, metadata};
 pub use open_options::OpenOptions;
+pub use permissions::Permissions;
 pub use read::read;
 pub use read_dir::{DirEntry, ReadDir, read_dir};
 pub use read_dir_stream::ReadDirStream;
@@ -40,10 +53,14 @@ pub use remove_dir::remove_dir;
 pub use remove_dir_all::remove_dir_all;
 pub use remove_file::remove_file;
 pub use rename::rename;
+pub use retry_policy::{RetryPolicy, retry_policy, set_retry_policy};
+pub use set_permissions::set_permissions;
 pub use symlink::symlink;
 pub use symlink_metadata::symlink_metadata;
 pub use try_exists::try_exists;
+pub use writable_file::WritableFile;
 pub use write::write;
+pub use write_atomic::write_atomic;
 
 #[cfg(feature = "opfs_offload")]
 pub mod offload;
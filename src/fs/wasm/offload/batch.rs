@@ -0,0 +1,301 @@
+use std::{
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+use super::{Client, FsOffload, FsTask};
+
+/// Operations sent together to the [`Server`](super::Server) in one message, built by
+/// [`Client::batch`].
+///
+/// The server runs them in order and returns one result per operation, in the same order.
+pub struct Batch<'a> {
+    client: &'a Client,
+    ops: Vec<BatchOp>,
+    on_error: OnError,
+}
+
+/// An operation of a [`Batch`].
+#[derive(Debug)]
+pub enum BatchOp {
+    Read { path: PathBuf },
+    Write { path: PathBuf, content: Vec<u8> },
+    Copy { from: PathBuf, to: PathBuf },
+    Rename { from: PathBuf, to: PathBuf },
+    CreateDir { path: PathBuf },
+    CreateDirAll { path: PathBuf },
+    RemoveFile { path: PathBuf },
+    RemoveDir { path: PathBuf },
+    RemoveDirAll { path: PathBuf },
+}
+
+/// The output of a [`BatchOp`] that succeeded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BatchOutput {
+    /// The operation has no output.
+    Done,
+    /// The bytes copied by [`BatchOp::Copy`].
+    Copied(u64),
+    /// The contents read by [`BatchOp::Read`].
+    Read(Vec<u8>),
+}
+
+/// What a [`Batch`] does when an operation fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Runs the remaining operations.
+    #[default]
+    Continue,
+    /// Skips the remaining operations, which fail with `ErrorKind::Other`.
+    Stop,
+    /// Skips the remaining operations like `Stop`, then undoes the operations that succeeded, in
+    /// reverse order: files and directories they created are removed and renames are reverted.
+    ///
+    /// Removals and overwritten contents are not restored, and directories are only removed if
+    /// they are empty again. Undoing is best effort: its errors are ignored.
+    Rollback,
+}
+
+impl<'a> Batch<'a> {
+    pub(super) fn new(client: &'a Client) -> Batch<'a> {
+        Batch {
+            client,
+            ops: Vec::new(),
+            on_error: OnError::default(),
+        }
+    }
+
+    /// Sets what happens when an operation fails. Defaults to [`OnError::Continue`].
+    pub fn on_error(&mut self, on_error: OnError) -> &mut Batch<'a> {
+        self.on_error = on_error;
+        self
+    }
+
+    pub fn push(&mut self, op: BatchOp) -> &mut Batch<'a> {
+        self.ops.push(op);
+        self
+    }
+
+    pub fn read(&mut self, path: impl AsRef<Path>) -> &mut Batch<'a> {
+        let path = path.as_ref().into();
+        self.push(BatchOp::Read { path })
+    }
+
+    pub fn write(&mut self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> &mut Batch<'a> {
+        let path = path.as_ref().into();
+        let content = content.as_ref().to_vec();
+        self.push(BatchOp::Write { path, content })
+    }
+
+    pub fn copy(&mut self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> &mut Batch<'a> {
+        let from = from.as_ref().into();
+        let to = to.as_ref().into();
+        self.push(BatchOp::Copy { from, to })
+    }
+
+    pub fn rename(&mut self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> &mut Batch<'a> {
+        let from = from.as_ref().into();
+        let to = to.as_ref().into();
+        self.push(BatchOp::Rename { from, to })
+    }
+
+    pub fn create_dir(&mut self, path: impl AsRef<Path>) -> &mut Batch<'a> {
+        let path = path.as_ref().into();
+        self.push(BatchOp::CreateDir { path })
+    }
+
+    pub fn create_dir_all(&mut self, path: impl AsRef<Path>) -> &mut Batch<'a> {
+        let path = path.as_ref().into();
+        self.push(BatchOp::CreateDirAll { path })
+    }
+
+    pub fn remove_file(&mut self, path: impl AsRef<Path>) -> &mut Batch<'a> {
+        let path = path.as_ref().into();
+        self.push(BatchOp::RemoveFile { path })
+    }
+
+    pub fn remove_dir(&mut self, path: impl AsRef<Path>) -> &mut Batch<'a> {
+        let path = path.as_ref().into();
+        self.push(BatchOp::RemoveDir { path })
+    }
+
+    pub fn remove_dir_all(&mut self, path: impl AsRef<Path>) -> &mut Batch<'a> {
+        let path = path.as_ref().into();
+        self.push(BatchOp::RemoveDirAll { path })
+    }
+
+    /// Returns the number of operations in the batch.
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Returns `true` if the batch has no operations.
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Sends the batch and waits for the result of every operation. Only sending the batch can
+    /// fail as a whole.
+    pub async fn send(self) -> io::Result<Vec<io::Result<BatchOutput>>> {
+        let Batch {
+            client,
+            ops,
+            on_error,
+        } = self;
+        client
+            .dispatch(|sender| FsTask::Batch {
+                ops,
+                on_error,
+                sender,
+            })
+            .await
+    }
+}
+
+impl fmt::Debug for Batch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Batch")
+            .field("ops", &self.ops)
+            .field("on_error", &self.on_error)
+            .finish_non_exhaustive()
+    }
+}
+
+impl BatchOp {
+    pub(super) fn paths(&self) -> Vec<&Path> {
+        match self {
+            BatchOp::Read { path }
+            | BatchOp::Write { path, .. }
+            | BatchOp::CreateDir { path }
+            | BatchOp::CreateDirAll { path }
+            | BatchOp::RemoveFile { path }
+            | BatchOp::RemoveDir { path }
+            | BatchOp::RemoveDirAll { path } => vec![path],
+            BatchOp::Copy { from, to } | BatchOp::Rename { from, to } => vec![from, to],
+        }
+    }
+}
+
+/// A change made by a batch operation, undone by [`OnError::Rollback`].
+enum Undo {
+    RemoveFile(PathBuf),
+    RemoveDir(PathBuf),
+    Rename { from: PathBuf, to: PathBuf },
+}
+
+/// Runs `ops` in order with `offload`, the default for [`FsOffload::batch`].
+pub(super) async fn run_batch<O: FsOffload + ?Sized>(
+    offload: &O,
+    ops: Vec<BatchOp>,
+    on_error: OnError,
+) -> Vec<io::Result<BatchOutput>> {
+    let total = ops.len();
+    let mut results = Vec::with_capacity(total);
+    let mut undo = Vec::new();
+    let track = on_error == OnError::Rollback;
+
+    for op in ops {
+        let res = if track {
+            run_tracked_op(offload, op, &mut undo).await
+        } else {
+            run_op(offload, op).await
+        };
+        let failed = res.is_err();
+        results.push(res);
+        if failed && on_error != OnError::Continue {
+            break;
+        }
+    }
+
+    if track && results.iter().any(Result::is_err) {
+        for step in undo.into_iter().rev() {
+            let _ = match step {
+                Undo::RemoveFile(path) => offload.remove_file(path).await,
+                Undo::RemoveDir(path) => offload.remove_dir(path).await,
+                Undo::Rename { from, to } => offload.rename(from, to).await,
+            };
+        }
+    }
+
+    results.resize_with(total, || {
+        Err(io::Error::other(
+            "skipped after an earlier operation in the batch failed",
+        ))
+    });
+    results
+}
+
+async fn run_op<O: FsOffload + ?Sized>(offload: &O, op: BatchOp) -> io::Result<BatchOutput> {
+    match op {
+        BatchOp::Read { path } => offload.read(path).await.map(BatchOutput::Read),
+        BatchOp::Write { path, content } => offload
+            .write(path, content)
+            .await
+            .map(|_| BatchOutput::Done),
+        BatchOp::Copy { from, to } => offload.copy(from, to).await.map(BatchOutput::Copied),
+        BatchOp::Rename { from, to } => offload.rename(from, to).await.map(|_| BatchOutput::Done),
+        BatchOp::CreateDir { path } => offload.create_dir(path).await.map(|_| BatchOutput::Done),
+        BatchOp::CreateDirAll { path } => offload
+            .create_dir_all(path)
+            .await
+            .map(|_| BatchOutput::Done),
+        BatchOp::RemoveFile { path } => offload.remove_file(path).await.map(|_| BatchOutput::Done),
+        BatchOp::RemoveDir { path } => offload.remove_dir(path).await.map(|_| BatchOutput::Done),
+        BatchOp::RemoveDirAll { path } => offload
+            .remove_dir_all(path)
+            .await
+            .map(|_| BatchOutput::Done),
+    }
+}
+
+/// Runs `op` like [`run_op`], recording how to undo it in `undo`.
+async fn run_tracked_op<O: FsOffload + ?Sized>(
+    offload: &O,
+    op: BatchOp,
+    undo: &mut Vec<Undo>,
+) -> io::Result<BatchOutput> {
+    match op {
+        BatchOp::Write { path, content } => {
+            let created = !offload.try_exists(&path).await?;
+            offload.write(&path, content).await?;
+            if created {
+                undo.push(Undo::RemoveFile(path));
+            }
+            Ok(BatchOutput::Done)
+        }
+        BatchOp::Copy { from, to } => {
+            let created = !offload.try_exists(&to).await?;
+            let copied = offload.copy(from, &to).await?;
+            if created {
+                undo.push(Undo::RemoveFile(to));
+            }
+            Ok(BatchOutput::Copied(copied))
+        }
+        BatchOp::Rename { from, to } => {
+            offload.rename(&from, &to).await?;
+            undo.push(Undo::Rename { from: to, to: from });
+            Ok(BatchOutput::Done)
+        }
+        BatchOp::CreateDir { path } => {
+            offload.create_dir(&path).await?;
+            undo.push(Undo::RemoveDir(path));
+            Ok(BatchOutput::Done)
+        }
+        BatchOp::CreateDirAll { path } => {
+            // The ancestors that do not exist yet, deepest first.
+            let mut missing = Vec::new();
+            let mut dir = Some(path.as_path());
+            while let Some(d) = dir {
+                if offload.try_exists(d).await? {
+                    break;
+                }
+                missing.push(d.to_path_buf());
+                dir = d.parent();
+            }
+            offload.create_dir_all(&path).await?;
+            undo.extend(missing.into_iter().rev().map(Undo::RemoveDir));
+            Ok(BatchOutput::Done)
+        }
+        op => run_op(offload, op).await,
+    }
+}
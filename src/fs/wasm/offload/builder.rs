@@ -0,0 +1,78 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::sync::{Semaphore, mpsc};
+
+use super::{Client, Server, SharedRing};
+
+/// Configures an offload [`Server`] and [`Client`] pair.
+///
+/// By default the queue is unbounded, the server runs every task it receives at once and
+/// requests have no deadline, as with [`split`](super::split).
+#[derive(Debug, Clone, Default)]
+pub struct Builder {
+    queue_depth: Option<usize>,
+    max_in_flight: Option<usize>,
+    timeout: Option<Duration>,
+    ring: Option<SharedRing>,
+}
+
+impl Builder {
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Bounds the requests sent by clients that the server has not started yet, including
+    /// operations on [`RemoteFile`](super::RemoteFile)s. Once `depth` are queued, client calls
+    /// wait for the server to take one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `depth` is zero.
+    pub fn queue_depth(&mut self, depth: usize) -> &mut Builder {
+        assert!(depth > 0, "queue depth must be non-zero");
+        self.queue_depth = Some(depth);
+        self
+    }
+
+    /// Bounds the tasks the server runs at once. Further requests stay queued until one is done.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max` is zero.
+    pub fn max_in_flight(&mut self, max: usize) -> &mut Builder {
+        assert!(max > 0, "max in-flight tasks must be non-zero");
+        self.max_in_flight = Some(max);
+        self
+    }
+
+    /// Sets how long a client call waits, counted from the call, so waiting for room in the queue
+    /// or for the server to take the request counts too. Past it, the call fails with `TimedOut`
+    /// and the server abandons the task.
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Builder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Shares `ring` between the pair, for [`Client::read_shared`].
+    pub fn shared_ring(&mut self, ring: SharedRing) -> &mut Builder {
+        self.ring = Some(ring);
+        self
+    }
+
+    pub fn build(&self) -> (Server, Client) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let server = Server {
+            receiver,
+            max_in_flight: self.max_in_flight.unwrap_or(usize::MAX),
+        };
+        let client = Client {
+            sender,
+            ring: self.ring.clone(),
+            queue: self
+                .queue_depth
+                .map(|depth| Arc::new(Semaphore::new(depth))),
+            timeout: self.timeout,
+        };
+        (server, client)
+    }
+}
@@ -1,14 +1,35 @@
-use std::{io, path::Path};
+use std::{
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+    pin::pin,
+    sync::Arc,
+    time::Duration,
+};
 
-use tokio::sync::{mpsc, oneshot};
+use futures::{
+    Stream, StreamExt,
+    future::{Either, join, select},
+};
+use tokio::sync::{Semaphore, mpsc, oneshot};
+use wasm_bindgen::{JsCast, JsValue, closure::Closure};
+use web_sys::DedicatedWorkerGlobalScope;
 
 #[cfg(feature = "opfs_watch")]
 use super::super::opfs::watch::event;
-use super::{FsTask, Metadata, ReadDir};
+use super::{
+    super::ReadDir, Batch, FileTask, FsTask, Metadata, OpenOptions, Permissions, RemoteFile,
+    SharedBuf, SharedReader, SharedRing, SharedSink, SharedSource, task::Request,
+};
 
 #[derive(Clone)]
 pub struct Client {
-    pub(super) sender: mpsc::UnboundedSender<FsTask>,
+    pub(super) sender: mpsc::UnboundedSender<Request>,
+    /// Set by [`Builder::shared_ring`](super::Builder::shared_ring).
+    pub(super) ring: Option<SharedRing>,
+    /// One permit per request the queue has room for.
+    pub(super) queue: Option<Arc<Semaphore>>,
+    pub(super) timeout: Option<Duration>,
 }
 
 impl Client {
@@ -28,10 +49,19 @@ impl Client {
         .await
     }
 
+    pub async fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<u64> {
+        let from = from.as_ref().into();
+        let to = to.as_ref().into();
+        self.dispatch(|sender| FsTask::Copy { from, to, sender })
+            .await
+    }
+
     pub async fn read_dir(&self, path: impl AsRef<Path>) -> io::Result<ReadDir> {
         let path = path.as_ref().into();
-        self.dispatch(|sender| FsTask::ReadDir { path, sender })
-            .await
+        let entries = self
+            .dispatch(|sender| FsTask::ReadDir { path, sender })
+            .await?;
+        Ok(entries.into_iter().collect())
     }
 
     pub async fn create_dir(&self, path: impl AsRef<Path>) -> io::Result<()> {
@@ -70,6 +100,191 @@ impl Client {
             .await
     }
 
+    pub async fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        let from = from.as_ref().into();
+        let to = to.as_ref().into();
+        self.dispatch(|sender| FsTask::Rename { from, to, sender })
+            .await
+    }
+
+    pub async fn try_exists(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        let path = path.as_ref().into();
+        self.dispatch(|sender| FsTask::TryExists { path, sender })
+            .await
+    }
+
+    pub async fn canonicalize(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = path.as_ref().into();
+        self.dispatch(|sender| FsTask::Canonicalize { path, sender })
+            .await
+    }
+
+    pub async fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        let path = path.as_ref().into();
+        self.dispatch(|sender| FsTask::ReadToString { path, sender })
+            .await
+    }
+
+    pub async fn symlink_metadata(&self, path: impl AsRef<Path>) -> io::Result<Metadata> {
+        let path = path.as_ref().into();
+        self.dispatch(|sender| FsTask::SymlinkMetadata { path, sender })
+            .await
+    }
+
+    pub async fn hard_link(
+        &self,
+        original: impl AsRef<Path>,
+        link: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let original = original.as_ref().into();
+        let link = link.as_ref().into();
+        self.dispatch(|sender| FsTask::HardLink {
+            original,
+            link,
+            sender,
+        })
+        .await
+    }
+
+    pub async fn symlink(
+        &self,
+        original: impl AsRef<Path>,
+        link: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        let original = original.as_ref().into();
+        let link = link.as_ref().into();
+        self.dispatch(|sender| FsTask::Symlink {
+            original,
+            link,
+            sender,
+        })
+        .await
+    }
+
+    pub async fn read_link(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        let path = path.as_ref().into();
+        self.dispatch(|sender| FsTask::ReadLink { path, sender })
+            .await
+    }
+
+    pub async fn set_permissions(
+        &self,
+        path: impl AsRef<Path>,
+        perm: Permissions,
+    ) -> io::Result<()> {
+        let path = path.as_ref().into();
+        self.dispatch(|sender| FsTask::SetPermissions { path, perm, sender })
+            .await
+    }
+
+    pub async fn write_atomic(
+        &self,
+        path: impl AsRef<Path>,
+        content: impl AsRef<[u8]>,
+    ) -> io::Result<()> {
+        let path = path.as_ref().into();
+        let content = content.as_ref().to_vec();
+        self.dispatch(|sender| FsTask::WriteAtomic {
+            path,
+            content,
+            sender,
+        })
+        .await
+    }
+
+    /// Reads the file at `path` into slabs of the client's [`SharedRing`], without copying it
+    /// again to send it back. The file comes in chunks of one slab, so it can be larger than the
+    /// ring: the server waits while the ring is full. The request is sent once the reader is
+    /// first polled.
+    ///
+    /// Fails with `Unsupported` if the client was not given a ring by
+    /// [`split_shared`](super::split_shared) or [`Builder::shared_ring`](super::Builder::shared_ring).
+    pub fn read_shared(&self, path: impl AsRef<Path>) -> io::Result<SharedReader<'_>> {
+        let ring = self.ring.clone().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the client has no shared ring, create it with `split_shared`",
+            )
+        })?;
+        let path = path.as_ref().into();
+        let (sink, chunks) = mpsc::unbounded_channel();
+        let request = self.dispatch(|sender| FsTask::ReadShared {
+            path,
+            ring,
+            sink: SharedSink(sink),
+            sender,
+        });
+        Ok(SharedReader::new(chunks, Box::pin(request)))
+    }
+
+    /// Writes the chunks of `content` to the file at `path` in order, replacing its contents. The
+    /// slabs are written from in place, and each chunk is released once written, so the file can
+    /// be larger than the ring when the chunks are allocated as the stream is polled.
+    pub async fn write_shared(
+        &self,
+        path: impl AsRef<Path>,
+        content: impl Stream<Item = SharedBuf>,
+    ) -> io::Result<()> {
+        let path = path.as_ref().into();
+        let (chunks, source) = mpsc::unbounded_channel();
+        let write = self.dispatch(|sender| FsTask::WriteShared {
+            path,
+            source: SharedSource(source),
+            sender,
+        });
+        let send = async move {
+            let mut content = pin!(content);
+            while let Some(chunk) = content.next().await {
+                if chunks.send(Some(chunk)).is_err() {
+                    // The write failed, which `write` returns.
+                    return;
+                }
+            }
+            let _ = chunks.send(None);
+        };
+        join(write, send).await.0
+    }
+
+    /// Starts a [`Batch`] of operations, sent to the server in one message.
+    pub fn batch(&self) -> Batch<'_> {
+        Batch::new(self)
+    }
+
+    /// Opens a file in read-only mode, like [`File::open`](super::super::File::open).
+    pub async fn open(&self, path: impl AsRef<Path>) -> io::Result<RemoteFile> {
+        self.open_with(path, OpenOptions::new().read(true)).await
+    }
+
+    /// Opens a file in write-only mode, like [`File::create`](super::super::File::create).
+    pub async fn create(&self, path: impl AsRef<Path>) -> io::Result<RemoteFile> {
+        self.open_with(
+            path,
+            OpenOptions::new().write(true).create(true).truncate(true),
+        )
+        .await
+    }
+
+    /// Opens a file with `options` on the `Server`, which keeps it until the returned
+    /// [`RemoteFile`] is dropped.
+    pub async fn open_with(
+        &self,
+        path: impl AsRef<Path>,
+        options: &OpenOptions,
+    ) -> io::Result<RemoteFile> {
+        let path = path.as_ref().into();
+        let options = *options;
+        let id = self
+            .dispatch(|sender| {
+                FsTask::File(FileTask::Open {
+                    path,
+                    options,
+                    sender,
+                })
+            })
+            .await?;
+        Ok(RemoteFile::new(self.sender.clone(), self.queue.clone(), id))
+    }
+
     #[cfg(feature = "opfs_watch")]
     pub async fn watch_dir(
         &self,
@@ -87,20 +302,79 @@ impl Client {
         .await
     }
 
-    async fn dispatch<T, F>(&self, create_task: F) -> io::Result<T>
+    /// Sends a task and waits for its result, failing with `TimedOut` past the timeout. Dropping
+    /// the returned future makes the `Server` abandon the task.
+    pub(super) async fn dispatch<T, F>(&self, create_task: F) -> io::Result<T>
     where
         F: FnOnce(oneshot::Sender<io::Result<T>>) -> FsTask,
     {
+        let Some(timeout) = self.timeout else {
+            return self.send(create_task, None).await;
+        };
+        // Counted from the call, so waiting for room in the queue counts too.
+        let deadline = js_sys::Date::now() + timeout.as_secs_f64() * 1000.0;
+        // The server abandons a task it runs past the deadline, but a request still waiting in
+        // the queue or in the channel is only timed out here.
+        let timer = pin!(timer(timeout));
+        match select(pin!(self.send(create_task, Some(deadline))), timer).await {
+            Either::Left((res, _)) => res,
+            Either::Right(_) => Err(io::Error::from(io::ErrorKind::TimedOut)),
+        }
+    }
+
+    async fn send<T, F>(&self, create_task: F, deadline: Option<f64>) -> io::Result<T>
+    where
+        F: FnOnce(oneshot::Sender<io::Result<T>>) -> FsTask,
+    {
+        let permit = match &self.queue {
+            Some(queue) => Some(
+                queue
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|_| io::Error::from(io::ErrorKind::ConnectionAborted))?,
+            ),
+            None => None,
+        };
         let (sender, receiver) = oneshot::channel();
+        // Dropped with this future, which cancels the task.
+        let (_cancel, cancelled) = oneshot::channel();
 
-        let task = create_task(sender);
+        let request = Request {
+            task: create_task(sender),
+            permit,
+            deadline,
+            cancelled: Some(cancelled),
+        };
 
         self.sender
-            .send(task)
+            .send(request)
             .map_err(|_| io::Error::from(io::ErrorKind::ConnectionAborted))?;
 
-        receiver
-            .await
-            .map_err(|_| io::Error::from(io::ErrorKind::ConnectionAborted))?
+        receiver.await.map_err(|_| match deadline {
+            // The server drops the task once the deadline passes.
+            Some(deadline) if js_sys::Date::now() >= deadline => {
+                io::Error::from(io::ErrorKind::TimedOut)
+            }
+            _ => io::Error::from(io::ErrorKind::ConnectionAborted),
+        })?
+    }
+}
+
+/// Resolves after `duration`. Unlike `sleep`, the returned future is `Send`: the timer runs on
+/// the current thread and fires through a channel.
+fn timer(duration: Duration) -> impl Future<Output = ()> + Send {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    let callback = Closure::once_into_js(move || {
+        let _ = sender.send(());
+    });
+    let global = DedicatedWorkerGlobalScope::from(JsValue::from(js_sys::global()));
+    // Without a timer, the call waits for the server.
+    let _ = global.set_timeout_with_callback_and_timeout_and_arguments_0(
+        callback.unchecked_ref(),
+        duration.as_millis().min(i32::MAX as u128) as i32,
+    );
+    async move {
+        let _ = receiver.await;
     }
 }
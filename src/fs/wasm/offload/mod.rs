@@ -1,30 +1,58 @@
-use std::{io, path::Path};
+use std::{
+    io::{self, SeekFrom},
+    path::{Path, PathBuf},
+};
 
-use tokio::sync::mpsc;
+use futures::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 
 #[cfg(feature = "opfs_watch")]
 use super::opfs::watch::{event, watch_dir};
 use super::{
-    Metadata, ReadDir, copy, create_dir, create_dir_all, metadata, read, read_dir, remove_dir,
-    remove_dir_all, remove_file, write,
+    DirEntry, File, Metadata, OpenOptions, Permissions, ReadDir, canonicalize, copy, create_dir,
+    create_dir_all, hard_link, metadata, read, read_dir, read_link, read_to_string, remove_dir,
+    remove_dir_all, remove_file, rename, set_permissions, symlink, symlink_metadata, try_exists,
+    write, write_atomic,
 };
 
+mod batch;
+mod builder;
 mod client;
+mod order;
+mod remote_file;
 mod server;
+mod shared_ring;
 mod task;
 
-pub use self::{client::Client, server::Server, task::FsTask};
+pub use self::{
+    batch::{Batch, BatchOp, BatchOutput, OnError},
+    builder::Builder,
+    client::Client,
+    remote_file::RemoteFile,
+    server::Server,
+    shared_ring::{SharedBuf, SharedReader, SharedRing, SharedSink, SharedSource},
+    task::{FileTask, FsTask},
+};
 
 pub fn split() -> (Server, Client) {
-    let (sender, receiver) = mpsc::unbounded_channel();
-    (Server { receiver }, Client { sender })
+    Builder::new().build()
+}
+
+/// Like [`split`], with a [`SharedRing`] that [`Client::read_shared`] reads files into.
+pub fn split_shared(ring: SharedRing) -> (Server, Client) {
+    Builder::new().shared_ring(ring).build()
 }
 
+/// Runs the operations sent by a [`Client`] on the [`Server`]'s thread.
+///
+/// Operations added after the first release have default implementations that call this crate
+/// directly, so existing implementations keep compiling.
 #[allow(async_fn_in_trait)]
 pub trait FsOffload {
     async fn read(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>>;
     async fn write(&self, path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()>;
     async fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<u64>;
+    /// Reads the entries of a directory. `ReadDir` is `!Send`, so the server
+    /// collects them before sending them back to the `Client`.
     async fn read_dir(&self, path: impl AsRef<Path>) -> io::Result<ReadDir>;
     async fn create_dir(&self, path: impl AsRef<Path>) -> io::Result<()>;
     async fn create_dir_all(&self, path: impl AsRef<Path>) -> io::Result<()>;
@@ -39,6 +67,137 @@ pub trait FsOffload {
         recursive: bool,
         cb: impl Fn(event::Event) + Send + Sync + 'static,
     ) -> io::Result<()>;
+
+    async fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+        rename(from, to).await
+    }
+
+    async fn try_exists(&self, path: impl AsRef<Path>) -> io::Result<bool> {
+        try_exists(path).await
+    }
+
+    async fn canonicalize(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        canonicalize(path).await
+    }
+
+    async fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        read_to_string(path).await
+    }
+
+    async fn symlink_metadata(&self, path: impl AsRef<Path>) -> io::Result<Metadata> {
+        symlink_metadata(path).await
+    }
+
+    async fn hard_link(
+        &self,
+        original: impl AsRef<Path>,
+        link: impl AsRef<Path>,
+    ) -> io::Result<()> {
+        hard_link(original, link).await
+    }
+
+    async fn symlink(&self, original: impl AsRef<Path>, link: impl AsRef<Path>) -> io::Result<()> {
+        symlink(original, link).await
+    }
+
+    async fn read_link(&self, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+        read_link(path).await
+    }
+
+    async fn set_permissions(&self, path: impl AsRef<Path>, perm: Permissions) -> io::Result<()> {
+        set_permissions(path, perm).await
+    }
+
+    async fn write_atomic(
+        &self,
+        path: impl AsRef<Path>,
+        content: impl AsRef<[u8]>,
+    ) -> io::Result<()> {
+        write_atomic(path, content).await
+    }
+
+    /// Opens the file behind a [`RemoteFile`]. The `Server` keeps the file, since `File` is
+    /// `!Send`, and runs the reads and writes of the `RemoteFile` on it.
+    async fn open(&self, path: impl AsRef<Path>, options: &OpenOptions) -> io::Result<File> {
+        options.open(path).await
+    }
+
+    /// Reads the file at `path` straight into slabs of `ring`, one slab at a time, sending each
+    /// to `sink`.
+    ///
+    /// A slab is taken before the file is read into it, and the file is closed while waiting for
+    /// one, so a slow reader does not keep the file open.
+    async fn read_shared(
+        &self,
+        path: impl AsRef<Path>,
+        ring: SharedRing,
+        sink: SharedSink,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        let slab_size = ring.slab_size();
+        let mut file: Option<File> = None;
+        let mut len = None;
+        let mut pos = 0;
+        while len != Some(pos) {
+            let mut buf = match ring.try_alloc(slab_size)? {
+                Some(buf) => buf,
+                None => {
+                    file = None;
+                    ring.alloc(slab_size).await?
+                }
+            };
+            let file = match &mut file {
+                Some(file) => file,
+                None => {
+                    let mut opened = File::open(path).await?;
+                    opened.seek(SeekFrom::Start(pos)).await?;
+                    file.insert(opened)
+                }
+            };
+            let total = *len.get_or_insert(file.size()?);
+            // At most `slab_size`, so it fits in `usize`.
+            let n = (total - pos).min(slab_size as u64) as usize;
+            buf.truncate(n);
+            for chunk in buf.chunks_mut() {
+                file.read_exact(chunk).await?;
+            }
+            pos += n as u64;
+            if n > 0 {
+                sink.send(buf)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the chunks from `source` to the file at `path` straight from their slabs, like
+    /// [`write`]. The file is created once the first chunk comes in.
+    async fn write_shared(
+        &self,
+        path: impl AsRef<Path>,
+        mut source: SharedSource,
+    ) -> io::Result<()> {
+        let mut next = source.next_chunk().await?;
+        let mut file = File::create(path).await?;
+        while let Some(content) = next {
+            for chunk in content.chunks() {
+                file.write_all(chunk).await?;
+            }
+            // Dropping `content` releases its slabs for the next chunk.
+            drop(content);
+            next = source.next_chunk().await?;
+        }
+        // The inherent `File::flush` would shadow the `AsyncWrite` one.
+        AsyncWriteExt::flush(&mut file).await
+    }
+
+    /// Runs the operations of a [`Batch`] in order with the methods of this trait.
+    async fn batch(
+        &self,
+        ops: Vec<BatchOp>,
+        on_error: OnError,
+    ) -> io::Result<Vec<io::Result<BatchOutput>>> {
+        Ok(batch::run_batch(self, ops, on_error).await)
+    }
 }
 
 pub struct FsOffloadDefault;
@@ -0,0 +1,85 @@
+use std::{
+    cell::Cell,
+    future::Future,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use futures::future::{FutureExt, Shared, join_all};
+use tokio::sync::oneshot;
+
+/// Orders the tasks of a [`Server`](super::Server) that touch overlapping paths.
+///
+/// Two paths overlap when they are equal or one is an ancestor of the other. A task starts once
+/// every task received before it with an overlapping path is done, so those run one at a time in
+/// the order they were received, while tasks on unrelated paths run concurrently.
+#[derive(Default)]
+pub(super) struct PathOrder {
+    /// Tasks not done yet, in the order they were received.
+    pending: Vec<Entry>,
+}
+
+struct Entry {
+    paths: Vec<PathBuf>,
+    done: Rc<Cell<bool>>,
+    /// Resolves once the task is done, when its `Done` guard is dropped.
+    finished: Shared<oneshot::Receiver<()>>,
+}
+
+/// Marks a task as done when dropped, whether it ran to completion or was abandoned.
+pub(super) struct Done {
+    done: Rc<Cell<bool>>,
+    _finished: oneshot::Sender<()>,
+}
+
+impl Drop for Done {
+    fn drop(&mut self) {
+        self.done.set(true);
+    }
+}
+
+impl PathOrder {
+    /// Registers a task touching `paths`. Returns a future resolving once the earlier tasks with
+    /// overlapping paths are done, and the guard to drop once this task is done.
+    pub(super) fn enter(
+        &mut self,
+        paths: Vec<PathBuf>,
+    ) -> (impl Future<Output = ()> + use<>, Done) {
+        self.pending.retain(|entry| !entry.done.get());
+
+        let earlier: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|entry| {
+                entry
+                    .paths
+                    .iter()
+                    .any(|a| paths.iter().any(|b| overlaps(a, b)))
+            })
+            .map(|entry| entry.finished.clone())
+            .collect();
+
+        let (sender, receiver) = oneshot::channel();
+        let done = Rc::new(Cell::new(false));
+        if !paths.is_empty() {
+            self.pending.push(Entry {
+                paths,
+                done: done.clone(),
+                finished: receiver.shared(),
+            });
+        }
+
+        let ready = async move {
+            join_all(earlier).await;
+        };
+        let guard = Done {
+            done,
+            _finished: sender,
+        };
+        (ready, guard)
+    }
+}
+
+fn overlaps(a: &Path, b: &Path) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
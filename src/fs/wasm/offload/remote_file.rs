@@ -0,0 +1,400 @@
+use std::{
+    fmt,
+    future::{Future, poll_fn},
+    io::{self, SeekFrom},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll, ready},
+};
+
+use futures::{
+    future::BoxFuture,
+    io::{AsyncRead, AsyncSeek, AsyncWrite},
+};
+use tokio::sync::{AcquireError, OwnedSemaphorePermit, Semaphore, mpsc, oneshot};
+
+use super::{super::file::DEFAULT_MAX_BUF_SIZE, FileTask, FsTask, task::Request};
+
+/// A [`File`](super::super::File) kept open by the [`Server`](super::Server) and used from any
+/// thread through a [`Client`](super::Client).
+///
+/// Every read, write and seek is a task sent to the `Server`, which runs it on the file. Like
+/// `tokio::fs::File`, a write returns as soon as its task is sent, and an error it hits is
+/// returned by the next operation: flush the file before dropping it to see every error. The file
+/// is closed on the `Server` when the `RemoteFile` is dropped.
+///
+/// Its operations wait for room in the queue like other client calls, but do not use the timeout
+/// of the [`Builder`](super::Builder): an abandoned write would leave the file in an unknown
+/// state.
+pub struct RemoteFile {
+    sender: mpsc::UnboundedSender<Request>,
+    /// The queue of the `Client` that opened the file, see [`poll_ready`](RemoteFile::poll_ready).
+    queue: Option<Arc<Semaphore>>,
+    /// Room in the queue taken for the next operation.
+    permit: Option<OwnedSemaphorePermit>,
+    acquire: Option<BoxFuture<'static, Result<OwnedSemaphorePermit, AcquireError>>>,
+    id: usize,
+    /// The operation in flight. Only one is sent at a time, so they run in order.
+    pending: Option<Pending>,
+    /// Data the `Server` read that did not fit in the caller's buffer, of which `consumed` bytes
+    /// were returned since.
+    read_buf: Vec<u8>,
+    consumed: usize,
+    /// Seek started by `tokio::io::AsyncSeek::start_seek` and not sent yet.
+    #[cfg(feature = "tokio-io")]
+    queued_seek: Option<SeekFrom>,
+}
+
+#[derive(Debug)]
+enum Pending {
+    Read(oneshot::Receiver<io::Result<Vec<u8>>>),
+    Write(oneshot::Receiver<io::Result<()>>),
+    Seek(oneshot::Receiver<io::Result<u64>>),
+    Flush(oneshot::Receiver<io::Result<()>>),
+}
+
+impl RemoteFile {
+    pub(super) fn new(
+        sender: mpsc::UnboundedSender<Request>,
+        queue: Option<Arc<Semaphore>>,
+        id: usize,
+    ) -> RemoteFile {
+        RemoteFile {
+            sender,
+            queue,
+            permit: None,
+            acquire: None,
+            id,
+            pending: None,
+            read_buf: Vec::new(),
+            consumed: 0,
+            #[cfg(feature = "tokio-io")]
+            queued_seek: None,
+        }
+    }
+
+    /// Truncates or extends the file to `size` bytes. The cursor is not moved.
+    pub async fn set_len(&mut self, size: u64) -> io::Result<()> {
+        poll_fn(|cx| self.poll_rewind(cx)).await?;
+        poll_fn(|cx| self.poll_ready(cx)).await?;
+        let id = self.id;
+        let receiver = self.send(|sender| FileTask::SetLen { id, size, sender })?;
+        recv(receiver).await
+    }
+
+    /// Waits for room in the queue for the next operation, which [`send`](RemoteFile::send)
+    /// takes.
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.permit.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+        let Some(queue) = &self.queue else {
+            return Poll::Ready(Ok(()));
+        };
+        let acquire = self
+            .acquire
+            .get_or_insert_with(|| Box::pin(queue.clone().acquire_owned()));
+        let res = ready!(acquire.as_mut().poll(cx));
+        self.acquire = None;
+        self.permit = Some(res.map_err(|_| io::Error::from(io::ErrorKind::ConnectionAborted))?);
+        Poll::Ready(Ok(()))
+    }
+
+    /// Sends an operation, in the room taken by [`poll_ready`](RemoteFile::poll_ready).
+    fn send<T>(
+        &mut self,
+        create_task: impl FnOnce(oneshot::Sender<io::Result<T>>) -> FileTask,
+    ) -> io::Result<oneshot::Receiver<io::Result<T>>> {
+        let (sender, receiver) = oneshot::channel();
+        let request = Request {
+            permit: self.permit.take(),
+            ..FsTask::File(create_task(sender)).into()
+        };
+        self.sender
+            .send(request)
+            .map_err(|_| io::Error::from(io::ErrorKind::ConnectionAborted))?;
+        Ok(receiver)
+    }
+
+    fn unread(&self) -> usize {
+        self.read_buf.len() - self.consumed
+    }
+
+    /// Waits for the operation in flight, keeping the data of a read.
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let res = match self.pending.as_mut() {
+            None => return Poll::Ready(Ok(())),
+            Some(Pending::Read(receiver)) => match ready!(poll_recv(receiver, cx)) {
+                Ok(data) => {
+                    self.read_buf = data;
+                    self.consumed = 0;
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            },
+            Some(Pending::Write(receiver) | Pending::Flush(receiver)) => {
+                ready!(poll_recv(receiver, cx))
+            }
+            Some(Pending::Seek(receiver)) => ready!(poll_recv(receiver, cx)).map(drop),
+        };
+        self.pending = None;
+        Poll::Ready(res)
+    }
+
+    /// Waits for the operation in flight, then moves the `Server`'s cursor back over data read
+    /// but not returned yet, so that the next operation starts where the caller expects.
+    fn poll_rewind(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            ready!(self.poll_pending(cx))?;
+            let unread = self.unread();
+            if unread == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            ready!(self.poll_ready(cx))?;
+            self.discard_read_buf();
+            let id = self.id;
+            let pos = SeekFrom::Current(-(unread as i64));
+            self.pending = Some(Pending::Seek(self.send(|sender| FileTask::Seek {
+                id,
+                pos,
+                sender,
+            })?));
+        }
+    }
+
+    fn discard_read_buf(&mut self) {
+        self.read_buf.clear();
+        self.consumed = 0;
+    }
+
+    /// Sends a seek. Call [`poll_ready`](RemoteFile::poll_ready) first.
+    fn start_seek(&mut self, pos: SeekFrom) -> io::Result<()> {
+        // The `Server`'s cursor is past the unread data.
+        let pos = match pos {
+            SeekFrom::Current(offset) => SeekFrom::Current(offset - self.unread() as i64),
+            pos => pos,
+        };
+        self.discard_read_buf();
+        let id = self.id;
+        self.pending = Some(Pending::Seek(self.send(|sender| FileTask::Seek {
+            id,
+            pos,
+            sender,
+        })?));
+        Ok(())
+    }
+}
+
+fn poll_recv<T>(
+    receiver: &mut oneshot::Receiver<io::Result<T>>,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<T>> {
+    Pin::new(receiver)
+        .poll(cx)
+        .map(|res| res.map_err(|_| io::Error::from(io::ErrorKind::ConnectionAborted))?)
+}
+
+async fn recv<T>(mut receiver: oneshot::Receiver<io::Result<T>>) -> io::Result<T> {
+    poll_fn(|cx| poll_recv(&mut receiver, cx)).await
+}
+
+impl Drop for RemoteFile {
+    fn drop(&mut self) {
+        let mut request: Request = FsTask::File(FileTask::Close { id: self.id }).into();
+        let Some(queue) = self.queue.clone() else {
+            let _ = self.sender.send(request);
+            return;
+        };
+        request.permit = self
+            .permit
+            .take()
+            .or_else(|| queue.clone().try_acquire_owned().ok());
+        if request.permit.is_some() {
+            let _ = self.sender.send(request);
+            return;
+        }
+        // The queue is full: the file is closed once there is room.
+        let sender = self.sender.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Ok(permit) = queue.acquire_owned().await {
+                request.permit = Some(permit);
+                let _ = sender.send(request);
+            }
+        });
+    }
+}
+
+impl fmt::Debug for RemoteFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RemoteFile")
+            .field("id", &self.id)
+            .field("pending", &self.pending)
+            .field("read_buf", &self.read_buf.len())
+            .field("consumed", &self.consumed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AsyncRead for RemoteFile {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            if this.unread() > 0 {
+                let n = this.unread().min(buf.len());
+                buf[..n].copy_from_slice(&this.read_buf[this.consumed..this.consumed + n]);
+                this.consumed += n;
+                return Poll::Ready(Ok(n));
+            }
+            if let Some(Pending::Read(receiver)) = this.pending.as_mut() {
+                let res = ready!(poll_recv(receiver, cx));
+                this.pending = None;
+                let data = res?;
+                let n = data.len().min(buf.len());
+                buf[..n].copy_from_slice(&data[..n]);
+                this.read_buf = data;
+                this.consumed = n;
+                return Poll::Ready(Ok(n));
+            }
+            ready!(this.poll_pending(cx))?;
+            if this.unread() == 0 {
+                if buf.is_empty() {
+                    return Poll::Ready(Ok(0));
+                }
+                ready!(this.poll_ready(cx))?;
+                let id = this.id;
+                let len = buf.len().min(DEFAULT_MAX_BUF_SIZE);
+                this.pending = Some(Pending::Read(this.send(|sender| FileTask::Read {
+                    id,
+                    len,
+                    sender,
+                })?));
+            }
+        }
+    }
+}
+
+impl AsyncWrite for RemoteFile {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        // One write in flight at a time keeps memory use bounded.
+        ready!(this.poll_rewind(cx))?;
+        ready!(this.poll_ready(cx))?;
+
+        let id = this.id;
+        let n = buf.len().min(DEFAULT_MAX_BUF_SIZE);
+        let data = buf[..n].to_vec();
+        this.pending = Some(Pending::Write(this.send(|sender| FileTask::Write {
+            id,
+            data,
+            sender,
+        })?));
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(Pending::Flush(receiver)) = this.pending.as_mut() {
+                let res = ready!(poll_recv(receiver, cx));
+                this.pending = None;
+                return Poll::Ready(res);
+            }
+            ready!(this.poll_rewind(cx))?;
+            ready!(this.poll_ready(cx))?;
+            let id = this.id;
+            this.pending = Some(Pending::Flush(
+                this.send(|sender| FileTask::Flush { id, sender })?,
+            ));
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_flush(cx)
+    }
+}
+
+impl AsyncSeek for RemoteFile {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(Pending::Seek(receiver)) = this.pending.as_mut() {
+                let res = ready!(poll_recv(receiver, cx));
+                this.pending = None;
+                return Poll::Ready(res);
+            }
+            ready!(this.poll_pending(cx))?;
+            ready!(this.poll_ready(cx))?;
+            this.start_seek(pos)?;
+        }
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncRead for RemoteFile {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let n = ready!(AsyncRead::poll_read(self, cx, buf.initialize_unfilled()))?;
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncWrite for RemoteFile {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(self, cx)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncSeek for RemoteFile {
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        let this = self.get_mut();
+        if this.pending.is_some() || this.queued_seek.is_some() {
+            return Err(io::Error::other(
+                "other file operation is pending, call poll_complete before start_seek",
+            ));
+        }
+        // Sent by `poll_complete`, once there is room in the queue.
+        this.queued_seek = Some(position);
+        Ok(())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        let this = self.get_mut();
+        if !matches!(this.pending, Some(Pending::Seek(_))) {
+            ready!(this.poll_pending(cx))?;
+            ready!(this.poll_ready(cx))?;
+            let pos = this.queued_seek.take().unwrap_or(SeekFrom::Current(0));
+            this.start_seek(pos)?;
+        }
+        AsyncSeek::poll_seek(Pin::new(this), cx, SeekFrom::Current(0))
+    }
+}
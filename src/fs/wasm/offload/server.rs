@@ -1,25 +1,49 @@
-use futures::stream::{FuturesUnordered, StreamExt};
+use std::{cell::RefCell, io, path::PathBuf, rc::Rc};
+
+use futures::{
+    lock::Mutex,
+    stream::{FuturesUnordered, StreamExt},
+};
+use slab::Slab;
 use tokio::sync::mpsc;
 
-use super::{FsOffload, FsOffloadDefault, FsTask};
+use super::{super::File, FsOffload, FsOffloadDefault, order::PathOrder, task::Request};
 
+/// Runs the tasks sent by the [`Client`](super::Client)s of its pair.
+///
+/// Tasks whose paths overlap, meaning equal or one inside the other, run one at a time in the
+/// order the server received them. Other tasks run concurrently. Calls that one client makes one
+/// after another are received in that order, so a `write` followed by a `read` of the same file
+/// sees the new contents, and a `write` into a directory made by an earlier `create_dir_all`
+/// finds it. Calls made concurrently, or from different clients, are ordered by when they reach
+/// the channel.
+///
+/// Paths are compared after resolving `.` and `..` against the server's current directory, but
+/// symbolic and hard links are not followed, so two paths naming the same file through a link
+/// are not ordered. Operations on a [`RemoteFile`](super::RemoteFile) are ordered by the path
+/// the file was opened with.
 pub struct Server {
-    pub(super) receiver: mpsc::UnboundedReceiver<FsTask>,
+    pub(super) receiver: mpsc::UnboundedReceiver<Request>,
+    /// Tasks run at once, see [`Builder::max_in_flight`](super::Builder::max_in_flight).
+    pub(super) max_in_flight: usize,
 }
 
 impl Server {
     pub async fn serve(&mut self, offload: impl FsOffload) {
+        let files = OpenFiles::default();
+        let files = &files;
         let mut tasks = FuturesUnordered::new();
         let offload = &offload;
+        let max_in_flight = self.max_in_flight;
+        let mut order = PathOrder::default();
 
         loop {
             tokio::select! {
-                res = self.receiver.recv() => {
+                // Past the limit, requests wait in the channel, which fills the clients' queues.
+                res = self.receiver.recv(), if tasks.len() < max_in_flight => {
                     match res {
-                        Some(task) => {
-                            tasks.push(async move {
-                                task.execute(offload).await;
-                            });
+                        Some(request) => {
+                            tasks.push(request.start(offload, files, &mut order));
                         }
                         None => {
                             while (tasks.next().await).is_some() {}
@@ -36,3 +60,39 @@ impl Server {
         self.serve(FsOffloadDefault).await
     }
 }
+
+/// Files opened by `RemoteFile`s, indexed by the id they were given.
+///
+/// Each file sits behind an async mutex, so operations on it run one at a time while operations
+/// on other files and paths go on.
+#[derive(Default)]
+pub(super) struct OpenFiles(RefCell<Slab<(PathBuf, Rc<Mutex<File>>)>>);
+
+impl OpenFiles {
+    pub(super) fn insert(&self, file: File) -> usize {
+        let path = file.path.clone();
+        self.0
+            .borrow_mut()
+            .insert((path, Rc::new(Mutex::new(file))))
+    }
+
+    pub(super) fn get(&self, id: usize) -> io::Result<Rc<Mutex<File>>> {
+        let files = self.0.borrow();
+        files.get(id).map(|(_, file)| file.clone()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("no open file with id {id}"),
+            )
+        })
+    }
+
+    /// Returns the virtualized path the file was opened with.
+    pub(super) fn path(&self, id: usize) -> Option<PathBuf> {
+        self.0.borrow().get(id).map(|(path, _)| path.clone())
+    }
+
+    /// The file is closed once the operations holding it are done.
+    pub(super) fn remove(&self, id: usize) {
+        self.0.borrow_mut().try_remove(id);
+    }
+}
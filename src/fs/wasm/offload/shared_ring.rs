@@ -0,0 +1,306 @@
+use std::{
+    fmt,
+    future::poll_fn,
+    io,
+    sync::{Arc, Mutex},
+    task::{Poll, ready},
+};
+
+use futures::future::BoxFuture;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, mpsc};
+
+/// A fixed set of equally sized slabs shared by a [`Client`](super::Client) and the
+/// [`Server`](super::Server), used to move file contents between threads without copying them.
+///
+/// The slabs live in the wasm memory, which is a `SharedArrayBuffer` when the module is built
+/// with the `atomics` target feature, so both threads read and write them in place and only the
+/// buffer descriptors go through the channel. The server reads files straight into slabs and
+/// writes files straight from them.
+///
+/// The ring bounds the memory used for payloads in flight: [`alloc`](SharedRing::alloc) waits
+/// while the ring is full, until enough slabs are released by dropping [`SharedBuf`]s. Files of
+/// any size go through it one slab at a time, see [`Client::read_shared`](super::Client::read_shared)
+/// and [`Client::write_shared`](super::Client::write_shared).
+#[derive(Clone)]
+pub struct SharedRing {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    slab_size: usize,
+    slab_count: usize,
+    /// Released slabs, kept for reuse. Slabs are only allocated when none is free.
+    free: Mutex<Vec<Box<[u8]>>>,
+    /// One permit per slab not handed out.
+    permits: Arc<Semaphore>,
+}
+
+impl SharedRing {
+    /// Creates a ring of `slab_count` slabs of `slab_size` bytes each. Slabs are allocated the
+    /// first time they are needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slab_size` or `slab_count` is zero, or if `slab_count` exceeds `u32::MAX`.
+    pub fn new(slab_size: usize, slab_count: usize) -> SharedRing {
+        assert!(slab_size > 0, "slab size must be non-zero");
+        assert!(slab_count > 0, "slab count must be non-zero");
+        assert!(u32::try_from(slab_count).is_ok(), "too many slabs");
+        SharedRing {
+            inner: Arc::new(Inner {
+                slab_size,
+                slab_count,
+                free: Mutex::new(Vec::new()),
+                permits: Arc::new(Semaphore::new(slab_count)),
+            }),
+        }
+    }
+
+    /// Returns the size of each slab in bytes.
+    pub fn slab_size(&self) -> usize {
+        self.inner.slab_size
+    }
+
+    /// Returns the total size of the ring in bytes, which is the largest buffer it can hold.
+    pub fn capacity(&self) -> usize {
+        self.inner.slab_size * self.inner.slab_count
+    }
+
+    /// Takes enough slabs to hold `len` bytes, waiting while they are in use.
+    ///
+    /// The contents of the buffer are unspecified until written. Fails with `InvalidInput` if
+    /// `len` is larger than the [`capacity`](SharedRing::capacity) of the ring.
+    pub async fn alloc(&self, len: usize) -> io::Result<SharedBuf> {
+        let slabs = self.slabs_for(len)?;
+        let permit = self
+            .inner
+            .permits
+            .clone()
+            .acquire_many_owned(slabs)
+            .await
+            .map_err(io::Error::other)?;
+        Ok(self.take(len, permit))
+    }
+
+    /// Like [`alloc`](SharedRing::alloc), but returns `None` instead of waiting while the slabs
+    /// are in use.
+    pub fn try_alloc(&self, len: usize) -> io::Result<Option<SharedBuf>> {
+        let slabs = self.slabs_for(len)?;
+        match self.inner.permits.clone().try_acquire_many_owned(slabs) {
+            Ok(permit) => Ok(Some(self.take(len, permit))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn slabs_for(&self, len: usize) -> io::Result<u32> {
+        let slabs = len.div_ceil(self.inner.slab_size);
+        if slabs > self.inner.slab_count {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "payload of {len} bytes is larger than the shared ring of {} bytes",
+                    self.capacity()
+                ),
+            ));
+        }
+        Ok(slabs as u32)
+    }
+
+    fn take(&self, len: usize, permit: OwnedSemaphorePermit) -> SharedBuf {
+        let slabs = permit.num_permits();
+        let mut free = self.inner.free.lock().unwrap_or_else(|e| e.into_inner());
+        let slabs = (0..slabs)
+            .map(|_| {
+                free.pop()
+                    .unwrap_or_else(|| vec![0; self.inner.slab_size].into_boxed_slice())
+            })
+            .collect();
+        drop(free);
+
+        SharedBuf {
+            ring: self.inner.clone(),
+            slabs,
+            len,
+            _permit: permit,
+        }
+    }
+}
+
+impl fmt::Debug for SharedRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedRing")
+            .field("slab_size", &self.inner.slab_size)
+            .field("slab_count", &self.inner.slab_count)
+            .field("available", &self.inner.permits.available_permits())
+            .finish()
+    }
+}
+
+/// A payload held in slabs of a [`SharedRing`], which gets them back when it is dropped.
+///
+/// The payload is split across slabs, so it is accessed chunk by chunk.
+pub struct SharedBuf {
+    ring: Arc<Inner>,
+    slabs: Vec<Box<[u8]>>,
+    len: usize,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl SharedBuf {
+    /// Returns the size of the payload in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the payload is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the payload as consecutive chunks of at most one slab each.
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        let mut remaining = self.len;
+        self.slabs.iter().map(move |slab| {
+            let n = remaining.min(slab.len());
+            remaining -= n;
+            &slab[..n]
+        })
+    }
+
+    /// Returns the payload as consecutive mutable chunks of at most one slab each.
+    pub fn chunks_mut(&mut self) -> impl Iterator<Item = &mut [u8]> {
+        let mut remaining = self.len;
+        self.slabs.iter_mut().map(move |slab| {
+            let n = remaining.min(slab.len());
+            remaining -= n;
+            &mut slab[..n]
+        })
+    }
+
+    /// Shortens the payload to `len` bytes. The buffer keeps its slabs until it is dropped. Does
+    /// nothing if `len` is not shorter than the payload.
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+    }
+
+    /// Copies `data` into the payload, which must be exactly as long.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `data.len()` differs from [`len`](SharedBuf::len).
+    pub fn copy_from_slice(&mut self, data: &[u8]) {
+        assert_eq!(data.len(), self.len, "source slice length does not match");
+        let mut data = data;
+        for chunk in self.chunks_mut() {
+            let (head, tail) = data.split_at(chunk.len());
+            chunk.copy_from_slice(head);
+            data = tail;
+        }
+    }
+
+    /// Copies the payload into a new `Vec`.
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.chunks().collect::<Vec<_>>().concat()
+    }
+}
+
+impl Drop for SharedBuf {
+    fn drop(&mut self) {
+        // The slabs go back before the permit is released, so `alloc` always finds them.
+        let mut free = self.ring.free.lock().unwrap_or_else(|e| e.into_inner());
+        free.append(&mut self.slabs);
+    }
+}
+
+impl fmt::Debug for SharedBuf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedBuf")
+            .field("len", &self.len)
+            .field("slabs", &self.slabs.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The chunks of a file read by [`Client::read_shared`](super::Client::read_shared), in order.
+///
+/// Each chunk holds slabs of the ring until it is dropped, and the `Server` waits for free slabs
+/// before reading more, so chunks should be dropped as they are used.
+pub struct SharedReader<'a> {
+    chunks: mpsc::UnboundedReceiver<SharedBuf>,
+    /// The request, until its result is known.
+    request: Option<BoxFuture<'a, io::Result<()>>>,
+}
+
+impl<'a> SharedReader<'a> {
+    pub(super) fn new(
+        chunks: mpsc::UnboundedReceiver<SharedBuf>,
+        request: BoxFuture<'a, io::Result<()>>,
+    ) -> SharedReader<'a> {
+        SharedReader {
+            chunks,
+            request: Some(request),
+        }
+    }
+
+    /// Returns the next chunk of the file, or `None` once it was all read.
+    pub async fn next_chunk(&mut self) -> io::Result<Option<SharedBuf>> {
+        poll_fn(|cx| {
+            // Polling the request sends it, then waits for its result.
+            if let Some(request) = self.request.as_mut()
+                && let Poll::Ready(res) = request.as_mut().poll(cx)
+            {
+                self.request = None;
+                if let Err(e) = res {
+                    // The chunks read before the error are dropped with their slabs.
+                    self.chunks.close();
+                    while self.chunks.try_recv().is_ok() {}
+                    return Poll::Ready(Err(e));
+                }
+            }
+            match ready!(self.chunks.poll_recv(cx)) {
+                Some(chunk) => Poll::Ready(Ok(Some(chunk))),
+                // The server is done, but its result may not be in yet.
+                None if self.request.is_some() => Poll::Pending,
+                None => Poll::Ready(Ok(None)),
+            }
+        })
+        .await
+    }
+}
+
+impl fmt::Debug for SharedReader<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedReader")
+            .field("done", &self.request.is_none())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where [`FsOffload::read_shared`](super::FsOffload::read_shared) sends the chunks it reads.
+#[derive(Debug)]
+pub struct SharedSink(pub(super) mpsc::UnboundedSender<SharedBuf>);
+
+impl SharedSink {
+    /// Sends the next chunk of the file. Fails with `BrokenPipe` if the reader was dropped.
+    pub fn send(&self, chunk: SharedBuf) -> io::Result<()> {
+        self.0
+            .send(chunk)
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+/// Where [`FsOffload::write_shared`](super::FsOffload::write_shared) takes the chunks to write
+/// from.
+#[derive(Debug)]
+pub struct SharedSource(pub(super) mpsc::UnboundedReceiver<Option<SharedBuf>>);
+
+impl SharedSource {
+    /// Returns the next chunk to write, or `None` once there are no more. Fails with
+    /// `Interrupted` if the client stopped sending chunks before the end.
+    pub async fn next_chunk(&mut self) -> io::Result<Option<SharedBuf>> {
+        self.0
+            .recv()
+            .await
+            .ok_or_else(|| io::Error::from(io::ErrorKind::Interrupted))
+    }
+}
@@ -1,10 +1,22 @@
-use std::{io, path::PathBuf};
+use std::{
+    future::{self, Future},
+    io::{self, SeekFrom},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use tokio::sync::oneshot;
+use futures::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::{OwnedSemaphorePermit, oneshot};
 
 #[cfg(feature = "opfs_watch")]
 use super::super::opfs::watch::event;
-use super::{FsOffload, Metadata, ReadDir};
+use super::{
+    super::opfs::{sleep, virtualize},
+    BatchOp, BatchOutput, DirEntry, FsOffload, Metadata, OnError, OpenOptions, Permissions,
+    SharedRing, SharedSink, SharedSource,
+    order::PathOrder,
+    server::OpenFiles,
+};
 
 pub enum FsTask {
     Read {
@@ -23,7 +35,7 @@ pub enum FsTask {
     },
     ReadDir {
         path: PathBuf,
-        sender: oneshot::Sender<io::Result<ReadDir>>,
+        sender: oneshot::Sender<io::Result<Vec<DirEntry>>>,
     },
     CreateDir {
         path: PathBuf,
@@ -56,6 +68,298 @@ pub enum FsTask {
         cb: Box<dyn Fn(event::Event) + Send + Sync + 'static>,
         sender: oneshot::Sender<io::Result<()>>,
     },
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    TryExists {
+        path: PathBuf,
+        sender: oneshot::Sender<io::Result<bool>>,
+    },
+    Canonicalize {
+        path: PathBuf,
+        sender: oneshot::Sender<io::Result<PathBuf>>,
+    },
+    ReadToString {
+        path: PathBuf,
+        sender: oneshot::Sender<io::Result<String>>,
+    },
+    SymlinkMetadata {
+        path: PathBuf,
+        sender: oneshot::Sender<io::Result<Metadata>>,
+    },
+    HardLink {
+        original: PathBuf,
+        link: PathBuf,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    Symlink {
+        original: PathBuf,
+        link: PathBuf,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    ReadLink {
+        path: PathBuf,
+        sender: oneshot::Sender<io::Result<PathBuf>>,
+    },
+    SetPermissions {
+        path: PathBuf,
+        perm: Permissions,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    WriteAtomic {
+        path: PathBuf,
+        content: Vec<u8>,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    ReadShared {
+        path: PathBuf,
+        ring: SharedRing,
+        sink: SharedSink,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    WriteShared {
+        path: PathBuf,
+        source: SharedSource,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    Batch {
+        ops: Vec<BatchOp>,
+        on_error: OnError,
+        sender: oneshot::Sender<io::Result<Vec<io::Result<BatchOutput>>>>,
+    },
+    File(FileTask),
+}
+
+impl FsTask {
+    /// Virtualized paths the task reads or changes, to order it against other tasks. Operations
+    /// on a `RemoteFile` touch the path the file was opened with.
+    pub(super) fn paths(&self, files: &OpenFiles) -> Vec<PathBuf> {
+        let paths: Vec<&Path> = match self {
+            FsTask::Read { path, .. }
+            | FsTask::Write { path, .. }
+            | FsTask::ReadDir { path, .. }
+            | FsTask::CreateDir { path, .. }
+            | FsTask::CreateDirAll { path, .. }
+            | FsTask::RemoveFile { path, .. }
+            | FsTask::RemoveDir { path, .. }
+            | FsTask::RemoveDirAll { path, .. }
+            | FsTask::Metadata { path, .. }
+            | FsTask::TryExists { path, .. }
+            | FsTask::Canonicalize { path, .. }
+            | FsTask::ReadToString { path, .. }
+            | FsTask::SymlinkMetadata { path, .. }
+            | FsTask::ReadLink { path, .. }
+            | FsTask::SetPermissions { path, .. }
+            | FsTask::WriteAtomic { path, .. }
+            | FsTask::ReadShared { path, .. }
+            | FsTask::WriteShared { path, .. }
+            | FsTask::File(FileTask::Open { path, .. }) => vec![path],
+            #[cfg(feature = "opfs_watch")]
+            FsTask::WatchDir { path, .. } => vec![path],
+            FsTask::Copy { from, to, .. } | FsTask::Rename { from, to, .. } => vec![from, to],
+            FsTask::Batch { ops, .. } => ops.iter().flat_map(BatchOp::paths).collect(),
+            FsTask::HardLink { original, link, .. } | FsTask::Symlink { original, link, .. } => {
+                vec![original, link]
+            }
+            FsTask::File(
+                FileTask::Read { id, .. }
+                | FileTask::Write { id, .. }
+                | FileTask::Seek { id, .. }
+                | FileTask::Flush { id, .. }
+                | FileTask::SetLen { id, .. }
+                | FileTask::Close { id },
+            ) => return files.path(*id).into_iter().collect(),
+        };
+        paths
+            .into_iter()
+            .map(|path| virtualize(path).unwrap_or_else(|_| path.to_path_buf()))
+            .collect()
+    }
+}
+
+/// A task as sent through the channel, with what the `Server` needs to schedule it.
+pub(super) struct Request {
+    pub(super) task: FsTask,
+    /// Slot in the `Client`'s bounded queue, released once the `Server` takes the task.
+    pub(super) permit: Option<OwnedSemaphorePermit>,
+    /// `Date.now()` after which the task is abandoned.
+    pub(super) deadline: Option<f64>,
+    /// Resolves once the caller stops waiting for the result, which abandons the task.
+    pub(super) cancelled: Option<oneshot::Receiver<()>>,
+}
+
+impl From<FsTask> for Request {
+    fn from(task: FsTask) -> Request {
+        Request {
+            task,
+            permit: None,
+            deadline: None,
+            cancelled: None,
+        }
+    }
+}
+
+impl Request {
+    /// Frees the queue slot and registers the task in `order`, then returns the future running
+    /// the task until it is done, cancelled or past its deadline. Dropping the task closes its
+    /// result channel.
+    pub(super) fn start<'a, O: FsOffload>(
+        self,
+        offload: &'a O,
+        files: &'a OpenFiles,
+        order: &mut PathOrder,
+    ) -> impl Future<Output = ()> + use<'a, O> {
+        let Request {
+            task,
+            permit,
+            deadline,
+            cancelled,
+        } = self;
+        drop(permit);
+        let (ready, done) = order.enter(task.paths(files));
+
+        async move {
+            let _done = done;
+            if deadline.is_some_and(|deadline| js_sys::Date::now() >= deadline) {
+                return;
+            }
+            let cancelled = async {
+                match cancelled {
+                    Some(cancelled) => {
+                        let _ = cancelled.await;
+                    }
+                    None => future::pending().await,
+                }
+            };
+            let expired = async {
+                match deadline {
+                    Some(deadline) => sleep_until(deadline).await,
+                    None => future::pending().await,
+                }
+            };
+            let run = async {
+                ready.await;
+                task.execute(offload, files).await
+            };
+            tokio::select! {
+                _ = run => {}
+                _ = cancelled => {}
+                _ = expired => {}
+            }
+        }
+    }
+}
+
+/// Waits until `Date.now()` reaches `deadline`, so that the `Client` sees it passed.
+async fn sleep_until(deadline: f64) {
+    loop {
+        let remaining = deadline - js_sys::Date::now();
+        if remaining <= 0.0 {
+            return;
+        }
+        if sleep(Duration::from_millis(remaining.ceil() as u64))
+            .await
+            .is_err()
+        {
+            // Without timers, the task runs to completion.
+            future::pending::<()>().await;
+        }
+    }
+}
+
+/// An operation on a file kept open by the `Server` for a [`RemoteFile`](super::RemoteFile),
+/// which refers to it by id.
+pub enum FileTask {
+    Open {
+        path: PathBuf,
+        options: OpenOptions,
+        sender: oneshot::Sender<io::Result<usize>>,
+    },
+    Read {
+        id: usize,
+        len: usize,
+        sender: oneshot::Sender<io::Result<Vec<u8>>>,
+    },
+    /// Writes all of `data`.
+    Write {
+        id: usize,
+        data: Vec<u8>,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    Seek {
+        id: usize,
+        pos: SeekFrom,
+        sender: oneshot::Sender<io::Result<u64>>,
+    },
+    Flush {
+        id: usize,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    SetLen {
+        id: usize,
+        size: u64,
+        sender: oneshot::Sender<io::Result<()>>,
+    },
+    /// Closes the file once the operations already started on it are done.
+    Close { id: usize },
+}
+
+impl FileTask {
+    async fn execute(self, offload: &impl FsOffload, files: &OpenFiles) {
+        match self {
+            FileTask::Open {
+                path,
+                options,
+                sender,
+            } => {
+                let res = offload.open(path, &options).await;
+                let _ = sender.send(res.map(|file| files.insert(file)));
+            }
+            FileTask::Read { id, len, sender } => {
+                let res = async {
+                    let file = files.get(id)?;
+                    let mut file = file.lock().await;
+                    let mut buf = vec![0; len];
+                    let n = file.read(&mut buf).await?;
+                    buf.truncate(n);
+                    Ok(buf)
+                };
+                let _ = sender.send(res.await);
+            }
+            FileTask::Write { id, data, sender } => {
+                let res = async {
+                    let file = files.get(id)?;
+                    file.lock().await.write_all(&data).await
+                };
+                let _ = sender.send(res.await);
+            }
+            FileTask::Seek { id, pos, sender } => {
+                let res = async {
+                    let file = files.get(id)?;
+                    file.lock().await.seek(pos).await
+                };
+                let _ = sender.send(res.await);
+            }
+            FileTask::Flush { id, sender } => {
+                let res = async {
+                    let file = files.get(id)?;
+                    // The inherent `File::flush` would shadow the `AsyncWrite` one.
+                    AsyncWriteExt::flush(&mut *file.lock().await).await
+                };
+                let _ = sender.send(res.await);
+            }
+            FileTask::SetLen { id, size, sender } => {
+                let res = async {
+                    let file = files.get(id)?;
+                    file.lock().await.set_len(size).await
+                };
+                let _ = sender.send(res.await);
+            }
+            FileTask::Close { id } => files.remove(id),
+        }
+    }
 }
 
 macro_rules! impl_fs_task_execute {
@@ -65,8 +369,13 @@ macro_rules! impl_fs_task_execute {
         [ $( $(#[$attr:meta])* ($variant:ident, $method:ident, ( $( $arg:ident : $arg_type:ty ),* ) ) ),* ]
     ) => {
         impl $task_enum {
-            pub(super) async fn execute(self, offload: &impl $offload_trait) {
+            pub(super) async fn execute(self, offload: &impl $offload_trait, files: &OpenFiles) {
                 match self {
+                    $task_enum::File(task) => task.execute(offload, files).await,
+                    $task_enum::ReadDir { path, sender } => {
+                        let res = async { offload.read_dir(path).await?.collect_entries().await };
+                        let _ = sender.send(res.await);
+                    }
                     $(
                         $(#[$attr])*
                         $task_enum::$variant { $( $arg, )* sender } => {
@@ -86,13 +395,25 @@ impl_fs_task_execute!(
         (Read, read, (path: PathBuf)),
         (Write, write, (path: PathBuf, content: Vec<u8>)),
         (Copy, copy, (from: PathBuf, to: PathBuf)),
-        (ReadDir, read_dir, (path: PathBuf)),
         (CreateDir, create_dir, (path: PathBuf)),
         (CreateDirAll, create_dir_all, (path: PathBuf)),
         (RemoveFile, remove_file, (path: PathBuf)),
         (RemoveDir, remove_dir, (path: PathBuf)),
         (RemoveDirAll, remove_dir_all, (path: PathBuf)),
         (Metadata, metadata, (path: PathBuf)),
+        (Rename, rename, (from: PathBuf, to: PathBuf)),
+        (TryExists, try_exists, (path: PathBuf)),
+        (Canonicalize, canonicalize, (path: PathBuf)),
+        (ReadToString, read_to_string, (path: PathBuf)),
+        (SymlinkMetadata, symlink_metadata, (path: PathBuf)),
+        (HardLink, hard_link, (original: PathBuf, link: PathBuf)),
+        (Symlink, symlink, (original: PathBuf, link: PathBuf)),
+        (ReadLink, read_link, (path: PathBuf)),
+        (SetPermissions, set_permissions, (path: PathBuf, perm: Permissions)),
+        (WriteAtomic, write_atomic, (path: PathBuf, content: Vec<u8>)),
+        (ReadShared, read_shared, (path: PathBuf, ring: SharedRing, sink: SharedSink)),
+        (WriteShared, write_shared, (path: PathBuf, source: SharedSource)),
+        (Batch, batch, (ops: Vec<BatchOp>, on_error: OnError)),
         #[cfg(feature = "opfs_watch")]
         (WatchDir, watch_dir, (path: PathBuf, recursive: bool, cb: Box<dyn Fn(event::Event) + Send + Sync + 'static> ))
     ]
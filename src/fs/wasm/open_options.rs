@@ -5,8 +5,10 @@ use futures::io::AsyncSeekExt;
 
 use super::{
     File,
-    opfs::{CreateFileMode, SyncAccessMode, open_file},
+    opfs::{CreateFileMode, SyncAccessMode, meta_store, open_file},
+    try_exists,
 };
+use crate::{OpenOptionsExt, fs::backend::OpenFlags};
 
 bitflags! {
     #[derive(Clone, Debug, Copy)]
@@ -26,77 +28,99 @@ impl Default for Flags {
     }
 }
 
+/// Mode new files get unless [`OpenOptionsExt::mode`] is called.
+const DEFAULT_MODE: u32 = 0o666;
+
 #[derive(Clone, Debug, Copy)]
-pub struct OpenOptions(Flags);
+pub struct OpenOptions {
+    flags: Flags,
+    mode: u32,
+}
 
 impl OpenOptions {
     pub fn new() -> OpenOptions {
-        OpenOptions(Flags::empty())
+        OpenOptions {
+            flags: Flags::empty(),
+            mode: DEFAULT_MODE,
+        }
     }
 
     pub fn read(&mut self, read: bool) -> &mut OpenOptions {
         if read {
-            self.0 |= Flags::READ;
+            self.flags |= Flags::READ;
         } else {
-            self.0.remove(Flags::READ);
+            self.flags.remove(Flags::READ);
         }
         self
     }
 
     pub fn write(&mut self, write: bool) -> &mut OpenOptions {
         if write {
-            self.0 |= Flags::WRITE;
+            self.flags |= Flags::WRITE;
         } else {
-            self.0.remove(Flags::WRITE);
+            self.flags.remove(Flags::WRITE);
         }
         self
     }
 
     pub fn append(&mut self, append: bool) -> &mut OpenOptions {
         if append {
-            self.0 |= Flags::APPEND;
+            self.flags |= Flags::APPEND;
         } else {
-            self.0.remove(Flags::APPEND);
+            self.flags.remove(Flags::APPEND);
         }
         self
     }
 
     pub fn truncate(&mut self, truncate: bool) -> &mut OpenOptions {
         if truncate {
-            self.0 |= Flags::TRUNCATE;
+            self.flags |= Flags::TRUNCATE;
         } else {
-            self.0.remove(Flags::TRUNCATE);
+            self.flags.remove(Flags::TRUNCATE);
         }
         self
     }
 
     pub fn create(&mut self, create: bool) -> &mut OpenOptions {
         if create {
-            self.0 |= Flags::CREATE;
+            self.flags |= Flags::CREATE;
         } else {
-            self.0.remove(Flags::CREATE);
+            self.flags.remove(Flags::CREATE);
         }
         self
     }
 
     pub fn create_new(&mut self, create_new: bool) -> &mut OpenOptions {
         if create_new {
-            self.0 |= Flags::CREATE_NEW;
+            self.flags |= Flags::CREATE_NEW;
         } else {
-            self.0.remove(Flags::CREATE_NEW);
+            self.flags.remove(Flags::CREATE_NEW);
         }
         self
     }
 
     pub async fn open(&self, path: impl AsRef<Path>) -> io::Result<File> {
-        if self.is_invalid() {
-            return Err(io::Error::from(io::ErrorKind::InvalidInput));
-        }
+        self.open_flags().validate()?;
+
+        // The mode only applies to files this call creates.
+        let record_mode = self.mode != DEFAULT_MODE
+            && match CreateFileMode::from(self) {
+                CreateFileMode::CreateNew => true,
+                CreateFileMode::Create => !try_exists(&path).await?,
+                CreateFileMode::NotCreate => false,
+            };
 
         let mut file = open_file(path, self.into(), self.into(), self.is_truncate()).await?;
 
-        if self.0.contains(Flags::APPEND) {
+        if record_mode {
+            meta_store::record_mode(&meta_store::mode_path(&file.path).await?, self.mode).await?;
+        }
+
+        if self.flags.contains(Flags::APPEND) {
+            // Writes go to the end of the file regardless; this only sets
+            // where reads start.
             file.seek(io::SeekFrom::End(0)).await?;
+            file.append = true;
         }
 
         Ok(file)
@@ -104,28 +128,30 @@ impl OpenOptions {
 }
 
 impl OpenOptions {
-    fn is_invalid(&self) -> bool {
-        // Must have at least one of read, write, or append
-        if !self
-            .0
-            .intersects(Flags::READ | Flags::WRITE | Flags::APPEND)
-        {
-            return true;
-        }
+    fn is_truncate(&self) -> bool {
+        self.flags.contains(Flags::TRUNCATE)
+    }
 
-        if self
-            .0
-            .intersects(Flags::CREATE | Flags::CREATE_NEW | Flags::TRUNCATE)
-            && !self.0.intersects(Flags::WRITE | Flags::APPEND)
-        {
-            return true;
+    pub(crate) fn open_flags(&self) -> OpenFlags {
+        OpenFlags {
+            read: self.flags.contains(Flags::READ),
+            write: self.flags.contains(Flags::WRITE),
+            append: self.flags.contains(Flags::APPEND),
+            truncate: self.flags.contains(Flags::TRUNCATE),
+            create: self.flags.contains(Flags::CREATE),
+            create_new: self.flags.contains(Flags::CREATE_NEW),
         }
+    }
+}
 
-        false
+impl OpenOptionsExt for OpenOptions {
+    fn mode(&mut self, mode: u32) -> &mut OpenOptions {
+        self.mode = mode;
+        self
     }
 
-    fn is_truncate(&self) -> bool {
-        self.0.contains(Flags::TRUNCATE)
+    fn custom_flags(&mut self, _flags: i32) -> &mut OpenOptions {
+        self
     }
 }
 
@@ -137,9 +163,9 @@ impl Default for OpenOptions {
 
 impl From<&OpenOptions> for CreateFileMode {
     fn from(options: &OpenOptions) -> Self {
-        if options.0.contains(Flags::CREATE_NEW) {
+        if options.flags.contains(Flags::CREATE_NEW) {
             CreateFileMode::CreateNew
-        } else if options.0.contains(Flags::CREATE) {
+        } else if options.flags.contains(Flags::CREATE) {
             CreateFileMode::Create
         } else {
             CreateFileMode::NotCreate
@@ -149,7 +175,7 @@ impl From<&OpenOptions> for CreateFileMode {
 
 impl From<&OpenOptions> for SyncAccessMode {
     fn from(options: &OpenOptions) -> Self {
-        if options.0.intersects(Flags::WRITE | Flags::APPEND) {
+        if options.flags.intersects(Flags::WRITE | Flags::APPEND) {
             SyncAccessMode::Readwrite
         } else {
             SyncAccessMode::Readonly
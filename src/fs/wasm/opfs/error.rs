@@ -37,6 +37,8 @@ impl From<OpfsError> for io::Error {
                 "InvalidStateError" => io::Error::new(io::ErrorKind::InvalidInput, e.message()),
                 "SecurityError" => io::Error::new(io::ErrorKind::PermissionDenied, e.message()),
                 "AbortError" => io::Error::new(io::ErrorKind::Interrupted, e.message()),
+                "TimeoutError" => io::Error::new(io::ErrorKind::TimedOut, e.message()),
+                "NotSupportedError" => io::Error::new(io::ErrorKind::Unsupported, e.message()),
                 _ => io::Error::other(format!("{}: {}", e.name(), e.message())),
             },
             None => {
@@ -0,0 +1,383 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use futures::{StreamExt, future::LocalBoxFuture};
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{JsFuture, spawn_local, stream::JsStream};
+use web_sys::{
+    File, FileSystemDirectoryHandle, FileSystemFileHandle, FileSystemGetDirectoryOptions,
+    FileSystemGetFileOptions, FileSystemHandle, FileSystemHandleKind,
+};
+
+use super::{
+    super::file::lock_file, meta_store, move_entry::move_entry_untracked,
+    open_file::resolve_parent, opfs_err, retry::create_writable, root::root,
+    symlink::read_link_record, virtualize::reject_reserved,
+};
+
+// ---------------------------------------------------------------------------
+// Emulated hard links
+//
+// The first time a file gets a second link, its data is moved to a blob in
+// `BLOBS_DIR` and every link becomes a regular file named `<name>` +
+// `HARD_LINK_SUFFIX`, holding `HARD_LINK_MAGIC` followed by the blob id.
+// Resolving any link leads to the blob, so writes through one link are
+// visible through all of them.
+//
+// The number of links to a blob is kept next to it in `<id>.links`. Removing
+// the last link removes the blob once no `File` has it open anymore.
+// ---------------------------------------------------------------------------
+
+/// Suffix of the OPFS file that stores a hard link record. Names ending with
+/// it are reserved.
+pub(crate) const HARD_LINK_SUFFIX: &str = ".__hardlink__";
+
+/// Name of the directory at the root of the OPFS holding linked data. It is
+/// hidden from `read_dir`.
+pub(crate) const BLOBS_DIR: &str = ".__tokio_fs_ext_blobs__";
+
+const HARD_LINK_MAGIC: &[u8] = b"tokio-fs-ext:hardlink:v1\n";
+
+pub(crate) fn hard_link_record_name(name: &str) -> String {
+    format!("{name}{HARD_LINK_SUFFIX}")
+}
+
+/// Returns the link name if `entry_name` is the name of a hard link record.
+pub(crate) fn hard_link_name(entry_name: &str) -> Option<&str> {
+    entry_name
+        .strip_suffix(HARD_LINK_SUFFIX)
+        .filter(|name| !name.is_empty())
+}
+
+fn blob_path(id: &str) -> PathBuf {
+    Path::new("/").join(BLOBS_DIR).join(id)
+}
+
+fn links_name(id: &str) -> String {
+    format!("{id}.links")
+}
+
+/// Reads the hard link record `name` in `dir`, returning the virtual path of
+/// the blob it refers to.
+pub(crate) async fn read_hard_link_record(
+    dir: &FileSystemDirectoryHandle,
+    name: &str,
+) -> io::Result<Option<PathBuf>> {
+    let Some(content) = read_entry(dir, &hard_link_record_name(name)).await? else {
+        return Ok(None);
+    };
+    let id = content
+        .strip_prefix(HARD_LINK_MAGIC)
+        .and_then(|id| String::from_utf8(id.to_vec()).ok())
+        .filter(|id| !id.is_empty() && !id.contains('/'))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed hard link record"))?;
+    Ok(Some(blob_path(&id)))
+}
+
+/// Returns the blob `path` refers to if it is a hard link.
+pub(crate) async fn read_hard_link(path: impl AsRef<Path>) -> io::Result<Option<PathBuf>> {
+    let (dir, name) = resolve_parent(path).await?;
+    read_hard_link_record(&dir, &name).await
+}
+
+/// Adds the link `link` to the regular file `original`. Both paths must be
+/// virtualized, and `original` must not go through a symbolic link.
+pub(crate) async fn create_hard_link(original: &Path, link: &Path) -> io::Result<()> {
+    reject_reserved(link)?;
+    let (link_dir, link_name) = resolve_parent(link).await?;
+    if entry_exists(&link_dir, &link_name).await? {
+        return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+    }
+
+    let (dir, name) = resolve_parent(original).await?;
+    let (blob, linked) = match read_hard_link_record(&dir, &name).await? {
+        Some(blob) => {
+            add_links(&blob, 1).await?;
+            (blob, true)
+        }
+        None => {
+            let blob = blob_path(&new_id());
+            add_links(&blob, 2).await?;
+            if let Err(e) = move_to_blob(original, &dir, &name, &blob).await {
+                remove_links(&blob).await;
+                return Err(e);
+            }
+            if let Err(e) = write_record(&dir, &name, &blob).await {
+                unlink_blob(original, &dir, &name, &blob).await;
+                return Err(e);
+            }
+            (blob, false)
+        }
+    };
+
+    if let Err(e) = write_record(&link_dir, &link_name, &blob).await {
+        // Undo the steps above, best effort, so that `original` is left as it
+        // was.
+        if linked {
+            let _ = add_links(&blob, -1).await;
+        } else {
+            let _ = JsFuture::from(dir.remove_entry(&hard_link_record_name(&name))).await;
+            unlink_blob(original, &dir, &name, &blob).await;
+        }
+        return Err(e);
+    }
+    meta_store::record_linked(original, link, &blob);
+    Ok(())
+}
+
+/// Moves the hard link record at `from` to `to`, which must not exist.
+pub(crate) async fn move_hard_link(from: &Path, to: &Path) -> io::Result<()> {
+    let (from_dir, from_name) = resolve_parent(from).await?;
+    let (to_dir, to_name) = resolve_parent(to).await?;
+    let blob = read_hard_link_record(&from_dir, &from_name)
+        .await?
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+
+    write_record(&to_dir, &to_name, &blob).await?;
+    JsFuture::from(from_dir.remove_entry(&hard_link_record_name(&from_name)))
+        .await
+        .map_err(opfs_err)?;
+    meta_store::record_renamed(from, to);
+    Ok(())
+}
+
+/// Removes the hard link record `name` from `dir`, releasing its blob.
+/// Returns `false` if there is no such record.
+pub(crate) async fn remove_hard_link(
+    dir: &FileSystemDirectoryHandle,
+    name: &str,
+) -> io::Result<bool> {
+    let Some(blob) = read_hard_link_record(dir, name).await? else {
+        return Ok(false);
+    };
+    JsFuture::from(dir.remove_entry(&hard_link_record_name(name)))
+        .await
+        .map_err(opfs_err)?;
+    release(&blob).await?;
+    Ok(true)
+}
+
+/// Returns the blobs referred to by every hard link below `dir`. Empty
+/// without a walk if no hard link was ever created.
+pub(crate) async fn hard_links_below(dir: &FileSystemDirectoryHandle) -> io::Result<Vec<PathBuf>> {
+    match blobs_dir(false).await {
+        Ok(_) => collect_hard_links(dir.clone()).await,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Drops one link to `blob`, removing it with its last link.
+pub(crate) async fn release(blob: &Path) -> io::Result<()> {
+    if add_links(blob, -1).await? > 0 {
+        return Ok(());
+    }
+
+    let blobs = blobs_dir(false).await?;
+    JsFuture::from(blobs.remove_entry(&links_name(&blob_id(blob))))
+        .await
+        .map_err(opfs_err)?;
+
+    // Like unlinking an open file on unix, the data lives on until every
+    // `File` on it is dropped.
+    let blob = blob.to_path_buf();
+    spawn_local(async move {
+        let id = blob_id(&blob);
+        let _guard = match lock_file(&blob, None).await {
+            Ok((guard, _)) => guard,
+            Err(err) => {
+                crate::warning!("failed to lock unlinked blob {id}, leaving it in place: {err}");
+                return;
+            }
+        };
+        if let Err(err) = JsFuture::from(blobs.remove_entry(&id)).await {
+            crate::warning!("failed to remove unlinked blob {id}: {}", opfs_err(err));
+        }
+    });
+    Ok(())
+}
+
+fn collect_hard_links(
+    dir: FileSystemDirectoryHandle,
+) -> LocalBoxFuture<'static, io::Result<Vec<PathBuf>>> {
+    Box::pin(async move {
+        let mut blobs = Vec::new();
+        let mut entries = JsStream::from(dir.entries());
+        while let Some(entry) = entries.next().await {
+            let entry = Array::from(&entry.map_err(opfs_err)?);
+            let handle: FileSystemHandle = entry.get(1).unchecked_into();
+            if handle.kind() == FileSystemHandleKind::Directory {
+                blobs.extend(collect_hard_links(handle.unchecked_into()).await?);
+            } else if let Some(name) = entry.get(0).as_string().as_deref().and_then(hard_link_name)
+                && let Some(blob) = read_hard_link_record(&dir, name).await?
+            {
+                blobs.push(blob);
+            }
+        }
+        Ok(blobs)
+    })
+}
+
+async fn move_to_blob(
+    original: &Path,
+    dir: &FileSystemDirectoryHandle,
+    name: &str,
+    blob: &Path,
+) -> io::Result<()> {
+    blobs_dir(true).await?;
+    match move_entry_untracked(original, blob).await {
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {}
+        res => return res,
+    }
+
+    let content = read_entry(dir, name)
+        .await?
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+    write_entry(&blobs_dir(false).await?, &blob_id(blob), &content).await?;
+    JsFuture::from(dir.remove_entry(name))
+        .await
+        .map_err(opfs_err)?;
+    Ok(())
+}
+
+/// Moves the data of `blob` back to `original`, undoing [`move_to_blob`], and
+/// removes its link count. Best effort: errors are ignored.
+async fn unlink_blob(original: &Path, dir: &FileSystemDirectoryHandle, name: &str, blob: &Path) {
+    let moved = match move_entry_untracked(blob, original).await {
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            async {
+                let blobs = blobs_dir(false).await?;
+                let content = read_entry(&blobs, &blob_id(blob))
+                    .await?
+                    .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+                write_entry(dir, name, &content).await?;
+                JsFuture::from(blobs.remove_entry(&blob_id(blob)))
+                    .await
+                    .map_err(opfs_err)?;
+                Ok(())
+            }
+            .await
+        }
+        res => res,
+    };
+    if moved.is_ok() {
+        remove_links(blob).await;
+    }
+}
+
+/// Removes the link count of `blob`, ignoring errors.
+async fn remove_links(blob: &Path) {
+    if let Ok(blobs) = blobs_dir(false).await {
+        let _ = JsFuture::from(blobs.remove_entry(&links_name(&blob_id(blob)))).await;
+    }
+}
+
+/// Adds `delta` to the link count of `blob`, returning the new count.
+async fn add_links(blob: &Path, delta: i64) -> io::Result<i64> {
+    let id = blob_id(blob);
+    let name = links_name(&id);
+    let (_guard, _) = lock_file(blob.with_file_name(&name), None).await?;
+
+    let blobs = blobs_dir(true).await?;
+    let count = match read_entry(&blobs, &name).await? {
+        Some(content) => String::from_utf8(content)
+            .ok()
+            .and_then(|c| c.parse::<i64>().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed link count"))?,
+        None => 0,
+    } + delta;
+    write_entry(&blobs, &name, count.to_string().as_bytes()).await?;
+    Ok(count)
+}
+
+async fn write_record(dir: &FileSystemDirectoryHandle, name: &str, blob: &Path) -> io::Result<()> {
+    let content = [HARD_LINK_MAGIC, blob_id(blob).as_bytes()].concat();
+    write_entry(dir, &hard_link_record_name(name), &content).await
+}
+
+async fn entry_exists(dir: &FileSystemDirectoryHandle, name: &str) -> io::Result<bool> {
+    Ok(JsFuture::from(dir.get_file_handle(name)).await.is_ok()
+        || JsFuture::from(dir.get_directory_handle(name)).await.is_ok()
+        || read_link_record(dir, name).await?.is_some()
+        || read_entry(dir, &hard_link_record_name(name))
+            .await?
+            .is_some())
+}
+
+async fn blobs_dir(create: bool) -> io::Result<FileSystemDirectoryHandle> {
+    let options = FileSystemGetDirectoryOptions::new();
+    options.set_create(create);
+    JsFuture::from(
+        root()
+            .await?
+            .get_directory_handle_with_options(BLOBS_DIR, &options),
+    )
+    .await
+    .map_err(opfs_err)
+    .map(|handle| handle.unchecked_into())
+}
+
+async fn read_entry(dir: &FileSystemDirectoryHandle, name: &str) -> io::Result<Option<Vec<u8>>> {
+    let options = FileSystemGetFileOptions::new();
+    options.set_create(false);
+    let handle: FileSystemFileHandle =
+        match JsFuture::from(dir.get_file_handle_with_options(name, &options))
+            .await
+            .map_err(opfs_err)
+        {
+            Ok(handle) => handle.unchecked_into(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+    let file: File = JsFuture::from(handle.get_file())
+        .await
+        .map_err(opfs_err)?
+        .unchecked_into();
+    let content = JsFuture::from(file.array_buffer())
+        .await
+        .map_err(opfs_err)?;
+    Ok(Some(Uint8Array::new(&content).to_vec()))
+}
+
+async fn write_entry(
+    dir: &FileSystemDirectoryHandle,
+    name: &str,
+    content: &[u8],
+) -> io::Result<()> {
+    let options = FileSystemGetFileOptions::new();
+    options.set_create(true);
+    let handle: FileSystemFileHandle =
+        JsFuture::from(dir.get_file_handle_with_options(name, &options))
+            .await
+            .map_err(opfs_err)?
+            .unchecked_into();
+
+    let stream = create_writable(&handle).await?;
+    JsFuture::from(
+        stream
+            .write_with_buffer_source(&Uint8Array::from(content))
+            .map_err(opfs_err)?,
+    )
+    .await
+    .map_err(opfs_err)?;
+    JsFuture::from(stream.close()).await.map_err(opfs_err)?;
+    Ok(())
+}
+
+fn blob_id(blob: &Path) -> String {
+    blob.file_name()
+        .map(|id| id.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn new_id() -> String {
+    format!(
+        "{:x}-{:08x}",
+        js_sys::Date::now() as u64,
+        (js_sys::Math::random() * f64::from(u32::MAX)) as u32
+    )
+}
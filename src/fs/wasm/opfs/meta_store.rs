@@ -0,0 +1,524 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    io,
+    ops::Bound,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use futures::lock::Mutex;
+use js_sys::Uint8Array;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{JsFuture, spawn_local};
+use web_sys::{File, FileSystemFileHandle, FileSystemGetFileOptions};
+
+use super::{
+    super::{LockMode, lock_mode},
+    CreateFileMode,
+    open_file::resolve_file,
+    opfs_err,
+    retry::create_writable,
+    root::root,
+    web_lock,
+};
+
+// ---------------------------------------------------------------------------
+// Sidecar metadata store
+//
+// OPFS only records the size and modification time of files. Everything else
+// `Metadata` reports (creation times, directory modification times, the
+// read-only bit and mode) lives in a single reserved file at the root of the OPFS,
+// keyed by virtual path. Writing to a file does not touch the store; its
+// modification time is the `lastModified` OPFS reports.
+//
+// Updates are applied to an in-memory copy right away and persisted in the
+// background, coalescing bursts of changes into one write. Persisting merges
+// the pending changes into whatever is on disk under a Web Lock, so contexts
+// sharing the origin do not discard each other's records; readers reload the
+// copy whenever the file changed since it was last read. Changes that fail to
+// persist stay pending and are retried with the next update.
+// ---------------------------------------------------------------------------
+
+/// Name of the OPFS file backing the store. It is hidden from `read_dir`.
+pub(crate) const META_STORE_NAME: &str = ".__tokio_fs_ext_meta__";
+
+const HEADER: &str = "tokio-fs-ext:meta:v1";
+
+/// What the store knows about one entry. Times are in milliseconds since
+/// epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct MetaRecord {
+    pub(crate) created: Option<u64>,
+    pub(crate) modified: Option<u64>,
+    pub(crate) readonly: bool,
+    /// Mode bits set with `OpenOptionsExt::mode` or `set_permissions`.
+    pub(crate) mode: Option<u32>,
+}
+
+enum Change {
+    Set(PathBuf, MetaRecord),
+    Remove(PathBuf),
+    Rename(PathBuf, PathBuf),
+}
+
+#[derive(Default)]
+struct Store {
+    records: BTreeMap<PathBuf, MetaRecord>,
+    /// `lastModified` of the backing file when it was last read or written.
+    loaded: Option<f64>,
+    /// Changes not yet persisted, in the order they were made.
+    pending: Vec<Change>,
+    flushing: bool,
+    /// Held while persisting, so that the changes of this context are written
+    /// in order.
+    persisting: Rc<Mutex<()>>,
+    handle: Option<FileSystemFileHandle>,
+}
+
+thread_local! {
+    static STORE: RefCell<Store> = RefCell::new(Store::default());
+}
+
+/// Returns the record of `path`, which must be virtualized. Call [`load`]
+/// first to pick up changes made by other contexts.
+pub(crate) fn get(path: &Path) -> Option<MetaRecord> {
+    STORE.with(|store| store.borrow().records.get(path).copied())
+}
+
+/// Reads the backing file if it changed since it was last read.
+pub(crate) async fn load() -> io::Result<()> {
+    let file = backing_file().await?;
+    if STORE.with(|store| store.borrow().loaded) == Some(file.last_modified()) {
+        return Ok(());
+    }
+
+    let mut records = parse(&read_text(&file).await?);
+    STORE.with(|store| {
+        let mut store = store.borrow_mut();
+        for change in &store.pending {
+            apply(&mut records, change);
+        }
+        store.records = records;
+        store.loaded = Some(file.last_modified());
+    });
+    Ok(())
+}
+
+/// Returns the record of `path` like [`get`], with the read-only bit and mode
+/// of the file it leads to, see [`mode_path`].
+pub(crate) async fn get_followed(path: &Path) -> Option<MetaRecord> {
+    let record = get(path);
+    match mode_path(path).await {
+        Ok(target) if target != path => {
+            let target = get(&target).unwrap_or_default();
+            Some(MetaRecord {
+                readonly: target.readonly,
+                mode: target.mode,
+                ..record.unwrap_or_default()
+            })
+        }
+        _ => record,
+    }
+}
+
+/// Returns the path the mode of `path` is recorded under: the file it leads
+/// to after following symbolic and hard links, so that every path to a file
+/// shares its mode. `path` itself if it does not lead to a file.
+pub(crate) async fn mode_path(path: &Path) -> io::Result<PathBuf> {
+    match resolve_file(path, CreateFileMode::NotCreate).await {
+        Ok((_, target)) => Ok(target),
+        // Missing, or a directory.
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::InvalidData
+            ) =>
+        {
+            Ok(path.to_path_buf())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Fails with `PermissionDenied` if the file `path` leads to is marked
+/// read-only.
+pub(crate) async fn ensure_writable(path: &Path) -> io::Result<()> {
+    // Without a readable store no entry is known to be read-only.
+    let _ = load().await;
+    match get(&mode_path(path).await?) {
+        Some(record) if record.readonly => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "file is read-only",
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Records that `path` was just created, which also modifies its parent.
+pub(crate) fn record_created(path: &Path) {
+    let now = now();
+    update(|records| {
+        let mut changes = vec![Change::Set(
+            path.to_path_buf(),
+            MetaRecord {
+                created: Some(now),
+                modified: Some(now),
+                readonly: false,
+                mode: None,
+            },
+        )];
+        changes.extend(touch_parent(records, path, now));
+        changes
+    });
+}
+
+/// Sets the mode of `path`, which is read-only if `mode` has no write bits.
+/// `path` must be the [`mode_path`] of the file.
+///
+/// Unlike other updates, this persists the mode before returning, since it is
+/// enforced in every context sharing the origin. Until then, and if it fails,
+/// the old mode stays in effect.
+pub(crate) async fn record_mode(path: &Path, mode: u32) -> io::Result<()> {
+    persist_pending(Some(path), |record| MetaRecord {
+        readonly: mode & 0o222 == 0,
+        mode: Some(mode),
+        ..record
+    })
+    .await
+}
+
+/// Records that `link` was just added as another link to `original`, whose
+/// data is now in `blob`, which modifies the parent of `link`.
+pub(crate) fn record_linked(original: &Path, link: &Path, blob: &Path) {
+    let now = now();
+    update(|records| {
+        let record = records.get(original).copied().unwrap_or_default();
+        let mut changes = vec![Change::Set(link.to_path_buf(), record)];
+        // The mode of the first link becomes the mode of the blob.
+        if !records.contains_key(blob) {
+            changes.push(Change::Set(
+                blob.to_path_buf(),
+                MetaRecord {
+                    readonly: record.readonly,
+                    mode: record.mode,
+                    ..MetaRecord::default()
+                },
+            ));
+        }
+        changes.extend(touch_parent(records, link, now));
+        changes
+    });
+}
+
+/// Forgets `path` and everything below it, which modifies its parent.
+pub(crate) fn record_removed(path: &Path) {
+    let now = now();
+    update(|records| {
+        let mut changes = vec![Change::Remove(path.to_path_buf())];
+        changes.extend(touch_parent(records, path, now));
+        changes
+    });
+}
+
+/// Moves the records of `from` and everything below it to `to`, which
+/// modifies both parents.
+pub(crate) fn record_renamed(from: &Path, to: &Path) {
+    let now = now();
+    update(|records| {
+        let mut changes = vec![Change::Rename(from.to_path_buf(), to.to_path_buf())];
+        changes.extend(touch_parent(records, from, now));
+        if from.parent() != to.parent() {
+            changes.extend(touch_parent(records, to, now));
+        }
+        changes
+    });
+}
+
+fn touch_parent(records: &BTreeMap<PathBuf, MetaRecord>, path: &Path, now: u64) -> Option<Change> {
+    let parent = path.parent()?;
+    let record = records.get(parent).copied().unwrap_or_default();
+    Some(Change::Set(
+        parent.to_path_buf(),
+        MetaRecord {
+            modified: Some(now),
+            ..record
+        },
+    ))
+}
+
+fn update(changes: impl FnOnce(&BTreeMap<PathBuf, MetaRecord>) -> Vec<Change>) {
+    let start_flush = STORE.with(|store| {
+        let mut store = store.borrow_mut();
+        for change in changes(&store.records) {
+            apply(&mut store.records, &change);
+            store.pending.push(change);
+        }
+        !std::mem::replace(&mut store.flushing, true)
+    });
+
+    if start_flush {
+        spawn_local(flush());
+    }
+}
+
+async fn flush() {
+    loop {
+        let done = STORE.with(|store| {
+            let mut store = store.borrow_mut();
+            let done = store.pending.is_empty();
+            if done {
+                store.flushing = false;
+            }
+            done
+        });
+        if done {
+            return;
+        }
+        if let Err(err) = persist_pending(None, |record| record).await {
+            // The changes stay pending; the next update tries again.
+            crate::warning!("failed to persist file metadata: {err}");
+            STORE.with(|store| store.borrow_mut().flushing = false);
+            return;
+        }
+    }
+}
+
+/// Persists the pending changes, followed by setting the record of `path` to
+/// what `set` makes of it if a path is given. That last change only reaches the
+/// in-memory copy once it is persisted; the pending changes stay pending if
+/// persisting fails.
+async fn persist_pending(
+    path: Option<&Path>,
+    set: impl FnOnce(MetaRecord) -> MetaRecord,
+) -> io::Result<()> {
+    let persisting = STORE.with(|store| store.borrow().persisting.clone());
+    let _persisting = persisting.lock().await;
+
+    let mut changes = STORE.with(|store| std::mem::take(&mut store.borrow_mut().pending));
+    let taken = changes.len();
+    if let Some(path) = path {
+        let record = get(path).unwrap_or_default();
+        changes.push(Change::Set(path.to_path_buf(), set(record)));
+    } else if changes.is_empty() {
+        return Ok(());
+    }
+
+    let res = persist(&changes).await;
+    if res.is_err() {
+        changes.truncate(taken);
+        STORE.with(|store| {
+            let mut store = store.borrow_mut();
+            changes.append(&mut store.pending);
+            store.pending = changes;
+        });
+    }
+    res
+}
+
+async fn persist(changes: &[Change]) -> io::Result<()> {
+    // Other contexts merge their changes into the same file, so the
+    // read-merge-write must not interleave with theirs. The name is not an
+    // absolute path, so it never clashes with the lock of a file.
+    let timeout = match lock_mode() {
+        LockMode::WebLocks { timeout } => timeout,
+        LockMode::Local => None,
+    };
+    let _lock = match web_lock::request_lock(Path::new(META_STORE_NAME), true, timeout).await {
+        Ok(guard) => Some(guard),
+        // Merging still keeps most concurrent changes.
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => None,
+        Err(e) => return Err(e),
+    };
+
+    let file = backing_file().await?;
+    let handle = backing_handle().await?;
+
+    let mut records = parse(&read_text(&file).await?);
+    for change in changes {
+        apply(&mut records, change);
+    }
+
+    let stream = create_writable(&handle).await?;
+    let content = Uint8Array::from(serialize(&records).as_bytes());
+    JsFuture::from(
+        stream
+            .write_with_buffer_source(&content)
+            .map_err(opfs_err)?,
+    )
+    .await
+    .map_err(opfs_err)?;
+    JsFuture::from(stream.close()).await.map_err(opfs_err)?;
+
+    // What was just written is at least as recent as the in-memory copy,
+    // except for changes made while persisting.
+    let written: File = JsFuture::from(handle.get_file())
+        .await
+        .map_err(opfs_err)?
+        .unchecked_into();
+    STORE.with(|store| {
+        let mut store = store.borrow_mut();
+        for change in &store.pending {
+            apply(&mut records, change);
+        }
+        store.records = records;
+        store.loaded = Some(written.last_modified());
+    });
+    Ok(())
+}
+
+fn apply(records: &mut BTreeMap<PathBuf, MetaRecord>, change: &Change) {
+    match change {
+        Change::Set(path, record) => {
+            records.insert(path.clone(), *record);
+        }
+        Change::Remove(path) => {
+            take_subtree(records, path);
+        }
+        Change::Rename(from, to) => {
+            take_subtree(records, to);
+            for (path, record) in take_subtree(records, from) {
+                let path = match path.strip_prefix(from) {
+                    Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+                    _ => to.clone(),
+                };
+                records.insert(path, record);
+            }
+        }
+    }
+}
+
+fn take_subtree(
+    records: &mut BTreeMap<PathBuf, MetaRecord>,
+    root: &Path,
+) -> Vec<(PathBuf, MetaRecord)> {
+    // Descendants do not necessarily sort right after `root` (`/a/b` sorts
+    // after `/a-b`), so the range is filtered by component prefix.
+    let paths: Vec<PathBuf> = records
+        .range::<Path, _>((Bound::Included(root), Bound::Unbounded))
+        .map(|(path, _)| path)
+        .filter(|path| path.starts_with(root))
+        .cloned()
+        .collect();
+    paths
+        .into_iter()
+        .filter_map(|path| records.remove(&path).map(|record| (path, record)))
+        .collect()
+}
+
+// One record per line: the escaped path, then tab-separated fields. Fields
+// missing at the end of a line take their default value, so new fields can be
+// appended without a format change.
+fn serialize(records: &BTreeMap<PathBuf, MetaRecord>) -> String {
+    let mut out = format!("{HEADER}\n");
+    for (path, record) in records {
+        let time = |t: Option<u64>| t.map_or_else(|| "-".to_string(), |t| t.to_string());
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            escape(&path.to_string_lossy()),
+            time(record.created),
+            time(record.modified),
+            u8::from(record.readonly),
+            record
+                .mode
+                .map_or_else(|| "-".to_string(), |mode| format!("{mode:o}")),
+        ));
+    }
+    out
+}
+
+fn parse(content: &str) -> BTreeMap<PathBuf, MetaRecord> {
+    let mut lines = content.lines();
+    if lines.next() != Some(HEADER) {
+        return BTreeMap::new();
+    }
+
+    lines
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let path = PathBuf::from(unescape(fields.next()?));
+            let mut time = || fields.next().and_then(|t| t.parse().ok());
+            let created = time();
+            let modified = time();
+            let readonly = fields.next() == Some("1");
+            let mode = fields
+                .next()
+                .and_then(|mode| u32::from_str_radix(mode, 8).ok());
+            Some((
+                path,
+                MetaRecord {
+                    created,
+                    modified,
+                    readonly,
+                    mode,
+                },
+            ))
+        })
+        .collect()
+}
+
+fn escape(path: &str) -> String {
+    path.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+}
+
+fn unescape(path: &str) -> String {
+    let mut out = String::with_capacity(path.len());
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some(c) => out.push(c),
+            None => {}
+        }
+    }
+    out
+}
+
+async fn backing_handle() -> io::Result<FileSystemFileHandle> {
+    if let Some(handle) = STORE.with(|store| store.borrow().handle.clone()) {
+        return Ok(handle);
+    }
+
+    let options = FileSystemGetFileOptions::new();
+    options.set_create(true);
+    let handle: FileSystemFileHandle = JsFuture::from(
+        root()
+            .await?
+            .get_file_handle_with_options(META_STORE_NAME, &options),
+    )
+    .await
+    .map_err(opfs_err)?
+    .unchecked_into();
+    STORE.with(|store| store.borrow_mut().handle = Some(handle.clone()));
+    Ok(handle)
+}
+
+async fn backing_file() -> io::Result<File> {
+    match JsFuture::from(backing_handle().await?.get_file()).await {
+        Ok(file) => Ok(file.unchecked_into()),
+        Err(err) => {
+            // The file may have been removed; look it up again next time.
+            STORE.with(|store| store.borrow_mut().handle = None);
+            Err(opfs_err(err))
+        }
+    }
+}
+
+async fn read_text(file: &File) -> io::Result<String> {
+    JsFuture::from(file.text())
+        .await
+        .map_err(opfs_err)?
+        .as_string()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed metadata store"))
+}
+
+fn now() -> u64 {
+    js_sys::Date::now() as u64
+}
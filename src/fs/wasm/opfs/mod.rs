@@ -1,17 +1,31 @@
 mod dir_handle_cache;
 mod error;
+mod hard_link;
+pub(super) mod meta_store;
+mod move_entry;
 mod open_dir;
 mod open_file;
 mod options;
 mod remove;
+mod retry;
 mod root;
+mod symlink;
 mod virtualize;
 #[cfg(feature = "opfs_watch")]
 pub mod watch;
+pub(super) mod web_lock;
 
 pub(super) use error::opfs_err;
+pub(super) use hard_link::{
+    BLOBS_DIR, create_hard_link, hard_link_name, move_hard_link, read_hard_link,
+};
+pub(super) use move_entry::move_entry;
 pub(super) use open_dir::open_dir;
 pub(super) use open_file::{lock_and_handle, open_file, resolve_file_handle};
 pub(super) use options::{CreateFileMode, OpenDirType, SyncAccessMode};
 pub(super) use remove::remove;
-pub(super) use virtualize::virtualize;
+pub(super) use retry::create_writable;
+#[cfg(feature = "opfs_offload")]
+pub(super) use retry::sleep;
+pub(super) use symlink::{canonicalize, create_link, link_name, read_link};
+pub(super) use virtualize::{reject_reserved, virtualize};
@@ -0,0 +1,57 @@
+use std::{io, path::Path};
+
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use super::{
+    CreateFileMode, OpenDirType,
+    dir_handle_cache::remove_cached_dir_handle,
+    meta_store, open_dir,
+    open_file::{resolve_file_handle, resolve_parent},
+    opfs_err,
+};
+
+/// Moves the entry at `from` to `to` with `FileSystemHandle.move()`.
+///
+/// Both paths must already be virtualized. Returns `Unsupported` when the
+/// browser does not implement `move()` for this kind of handle, so callers can
+/// fall back to copying.
+#[cfg_attr(feature = "opfs_tracing", tracing::instrument(level = "trace", fields(from = %from.to_string_lossy(), to = %to.to_string_lossy())))]
+pub(crate) async fn move_entry(from: &Path, to: &Path) -> io::Result<()> {
+    move_entry_untracked(from, to).await?;
+    meta_store::record_renamed(from, to);
+    Ok(())
+}
+
+/// Like [`move_entry`], without updating the sidecar metadata store.
+pub(super) async fn move_entry_untracked(from: &Path, to: &Path) -> io::Result<()> {
+    let handle: JsValue = match resolve_file_handle(from, CreateFileMode::NotCreate).await {
+        Ok(handle) => handle.into(),
+        Err(_) => open_dir(from, OpenDirType::NotCreate).await?.into(),
+    };
+
+    let move_fn = Reflect::get(&handle, &"move".into()).map_err(opfs_err)?;
+    let Some(move_fn) = move_fn.dyn_ref::<Function>() else {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "FileSystemHandle.move() is not supported",
+        ));
+    };
+
+    let (dest_dir, name) = resolve_parent(to).await?;
+
+    let promise = move_fn
+        .call2(&handle, &dest_dir, &JsValue::from_str(&name))
+        .map_err(opfs_err)?
+        .unchecked_into::<Promise>();
+
+    let res = JsFuture::from(promise).await.map_err(opfs_err);
+
+    // The moved handle (and every cached handle below it) now points to the
+    // new location, so the cache entries for both subtrees are stale.
+    remove_cached_dir_handle(&from.to_path_buf(), true);
+    remove_cached_dir_handle(&to.to_path_buf(), true);
+
+    res.map(|_| ())
+}
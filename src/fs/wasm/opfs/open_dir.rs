@@ -12,9 +12,10 @@ use crate::fs::wasm::current_dir::current_dir;
 
 use super::{
     dir_handle_cache::{get_cached_dir_handle, remove_cached_dir_handle, set_cached_dir_handle},
-    opfs_err,
+    meta_store, opfs_err,
     options::OpenDirType,
     root::root,
+    symlink::{MAX_LINK_FOLLOWS, follow, loop_err, read_link_record},
     virtualize,
 };
 
@@ -29,7 +30,7 @@ pub(crate) async fn open_dir(
         return Ok(handle);
     }
 
-    match open_dir_inner(&virt, r#type).await {
+    match open_dir_inner(&virt, r#type, 0).await {
         Ok(handle) => Ok(handle),
         Err(e) if e.kind() == io::ErrorKind::InvalidInput => {
             // InvalidInput maps from OPFS InvalidStateError — a cached
@@ -37,7 +38,7 @@ pub(crate) async fn open_dir(
             // cache and retry once from a fresh root.
             remove_cached_dir_handle(&PathBuf::from("/"), true);
             super::root::clear_cached_root();
-            open_dir_inner(&virt, r#type).await
+            open_dir_inner(&virt, r#type, 0).await
         }
         Err(e) => Err(e),
     }
@@ -46,6 +47,7 @@ pub(crate) async fn open_dir(
 async fn open_dir_inner(
     virt: &Path,
     r#type: OpenDirType,
+    follows: usize,
 ) -> io::Result<FileSystemDirectoryHandle> {
     let components: Vec<Cow<'_, str>> = virt
         .components()
@@ -79,7 +81,31 @@ async fn open_dir_inner(
                 }
             };
 
-            let dir_handle = get_dir_handle(&dir_handle, c, create).await?;
+            let dir_handle = match get_dir_handle(&dir_handle, c, false).await {
+                Ok(handle) => handle,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    match read_link_record(&dir_handle, c).await? {
+                        Some(record) => {
+                            // Restart from the link target, without caching
+                            // anything under the link's own path.
+                            if follows >= MAX_LINK_FOLLOWS {
+                                return Err(loop_err());
+                            }
+                            let mut target = follow(&cur_virt, &record.target)?;
+                            target.extend(components[i + 1..].iter().map(|c| c.as_ref()));
+                            return Box::pin(open_dir_inner(&target, r#type, follows + 1)).await;
+                        }
+                        None if create => {
+                            virtualize::reject_reserved(&cur_virt)?;
+                            let handle = get_dir_handle(&dir_handle, c, true).await?;
+                            meta_store::record_created(&cur_virt);
+                            handle
+                        }
+                        None => return Err(e),
+                    }
+                }
+                Err(e) => return Err(e),
+            };
 
             set_cached_dir_handle(cur_virt.clone(), dir_handle.clone());
             dir_handle
@@ -1,4 +1,7 @@
-use std::{io, path::Path};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use js_sys::{Function, Promise, Reflect};
 use wasm_bindgen::{JsCast, JsValue};
@@ -13,13 +16,18 @@ use web_sys::{
 use super::{
     super::{
         File,
-        file::{FileLockGuard, lock_file, set_lock_handle},
+        file::{
+            DEFAULT_MAX_BUF_SIZE, FileLockGuard, invalidate_read_ahead, lock_file, set_lock_handle,
+        },
     },
     OpenDirType,
     error::opfs_err,
-    open_dir,
+    hard_link::read_hard_link_record,
+    meta_store, open_dir,
     options::{CreateFileMode, CreateSyncAccessHandleOptions, SyncAccessMode},
+    retry::retry_busy,
     root::root,
+    symlink::{MAX_LINK_FOLLOWS, follow, loop_err, read_link_record},
     virtualize,
 };
 
@@ -30,21 +38,33 @@ pub(crate) async fn open_file(
     mode: SyncAccessMode,
     truncate: bool,
 ) -> io::Result<File> {
+    let virt = virtualize::virtualize(&path)?;
+    if mode == SyncAccessMode::Readwrite {
+        meta_store::ensure_writable(&virt).await?;
+    }
     let (handle, _lock, sync_access_handle) = get_file_and_lock(&path, create, mode).await?;
 
     if truncate {
         sync_access_handle.truncate_with_u32(0).map_err(opfs_err)?;
         sync_access_handle.flush().map_err(opfs_err)?;
+        invalidate_read_ahead(&_lock.path);
         // On error the `_lock` guard is dropped, which decrements
         // ref_count and closes the cached handle when it reaches 0.
         // We must NOT close the SyncAccessHandle here because other
         // `File` objects may already be sharing it.
     }
     Ok(File {
+        path: virt,
         handle,
         sync_access_handle,
         pos: Some(0),
         mode,
+        read_ahead: Default::default(),
+        write_behind: Default::default(),
+        buf_capacity: 0,
+        append: false,
+        max_buf_size: DEFAULT_MAX_BUF_SIZE,
+        advisory: Default::default(),
         _lock,
     })
 }
@@ -61,15 +81,24 @@ pub(crate) async fn lock_and_handle(
     if matches!(create, CreateFileMode::CreateNew) {
         // Safety: `CreateNew` relies on a check-then-act sequence in `resolve_file_handle`.
         // We must hold the lock *before* checking existence to ensure atomicity within the app.
-        let (lock, sync_handle) = lock_file(&path, mode).await;
+        let (lock, sync_handle) = lock_file(&path, mode).await?;
         // Check-then-act sequence happens here, protected by the lock
         let file_handle = resolve_file_handle(&path, create).await?;
         Ok((lock, sync_handle, file_handle))
     } else {
         // optimistically race for performance in `Open` (NotCreate) and `Create` (Overwrite/Open)
-        let ((lock, sync_handle), file_handle_res) =
-            futures::join!(lock_file(&path, mode), resolve_file_handle(&path, create));
-        let file_handle = file_handle_res?;
+        let (lock_res, file_res) =
+            futures::join!(lock_file(&path, mode), resolve_file(&path, create));
+        let (lock, sync_handle) = lock_res?;
+        let (file_handle, resolved) = file_res?;
+
+        // A link was followed: lock the file it leads to instead, so every
+        // path to the same file shares one `SyncAccessHandle`.
+        if resolved != virtualize::virtualize(&path)? {
+            drop(lock);
+            let (lock, sync_handle) = lock_file(&resolved, mode).await?;
+            return Ok((lock, sync_handle, file_handle));
+        }
         Ok((lock, sync_handle, file_handle))
     }
 }
@@ -90,7 +119,7 @@ pub(crate) async fn get_file_and_lock(
         h
     } else {
         let h = create_sync_access_handle(&file_handle, access_mode).await?;
-        set_lock_handle(&path, h.clone());
+        set_lock_handle(&lock.path, h.clone());
         h
     };
 
@@ -101,19 +130,54 @@ pub(crate) async fn resolve_file_handle(
     path: impl AsRef<Path>,
     create: CreateFileMode,
 ) -> io::Result<FileSystemFileHandle> {
-    let (dir_entry, name) = resolve_parent(path).await?;
+    resolve_file(path, create).await.map(|(handle, _)| handle)
+}
 
-    match create {
-        CreateFileMode::Create => get_raw_handle(&name, &dir_entry, true).await,
-        CreateFileMode::CreateNew => match get_raw_handle(&name, &dir_entry, false).await {
-            Ok(_) => Err(io::Error::from(io::ErrorKind::AlreadyExists)),
-            Err(_) => get_raw_handle(&name, &dir_entry, true).await,
-        },
-        CreateFileMode::NotCreate => get_raw_handle(&name, &dir_entry, false).await,
+/// Like [`resolve_file_handle`], also returning the virtual path of the file
+/// after following links.
+pub(crate) async fn resolve_file(
+    path: impl AsRef<Path>,
+    create: CreateFileMode,
+) -> io::Result<(FileSystemFileHandle, PathBuf)> {
+    let mut virt = virtualize::virtualize(path)?;
+
+    for _ in 0..=MAX_LINK_FOLLOWS {
+        let (dir_entry, name) = resolve_parent(&virt).await?;
+
+        let e = match get_raw_handle(&name, &dir_entry, false).await {
+            Ok(_) if create == CreateFileMode::CreateNew => {
+                return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+            }
+            Ok(handle) => return Ok((handle, virt)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => e,
+            Err(e) => return Err(e),
+        };
+
+        let link = match read_link_record(&dir_entry, &name).await? {
+            Some(record) => Some(follow(&virt, &record.target)?),
+            None => read_hard_link_record(&dir_entry, &name).await?,
+        };
+        match link {
+            Some(_) if create == CreateFileMode::CreateNew => {
+                return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+            }
+            Some(target) => virt = target,
+            None if create == CreateFileMode::NotCreate => return Err(e),
+            None => {
+                virtualize::reject_reserved(&virt)?;
+                let handle = get_raw_handle(&name, &dir_entry, true).await?;
+                meta_store::record_created(&virt);
+                return Ok((handle, virt));
+            }
+        }
     }
+
+    Err(loop_err())
 }
 
-async fn resolve_parent(path: impl AsRef<Path>) -> io::Result<(FileSystemDirectoryHandle, String)> {
+pub(super) async fn resolve_parent(
+    path: impl AsRef<Path>,
+) -> io::Result<(FileSystemDirectoryHandle, String)> {
     let virt = virtualize::virtualize(path)?;
     let parent = virt.parent();
 
@@ -148,18 +212,22 @@ async fn create_sync_access_handle(
 ) -> io::Result<FileSystemSyncAccessHandle> {
     let file_handle_js_value = JsValue::from(handle);
 
-    let promise = Reflect::get(&file_handle_js_value, &"createSyncAccessHandle".into())
-        .map_err(opfs_err)?
-        .unchecked_into::<Function>()
-        .call1(
-            &file_handle_js_value,
-            &CreateSyncAccessHandleOptions::from(mode).into(),
-        )
-        .map_err(opfs_err)?
-        .unchecked_into::<Promise>();
-
-    JsFuture::from(promise)
-        .await
-        .map_err(opfs_err)
-        .map(|v| v.unchecked_into::<FileSystemSyncAccessHandle>())
+    // Fails with `WouldBlock` while another context has the file open.
+    retry_busy(|| async {
+        let promise = Reflect::get(&file_handle_js_value, &"createSyncAccessHandle".into())
+            .map_err(opfs_err)?
+            .unchecked_into::<Function>()
+            .call1(
+                &file_handle_js_value,
+                &CreateSyncAccessHandleOptions::from(mode).into(),
+            )
+            .map_err(opfs_err)?
+            .unchecked_into::<Promise>();
+
+        JsFuture::from(promise)
+            .await
+            .map_err(opfs_err)
+            .map(|v| v.unchecked_into::<FileSystemSyncAccessHandle>())
+    })
+    .await
 }
@@ -1,10 +1,16 @@
 use std::{io, path::Path};
 
+use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::FileSystemRemoveOptions;
 
 use super::{
-    OpenDirType, dir_handle_cache::remove_cached_dir_handle, open_dir, opfs_err, root::root,
+    OpenDirType,
+    dir_handle_cache::remove_cached_dir_handle,
+    hard_link::{hard_links_below, release, remove_hard_link},
+    meta_store, open_dir, opfs_err,
+    root::root,
+    symlink::link_record_name,
     virtualize,
 };
 
@@ -24,14 +30,37 @@ pub(crate) async fn remove(path: impl AsRef<Path>, recursive: bool) -> io::Resul
         None => root().await?,
     };
 
+    // Hard links inside a removed directory release their data too.
+    let mut blobs = Vec::new();
+    if recursive && let Ok(dir) = JsFuture::from(dir_entry.get_directory_handle(&name)).await {
+        blobs = hard_links_below(&dir.unchecked_into()).await?;
+    }
+
     let options = FileSystemRemoveOptions::new();
     options.set_recursive(recursive);
 
-    JsFuture::from(dir_entry.remove_entry_with_options(&name, &options))
+    match JsFuture::from(dir_entry.remove_entry_with_options(&name, &options))
         .await
-        .map_err(opfs_err)?;
+        .map_err(opfs_err)
+    {
+        Ok(_) => remove_cached_dir_handle(&virt, recursive),
+        // Links are removed themselves, never their targets.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            if JsFuture::from(dir_entry.remove_entry(&link_record_name(&name)))
+                .await
+                .is_err()
+                && !remove_hard_link(&dir_entry, &name).await?
+            {
+                return Err(e);
+            }
+        }
+        Err(e) => return Err(e),
+    }
 
-    remove_cached_dir_handle(&virt, recursive);
+    for blob in blobs {
+        release(&blob).await?;
+    }
 
+    meta_store::record_removed(&virt);
     Ok(())
 }
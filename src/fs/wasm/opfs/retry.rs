@@ -0,0 +1,70 @@
+use std::{future::Future, io, time::Duration};
+
+use js_sys::Promise;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{DedicatedWorkerGlobalScope, FileSystemFileHandle, FileSystemWritableFileStream};
+
+use super::{
+    super::{RetryPolicy, retry_policy},
+    opfs_err,
+};
+
+/// Runs `op` until it succeeds, fails with anything but `WouldBlock`, or the
+/// current [`RetryPolicy`] gives up.
+pub(crate) async fn retry_busy<T, Fut>(mut op: impl FnMut() -> Fut) -> io::Result<T>
+where
+    Fut: Future<Output = io::Result<T>>,
+{
+    let RetryPolicy {
+        max_attempts,
+        initial_backoff,
+        max_backoff,
+        deadline,
+    } = retry_policy();
+    let start = js_sys::Date::now();
+    let mut backoff = initial_backoff;
+    let mut attempts = 1;
+
+    loop {
+        let err = match op().await {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => e,
+            res => return res,
+        };
+
+        let elapsed = Duration::from_millis((js_sys::Date::now() - start) as u64);
+        if attempts >= max_attempts || deadline.is_some_and(|d| elapsed + backoff > d) {
+            return Err(err);
+        }
+        sleep(backoff).await?;
+        backoff = (backoff * 2).min(max_backoff);
+        attempts += 1;
+    }
+}
+
+/// Opens a writable stream on `handle`, retrying while the file is busy.
+pub(crate) async fn create_writable(
+    handle: &FileSystemFileHandle,
+) -> io::Result<FileSystemWritableFileStream> {
+    retry_busy(|| async {
+        JsFuture::from(handle.create_writable())
+            .await
+            .map_err(opfs_err)
+            .map(|stream| stream.unchecked_into())
+    })
+    .await
+}
+
+pub(crate) async fn sleep(duration: Duration) -> io::Result<()> {
+    let global = DedicatedWorkerGlobalScope::from(JsValue::from(js_sys::global()));
+    let mut res = Ok(0);
+    let timer = Promise::new(&mut |resolve, _| {
+        res = global.set_timeout_with_callback_and_timeout_and_arguments_0(
+            &resolve,
+            duration.as_millis().min(i32::MAX as u128) as i32,
+        );
+    });
+    res.map_err(opfs_err)?;
+    JsFuture::from(timer).await.map_err(opfs_err)?;
+    Ok(())
+}
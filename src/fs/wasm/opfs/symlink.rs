@@ -0,0 +1,212 @@
+use std::{
+    collections::VecDeque,
+    ffi::OsString,
+    io,
+    path::{Component, Path, PathBuf},
+};
+
+use js_sys::Uint8Array;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{File, FileSystemDirectoryHandle, FileSystemFileHandle, FileSystemGetFileOptions};
+
+use super::{
+    OpenDirType, dir_handle_cache::get_cached_dir_handle, hard_link::read_hard_link_record,
+    meta_store, open_dir, open_file::resolve_parent, opfs_err, retry::create_writable, virtualize,
+    virtualize::reject_reserved,
+};
+
+// ---------------------------------------------------------------------------
+// Emulated symbolic links
+//
+// OPFS has no notion of links, so a link named `<name>` is stored as a regular
+// file named `<name>` + `LINK_SUFFIX` next to where the link lives. Its content
+// is `LINK_MAGIC` followed by the UTF-8 target, exactly as passed to `symlink`.
+//
+// Links are only looked up after the literal entry was not found, so paths
+// without links cost nothing extra. Handles are never cached under a path that
+// goes through a link; the dir handle cache therefore only holds real paths.
+// ---------------------------------------------------------------------------
+
+/// Suffix of the OPFS file that stores a link record. Names ending with it are
+/// reserved.
+pub(crate) const LINK_SUFFIX: &str = ".__symlink__";
+
+const LINK_MAGIC: &[u8] = b"tokio-fs-ext:symlink:v1\n";
+
+/// Maximum number of links followed while resolving one path, like Linux.
+pub(crate) const MAX_LINK_FOLLOWS: usize = 40;
+
+pub(crate) struct LinkRecord {
+    pub(crate) target: PathBuf,
+    /// Modification time of the record in milliseconds since epoch.
+    pub(crate) mtime: u64,
+}
+
+pub(crate) fn link_record_name(name: &str) -> String {
+    format!("{name}{LINK_SUFFIX}")
+}
+
+/// Returns the link name if `entry_name` is the name of a link record.
+pub(crate) fn link_name(entry_name: &str) -> Option<&str> {
+    entry_name
+        .strip_suffix(LINK_SUFFIX)
+        .filter(|name| !name.is_empty())
+}
+
+pub(crate) fn loop_err() -> io::Error {
+    io::Error::other("too many levels of symbolic links")
+}
+
+/// Resolves `target` relative to the directory containing `link`.
+pub(crate) fn follow(link: &Path, target: &Path) -> io::Result<PathBuf> {
+    match link.parent() {
+        Some(parent) if target.is_relative() => virtualize(parent.join(target)),
+        _ => virtualize(target),
+    }
+}
+
+/// Reads the record of the link `name` in `dir`, if there is one.
+pub(crate) async fn read_link_record(
+    dir: &FileSystemDirectoryHandle,
+    name: &str,
+) -> io::Result<Option<LinkRecord>> {
+    let options = FileSystemGetFileOptions::new();
+    options.set_create(false);
+    let handle =
+        match JsFuture::from(dir.get_file_handle_with_options(&link_record_name(name), &options))
+            .await
+            .map_err(opfs_err)
+        {
+            Ok(handle) => handle.unchecked_into::<FileSystemFileHandle>(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+    let file: File = JsFuture::from(handle.get_file())
+        .await
+        .map_err(opfs_err)?
+        .unchecked_into();
+    let content = Uint8Array::new(
+        &JsFuture::from(file.array_buffer())
+            .await
+            .map_err(opfs_err)?,
+    )
+    .to_vec();
+
+    let target = content
+        .strip_prefix(LINK_MAGIC)
+        .and_then(|t| String::from_utf8(t.to_vec()).ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed symlink record"))?;
+
+    Ok(Some(LinkRecord {
+        target: PathBuf::from(target),
+        mtime: file.last_modified() as u64,
+    }))
+}
+
+/// Creates the link `link` pointing to `original`. Fails with `AlreadyExists`
+/// if anything exists at `link`.
+pub(crate) async fn create_link(original: &Path, link: impl AsRef<Path>) -> io::Result<()> {
+    let target = original
+        .to_str()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidFilename))?;
+    reject_reserved(link.as_ref())?;
+    let (dir, name) = resolve_parent(&link).await?;
+
+    if read_link_record(&dir, &name).await?.is_some()
+        || read_hard_link_record(&dir, &name).await?.is_some()
+        || JsFuture::from(dir.get_file_handle(&name)).await.is_ok()
+        || JsFuture::from(dir.get_directory_handle(&name))
+            .await
+            .is_ok()
+    {
+        return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+    }
+
+    let options = FileSystemGetFileOptions::new();
+    options.set_create(true);
+    let handle =
+        JsFuture::from(dir.get_file_handle_with_options(&link_record_name(&name), &options))
+            .await
+            .map_err(opfs_err)?
+            .unchecked_into::<FileSystemFileHandle>();
+
+    let stream = create_writable(&handle).await?;
+
+    let content = Uint8Array::from([LINK_MAGIC, target.as_bytes()].concat().as_slice());
+    JsFuture::from(
+        stream
+            .write_with_buffer_source(&content)
+            .map_err(opfs_err)?,
+    )
+    .await
+    .map_err(opfs_err)?;
+    JsFuture::from(stream.close()).await.map_err(opfs_err)?;
+
+    meta_store::record_created(&virtualize(link)?);
+    Ok(())
+}
+
+/// Reads the link record at `path` without following it.
+pub(crate) async fn read_link(path: impl AsRef<Path>) -> io::Result<Option<LinkRecord>> {
+    let (dir, name) = resolve_parent(path).await?;
+    read_link_record(&dir, &name).await
+}
+
+/// Resolves every link in `path`, returning the real, absolute path of an
+/// existing entry.
+#[cfg_attr(feature = "opfs_tracing", tracing::instrument(level = "trace", fields(path = %path.as_ref().to_string_lossy())))]
+pub(crate) async fn canonicalize(path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let mut pending: VecDeque<OsString> = normal_components(&virtualize(path)?).collect();
+    let mut resolved = PathBuf::from("/");
+    let mut follows = 0;
+
+    while let Some(name) = pending.pop_front() {
+        if name == ".." {
+            resolved.pop();
+            continue;
+        }
+
+        let candidate = resolved.join(&name);
+        if get_cached_dir_handle(&candidate).is_none() {
+            let dir = open_dir(&resolved, OpenDirType::NotCreate).await?;
+            if let Some(record) = read_link_record(&dir, &name.to_string_lossy()).await? {
+                follows += 1;
+                if follows > MAX_LINK_FOLLOWS {
+                    return Err(loop_err());
+                }
+                if record.target.is_absolute() {
+                    resolved = PathBuf::from("/");
+                }
+                // `..` is kept so it applies to the directory the link lives in.
+                let target: Vec<OsString> = record
+                    .target
+                    .components()
+                    .filter_map(|c| match c {
+                        Component::Normal(c) => Some(c.to_os_string()),
+                        Component::ParentDir => Some(OsString::from("..")),
+                        _ => None,
+                    })
+                    .collect();
+                for c in target.into_iter().rev() {
+                    pending.push_front(c);
+                }
+                continue;
+            }
+        }
+        resolved = candidate;
+    }
+
+    if !super::super::try_exists(&resolved).await? {
+        return Err(io::Error::from(io::ErrorKind::NotFound));
+    }
+    Ok(resolved)
+}
+
+fn normal_components(path: &Path) -> impl Iterator<Item = OsString> + '_ {
+    path.components().filter_map(|c| match c {
+        Component::Normal(c) => Some(c.to_os_string()),
+        _ => None,
+    })
+}
@@ -3,11 +3,17 @@ use std::{
     path::{Component, MAIN_SEPARATOR_STR, Path, PathBuf},
 };
 
+use super::{
+    BLOBS_DIR, hard_link::HARD_LINK_SUFFIX, meta_store::META_STORE_NAME, symlink::LINK_SUFFIX,
+};
 use crate::fs::wasm::current_dir;
 
+/// Makes `path` absolute and lexically normalized.
+///
+/// Symbolic links are not resolved here: they are followed while the path is
+/// walked against OPFS, see `open_dir`, `resolve_file_handle` and
+/// `symlink::canonicalize`.
 pub(crate) fn virtualize(path: impl AsRef<Path>) -> Result<PathBuf, io::Error> {
-    // TODO: should handle symlink here
-
     let path = if path.as_ref().starts_with(MAIN_SEPARATOR_STR) {
         path.as_ref().into()
     } else {
@@ -39,3 +45,24 @@ pub(crate) fn virtualize(path: impl AsRef<Path>) -> Result<PathBuf, io::Error> {
         Ok(PathBuf::from("/"))
     }
 }
+
+/// Fails with `InvalidInput` if the last component of `path` is one of the
+/// names used by the emulation: link records, the blobs directory and the
+/// metadata store. Checked wherever an entry is created from a user path, so
+/// that it cannot be mistaken for one of them.
+pub(crate) fn reject_reserved(path: &Path) -> io::Result<()> {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(());
+    };
+    if name.ends_with(LINK_SUFFIX)
+        || name.ends_with(HARD_LINK_SUFFIX)
+        || name == BLOBS_DIR
+        || name == META_STORE_NAME
+    {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("`{name}` is a reserved file name"),
+        ));
+    }
+    Ok(())
+}
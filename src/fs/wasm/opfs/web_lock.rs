@@ -0,0 +1,98 @@
+use std::{io, path::Path, time::Duration};
+
+use futures::{
+    channel::oneshot,
+    future::{Either, select},
+};
+use js_sys::{Function, Object, Promise, Reflect};
+use wasm_bindgen::{
+    JsCast, JsValue,
+    prelude::{Closure, wasm_bindgen},
+};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{AbortSignal, DedicatedWorkerGlobalScope};
+
+use super::opfs_err;
+
+// ---------------------------------------------------------------------------
+// Web Locks
+//
+// `navigator.locks` coordinates every worker and tab of the origin. A lock is
+// held until the promise returned by the request callback settles, so the
+// callback hands back a promise that is only resolved when the
+// `WebLockGuard` is dropped.
+//
+// web-sys only exposes `LockManager` behind `web_sys_unstable_apis`, so the
+// one method needed is bound here.
+// ---------------------------------------------------------------------------
+
+#[wasm_bindgen]
+extern "C" {
+    // https://developer.mozilla.org/en-US/docs/Web/API/LockManager
+    #[wasm_bindgen(extends = js_sys::Object, js_name = LockManager)]
+    type LockManager;
+
+    #[wasm_bindgen(method, structural, js_class = "LockManager", js_name = request)]
+    fn request(this: &LockManager, name: &str, options: &Object, callback: &Function) -> Promise;
+}
+
+const LOCK_NAME_PREFIX: &str = "tokio-fs-ext:";
+
+/// A held Web Lock, released when dropped.
+pub(crate) struct WebLockGuard {
+    release: Function,
+}
+
+impl Drop for WebLockGuard {
+    fn drop(&mut self) {
+        let _ = self.release.call0(&JsValue::UNDEFINED);
+    }
+}
+
+/// Requests the Web Lock of `path`, waiting until every other context has
+/// released it. Fails with `TimedOut` if that takes longer than `timeout`.
+pub(crate) async fn request_lock(
+    path: &Path,
+    exclusive: bool,
+    timeout: Option<Duration>,
+) -> io::Result<WebLockGuard> {
+    let navigator = DedicatedWorkerGlobalScope::from(JsValue::from(js_sys::global())).navigator();
+    let locks = Reflect::get(&navigator, &"locks".into()).map_err(opfs_err)?;
+    if locks.is_undefined() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the Web Locks API is not available",
+        ));
+    }
+    let locks: LockManager = locks.unchecked_into();
+
+    let options = Object::new();
+    let mode = if exclusive { "exclusive" } else { "shared" };
+    Reflect::set(&options, &"mode".into(), &mode.into()).map_err(opfs_err)?;
+    if let Some(timeout) = timeout {
+        let signal = AbortSignal::timeout_with_f64(timeout.as_millis() as f64);
+        Reflect::set(&options, &"signal".into(), &signal).map_err(opfs_err)?;
+    }
+
+    let mut release = None;
+    let held = Promise::new(&mut |resolve, _| release = Some(resolve));
+    let (granted_tx, granted_rx) = oneshot::channel();
+    // If the request was given up on by the time the lock is granted, it is
+    // released right away.
+    let callback = Closure::once_into_js(move |_lock: JsValue| match granted_tx.send(()) {
+        Ok(()) => held,
+        Err(()) => Promise::resolve(&JsValue::UNDEFINED),
+    });
+    let name = format!("{LOCK_NAME_PREFIX}{}", path.to_string_lossy());
+    let request = JsFuture::from(locks.request(&name, &options, callback.unchecked_ref()));
+
+    match select(granted_rx, request).await {
+        Either::Left((Ok(()), _)) => Ok(WebLockGuard {
+            release: release.expect("the promise executor runs synchronously"),
+        }),
+        Either::Right((Err(err), _)) => Err(opfs_err(err)),
+        _ => Err(io::Error::other(
+            "the Web Lock request ended without a lock",
+        )),
+    }
+}
@@ -0,0 +1,55 @@
+/// Representation of the permissions on a file or a directory.
+///
+/// OPFS has no permission model of its own. The mode bits, including the
+/// read-only bit, are kept in the sidecar metadata store alongside the entry,
+/// and only the read-only bit is enforced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permissions {
+    mode: u32,
+}
+
+impl Permissions {
+    /// Mode of entries created without an explicit one.
+    const DEFAULT_MODE: u32 = 0o666;
+
+    pub(crate) fn from_record(readonly: bool, mode: Option<u32>) -> Self {
+        let mut perm = Self {
+            mode: mode.unwrap_or(Self::DEFAULT_MODE),
+        };
+        if readonly {
+            perm.set_readonly(true);
+        }
+        perm
+    }
+
+    pub fn readonly(&self) -> bool {
+        self.mode & 0o222 == 0
+    }
+
+    /// Clears every write bit of the mode, or sets them all, like `std` on Unix.
+    pub fn set_readonly(&mut self, readonly: bool) {
+        if readonly {
+            self.mode &= !0o222;
+        } else {
+            self.mode |= 0o222;
+        }
+    }
+
+    /// Returns the mode bits, `0o666` (without the write bits if read-only)
+    /// unless another mode was set.
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: u32) {
+        self.mode = mode;
+    }
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            mode: Self::DEFAULT_MODE,
+        }
+    }
+}
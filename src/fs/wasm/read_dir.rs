@@ -1,95 +1,155 @@
 use std::{
+    collections::VecDeque,
     ffi::OsString,
     fmt::Debug,
+    future::poll_fn,
     io,
     path::{Path, PathBuf},
     str::FromStr,
-    task::{Context, Poll},
+    task::{Context, Poll, ready},
 };
 
-use futures::{TryStreamExt, stream::StreamExt};
+use futures::stream::StreamExt;
 use js_sys::{Array, JsString};
-use wasm_bindgen::JsCast;
+use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::stream::JsStream;
 use web_sys::FileSystemHandle;
 
 use super::{
     metadata::{FileType, Metadata},
-    opfs::{open_dir, opfs_err},
+    opfs::{BLOBS_DIR, hard_link_name, link_name, meta_store::META_STORE_NAME, open_dir, opfs_err},
 };
 
 pub async fn read_dir(path: impl AsRef<Path>) -> io::Result<ReadDir> {
     let dir_handle = open_dir(&path, super::opfs::OpenDirType::NotCreate).await?;
-    let entries = JsStream::from(dir_handle.entries())
-        .map(|handle| {
-            handle.map_or_else(
-                |err| Err(opfs_err(err)),
-                |entry| {
-                    Ok({
-                        let js_array = Array::from(&entry);
-
-                        let name = OsString::from_str(
-                            JsString::from(js_array.get(0))
-                                .as_string()
-                                .ok_or(io::Error::from(io::ErrorKind::InvalidFilename))?
-                                .as_str(),
-                        )
-                        .map_err(|_| io::Error::from(io::ErrorKind::InvalidFilename))?;
-
-                        let path = path.as_ref().join(&name);
-
-                        let file_type = js_array
-                            .get(1)
-                            .unchecked_into::<FileSystemHandle>()
-                            .kind()
-                            .into();
-
-                        DirEntry {
-                            file_type,
-                            path,
-                            name,
-                        }
-                    })
-                },
-            )
-        })
-        .try_collect()
-        .await?;
-
-    Ok(ReadDir { entries })
+
+    Ok(ReadDir {
+        entries: Entries::Lazy {
+            path: path.as_ref().to_path_buf(),
+            stream: JsStream::from(dir_handle.entries()),
+        },
+    })
 }
 
-#[derive(Debug)]
+/// Reads the entries in a directory.
+///
+/// Entries are pulled from the directory's async iterator on demand and
+/// returned in iteration order.
 pub struct ReadDir {
-    entries: Vec<DirEntry>,
+    entries: Entries,
+}
+
+enum Entries {
+    Lazy { path: PathBuf, stream: JsStream },
+    Collected(VecDeque<DirEntry>),
 }
 
 impl ReadDir {
     pub async fn next_entry(&mut self) -> io::Result<Option<DirEntry>> {
-        Ok(self.entries.pop())
+        poll_fn(|cx| self.poll_next_entry(cx)).await
     }
 
-    pub fn poll_next_entry(&mut self, _cx: &mut Context<'_>) -> Poll<io::Result<Option<DirEntry>>> {
-        Poll::Ready(Ok(self.entries.pop()))
+    pub fn poll_next_entry(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<Option<DirEntry>>> {
+        match &mut self.entries {
+            Entries::Lazy { path, stream } => loop {
+                match ready!(stream.poll_next_unpin(cx)) {
+                    Some(Ok(entry)) => match to_dir_entry(path, entry) {
+                        Ok(None) => continue,
+                        res => return Poll::Ready(res),
+                    },
+                    Some(Err(err)) => return Poll::Ready(Err(opfs_err(err))),
+                    None => return Poll::Ready(Ok(None)),
+                }
+            },
+            Entries::Collected(entries) => Poll::Ready(Ok(entries.pop_front())),
+        }
+    }
+
+    /// Reads the remaining entries.
+    #[cfg(feature = "opfs_offload")]
+    pub(crate) async fn collect_entries(mut self) -> io::Result<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        while let Some(entry) = self.next_entry().await? {
+            entries.push(entry);
+        }
+        Ok(entries)
     }
 }
 
+/// Yields the entries of a `ReadDir` collected from an iterator, such as the one returned by
+/// the offload `Client`. A `ReadDir` returned by [`read_dir`] fetches its entries lazily, which
+/// cannot be done synchronously, so iterating it yields a single `WouldBlock` error; use
+/// [`ReadDir::next_entry`] instead.
 impl Iterator for ReadDir {
     type Item = io::Result<DirEntry>;
 
     fn next(&mut self) -> Option<io::Result<DirEntry>> {
-        self.entries.pop().map(Result::Ok)
+        match &mut self.entries {
+            Entries::Collected(entries) => entries.pop_front().map(Ok),
+            Entries::Lazy { .. } => {
+                self.entries = Entries::Collected(VecDeque::new());
+                Some(Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "entries are fetched lazily, use `next_entry`",
+                )))
+            }
+        }
+    }
+}
+
+impl Debug for ReadDir {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.entries {
+            Entries::Lazy { path, .. } => f.debug_struct("ReadDir").field("path", path).finish(),
+            Entries::Collected(entries) => {
+                f.debug_struct("ReadDir").field("entries", entries).finish()
+            }
+        }
     }
 }
 
 impl FromIterator<DirEntry> for ReadDir {
     fn from_iter<T: IntoIterator<Item = DirEntry>>(iter: T) -> Self {
         ReadDir {
-            entries: iter.into_iter().collect(),
+            entries: Entries::Collected(iter.into_iter().collect()),
         }
     }
 }
 
+/// Returns `None` for entries reserved by this crate.
+fn to_dir_entry(path: &Path, entry: JsValue) -> io::Result<Option<DirEntry>> {
+    let js_array = Array::from(&entry);
+
+    let raw_name = JsString::from(js_array.get(0))
+        .as_string()
+        .ok_or(io::Error::from(io::ErrorKind::InvalidFilename))?;
+    if raw_name == META_STORE_NAME || raw_name == BLOBS_DIR {
+        return Ok(None);
+    }
+
+    let (name, file_type) = match (link_name(&raw_name), hard_link_name(&raw_name)) {
+        (Some(name), _) => (name, FileType::Symlink),
+        (_, Some(name)) => (name, FileType::File),
+        _ => (
+            raw_name.as_str(),
+            js_array
+                .get(1)
+                .unchecked_into::<FileSystemHandle>()
+                .kind()
+                .into(),
+        ),
+    };
+
+    let name =
+        OsString::from_str(name).map_err(|_| io::Error::from(io::ErrorKind::InvalidFilename))?;
+
+    Ok(Some(DirEntry {
+        file_type,
+        path: path.join(&name),
+        name,
+    }))
+}
+
 #[derive(Debug)]
 pub struct DirEntry {
     file_type: FileType,
@@ -121,7 +181,9 @@ impl DirEntry {
         Ok(self.file_type)
     }
 
+    /// Returns the metadata for the file that this entry points at, without
+    /// following symlinks, like `std::fs::DirEntry::metadata`.
     pub async fn metadata(&self) -> io::Result<Metadata> {
-        super::metadata(&self.path).await
+        super::symlink_metadata(&self.path).await
     }
 }
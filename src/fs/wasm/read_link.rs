@@ -3,9 +3,15 @@ use std::{
     path::{Path, PathBuf},
 };
 
-pub async fn read_link(_path: impl AsRef<Path>) -> io::Result<PathBuf> {
-    Err(io::Error::new(
-        io::ErrorKind::Unsupported,
-        "Symbolic links are not supported on OPFS",
-    ))
+use super::{opfs::read_link as read_link_record, try_exists};
+
+pub async fn read_link(path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    match read_link_record(&path).await? {
+        Some(record) => Ok(record.target),
+        None if try_exists(&path).await? => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "not a symbolic link",
+        )),
+        None => Err(io::Error::from(io::ErrorKind::NotFound)),
+    }
 }
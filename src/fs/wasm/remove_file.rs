@@ -1,8 +1,12 @@
 use std::{io, path::Path};
 
-use super::opfs::remove;
+use super::opfs::{meta_store, read_link, remove, virtualize};
 
 pub async fn remove_file(path: impl AsRef<Path>) -> io::Result<()> {
-    let (mut _guard, _) = super::file::lock_file(&path, None).await;
+    let (mut _guard, _) = super::file::lock_file(&path, None).await?;
+    // Removing a symbolic link leaves its target alone, whatever its mode.
+    if read_link(&path).await?.is_none() {
+        meta_store::ensure_writable(&virtualize(&path)?).await?;
+    }
     remove(path, false).await
 }
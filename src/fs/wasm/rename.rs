@@ -1,10 +1,203 @@
-use std::{io, path::Path};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
-use super::{copy, remove_file};
+use futures::future::LocalBoxFuture;
 
+use super::{
+    Metadata, copy, create_dir,
+    file::lock_file,
+    opfs::{
+        OpenDirType, move_entry, move_hard_link, open_dir, read_hard_link, reject_reserved,
+        virtualize,
+    },
+    read, read_dir, read_link, remove_dir, remove_dir_all, remove_file, symlink, symlink_metadata,
+    write,
+};
+
+/// Renames a file or directory to a new name, replacing the original file if
+/// `to` already exists.
+///
+/// Follows `tokio::fs::rename` semantics: a directory may only replace an
+/// empty directory, a file may not replace a directory, and a link is renamed
+/// itself rather than its target.
+///
+/// `FileSystemHandle.move()` is used where the browser supports it. Otherwise
+/// the entry is copied and the source removed afterwards; if that fails
+/// halfway, the copy is rolled back and `from` is left untouched.
 pub async fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
-    // TODO: rename dir
-    copy(&from, to).await?;
-    remove_file(from).await?;
+    let from = virtualize(from)?;
+    let to = virtualize(to)?;
+    reject_reserved(&to)?;
+
+    let from_meta = symlink_metadata(&from).await?;
+    if from == to {
+        return Ok(());
+    }
+
+    if let Some(parent) = to.parent() {
+        open_dir(parent, OpenDirType::NotCreate).await?;
+    }
+
+    let to_meta = match symlink_metadata(&to).await {
+        Ok(meta) => Some(meta),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+
+    if from_meta.is_dir() {
+        return rename_dir(&from, &to, to_meta).await;
+    }
+    if to_meta.is_some_and(|m| m.is_dir()) {
+        return Err(io::Error::from(io::ErrorKind::IsADirectory));
+    }
+
+    // A link at `to` is replaced by removing its record, since the new entry
+    // is stored under a different OPFS name.
+    let to_exists = match to_meta {
+        Some(m) if m.is_symlink() || read_hard_link(&to).await?.is_some() => {
+            remove_file(&to).await?;
+            false
+        }
+        m => m.is_some(),
+    };
+
+    if from_meta.is_symlink() {
+        rename_link(&from, &to, to_exists).await
+    } else if read_hard_link(&from).await?.is_some() {
+        if to_exists {
+            remove_file(&to).await?;
+        }
+        move_hard_link(&from, &to).await
+    } else {
+        rename_file(&from, &to, to_exists).await
+    }
+}
+
+async fn rename_link(from: &Path, to: &Path, to_exists: bool) -> io::Result<()> {
+    let target = read_link(from).await?;
+    if to_exists {
+        remove_file(to).await?;
+    }
+    symlink(&target, to).await?;
+    remove_file(from).await
+}
+
+async fn rename_file(from: &Path, to: &Path, to_exists: bool) -> io::Result<()> {
+    {
+        // Lock in a stable order so two concurrent renames cannot deadlock.
+        let (first, second) = if from < to { (from, to) } else { (to, from) };
+        let (_first_guard, _) = lock_file(first, None).await?;
+        let (_second_guard, _) = lock_file(second, None).await?;
+
+        match move_entry(from, to).await {
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {}
+            res => return res,
+        }
+    }
+
+    let previous = if to_exists {
+        Some(read(to).await?)
+    } else {
+        None
+    };
+
+    copy(from, to).await?;
+
+    if let Err(e) = remove_file(from).await {
+        let _ = match previous {
+            Some(contents) => write(to, contents).await,
+            None => remove_file(to).await,
+        };
+        return Err(e);
+    }
+    Ok(())
+}
+
+async fn rename_dir(from: &Path, to: &Path, to_meta: Option<Metadata>) -> io::Result<()> {
+    if to.starts_with(from) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "cannot move a directory into itself",
+        ));
+    }
+
+    let to_exists = to_meta.is_some();
+    if let Some(to_meta) = to_meta {
+        if !to_meta.is_dir() {
+            return Err(io::Error::from(io::ErrorKind::NotADirectory));
+        }
+        // Fails with `DirectoryNotEmpty` unless `to` may be replaced.
+        if read_dir(to).await?.next_entry().await?.is_some() {
+            return Err(io::Error::from(io::ErrorKind::DirectoryNotEmpty));
+        }
+        remove_dir(to).await?;
+    }
+
+    match move_entry(from, to).await {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {}
+        Err(e) => {
+            // Put back the empty directory `from` was to replace.
+            if to_exists {
+                let _ = create_dir(to).await;
+            }
+            return Err(e);
+        }
+    }
+
+    let discard_copy = async || {
+        let _ = remove_dir_all(to).await;
+        if to_exists {
+            let _ = create_dir(to).await;
+        }
+    };
+
+    if let Err(e) = copy_dir_all(from.to_path_buf(), to.to_path_buf(), false).await {
+        discard_copy().await;
+        return Err(e);
+    }
+    if let Err(e) = remove_dir_all(from).await {
+        // The source may have been partially removed: put back whatever is
+        // missing from the copy before discarding it.
+        if copy_dir_all(to.to_path_buf(), from.to_path_buf(), true)
+            .await
+            .is_ok()
+        {
+            discard_copy().await;
+        }
+        return Err(e);
+    }
     Ok(())
 }
+
+/// Recursively copies `from` into `to`. With `merge`, entries that already
+/// exist in `to` are kept as they are.
+fn copy_dir_all(
+    from: PathBuf,
+    to: PathBuf,
+    merge: bool,
+) -> LocalBoxFuture<'static, io::Result<()>> {
+    Box::pin(async move {
+        match create_dir(&to).await {
+            Err(e) if !(merge && e.kind() == io::ErrorKind::AlreadyExists) => return Err(e),
+            _ => {}
+        }
+        let mut entries = read_dir(&from).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let target = to.join(entry.file_name());
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                copy_dir_all(entry.path(), target, merge).await?;
+            } else if merge && symlink_metadata(&target).await.is_ok() {
+                continue;
+            } else if file_type.is_symlink() {
+                symlink(read_link(entry.path()).await?, target).await?;
+            } else {
+                copy(entry.path(), target).await?;
+            }
+        }
+        Ok(())
+    })
+}
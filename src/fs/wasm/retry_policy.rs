@@ -0,0 +1,56 @@
+use std::{
+    sync::{PoisonError, RwLock},
+    time::Duration,
+};
+
+/// How often opening a file is retried while another worker or tab holds it.
+///
+/// OPFS lets only one context at a time hold a `SyncAccessHandle` or a writable stream on a file;
+/// the others fail with `WouldBlock`. Those failures are retried with exponential backoff until
+/// `max_attempts` attempts were made or `deadline` passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry. It doubles after each retry.
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between two attempts.
+    pub max_backoff: Duration,
+    /// Time after the first attempt past which no retry is started, or `None` for no limit.
+    pub deadline: Option<Duration>,
+}
+
+impl RetryPolicy {
+    /// A policy that never retries, failing with `WouldBlock` right away.
+    pub const NONE: RetryPolicy = RetryPolicy {
+        max_attempts: 1,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+        deadline: None,
+    };
+
+    const DEFAULT: RetryPolicy = RetryPolicy {
+        max_attempts: 8,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(500),
+        deadline: Some(Duration::from_secs(3)),
+    };
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::DEFAULT
+    }
+}
+
+static RETRY_POLICY: RwLock<RetryPolicy> = RwLock::new(RetryPolicy::DEFAULT);
+
+/// Returns the current [`RetryPolicy`].
+pub fn retry_policy() -> RetryPolicy {
+    *RETRY_POLICY.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Sets the [`RetryPolicy`] used by file operations started from now on.
+pub fn set_retry_policy(policy: RetryPolicy) {
+    *RETRY_POLICY.write().unwrap_or_else(PoisonError::into_inner) = policy;
+}
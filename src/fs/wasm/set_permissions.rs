@@ -0,0 +1,19 @@
+use std::{io, path::Path};
+
+use super::{
+    Permissions, metadata,
+    opfs::{meta_store, virtualize},
+};
+
+/// Changes the permissions found on a file or a directory.
+///
+/// The mode is kept in the sidecar metadata store, for the file `path` leads
+/// to after following links. Only the read-only bit is enforced:
+/// `OpenOptions::open` with write access, `write`, `remove_file` and
+/// `File::set_len` fail with `PermissionDenied` on read-only files, through
+/// any path to them.
+pub async fn set_permissions(path: impl AsRef<Path>, perm: Permissions) -> io::Result<()> {
+    metadata(&path).await?;
+    let target = meta_store::mode_path(&virtualize(&path)?).await?;
+    meta_store::record_mode(&target, perm.mode()).await
+}
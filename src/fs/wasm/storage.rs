@@ -0,0 +1,55 @@
+use std::io;
+
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{DedicatedWorkerGlobalScope, StorageManager};
+
+use super::opfs::opfs_err;
+use crate::storage::StorageEstimate;
+
+/// Returns how much storage the origin uses, and how much it may use, through
+/// `navigator.storage.estimate()`.
+pub async fn estimate() -> io::Result<StorageEstimate> {
+    let promise = storage_manager().estimate().map_err(opfs_err)?;
+    let estimate = JsFuture::from(promise).await.map_err(opfs_err)?;
+    let field = |name: &str| {
+        Reflect::get(&estimate, &name.into())
+            .ok()
+            .and_then(|value| value.as_f64())
+            .unwrap_or_default() as u64
+    };
+    Ok(StorageEstimate::new(field("usage"), field("quota")))
+}
+
+/// Asks the browser not to evict the origin's storage under storage pressure,
+/// returning whether it agreed.
+///
+/// Browsers only expose `navigator.storage.persist()` to windows, so this fails
+/// with `Unsupported` in the workers OPFS is used from; call it from the main
+/// thread instead.
+pub async fn persist() -> io::Result<bool> {
+    let storage = storage_manager();
+    if !Reflect::has(&storage, &"persist".into()).unwrap_or(false) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "navigator.storage.persist() is only available in windows",
+        ));
+    }
+    let promise = storage.persist().map_err(opfs_err)?;
+    let persisted = JsFuture::from(promise).await.map_err(opfs_err)?;
+    Ok(persisted.is_truthy())
+}
+
+/// Returns whether the origin's storage is exempt from eviction.
+pub async fn persisted() -> io::Result<bool> {
+    let promise = storage_manager().persisted().map_err(opfs_err)?;
+    let persisted = JsFuture::from(promise).await.map_err(opfs_err)?;
+    Ok(persisted.is_truthy())
+}
+
+fn storage_manager() -> StorageManager {
+    DedicatedWorkerGlobalScope::from(JsValue::from(js_sys::global()))
+        .navigator()
+        .storage()
+}
@@ -1,8 +1,12 @@
 use std::{io, path::Path};
 
-pub async fn symlink(_original: impl AsRef<Path>, _link: impl AsRef<Path>) -> io::Result<()> {
-    Err(io::Error::new(
-        io::ErrorKind::Unsupported,
-        "Symbolic links are not supported on OPFS",
-    ))
+use super::opfs::create_link;
+
+/// Creates a new symbolic link on the filesystem.
+///
+/// The `link` path will be a symbolic link pointing to the `original` path.
+/// OPFS has no native links, so the link is emulated by a reserved record file
+/// that is followed transparently by every other function of this crate.
+pub async fn symlink(original: impl AsRef<Path>, link: impl AsRef<Path>) -> io::Result<()> {
+    create_link(original.as_ref(), link).await
 }
@@ -1,10 +1,22 @@
 use std::{io, path::Path};
 
-use super::Metadata;
+use super::{
+    FileType, Metadata, metadata,
+    opfs::{meta_store, read_link, virtualize},
+};
 
-pub async fn symlink_metadata(_path: impl AsRef<Path>) -> io::Result<Metadata> {
-    Err(io::Error::new(
-        io::ErrorKind::Unsupported,
-        "Symbolic links are not supported on OPFS",
-    ))
+/// Queries the metadata about a file without following symlinks.
+pub async fn symlink_metadata(path: impl AsRef<Path>) -> io::Result<Metadata> {
+    match read_link(&path).await? {
+        Some(record) => {
+            let _ = meta_store::load().await;
+            Ok(Metadata::new(
+                FileType::Symlink,
+                record.target.as_os_str().len() as u64,
+                Some(record.mtime),
+            )
+            .with_record(meta_store::get(&virtualize(&path)?)))
+        }
+        None => metadata(path).await,
+    }
 }
@@ -0,0 +1,247 @@
+use std::{
+    future::{Future, poll_fn},
+    io::{self, SeekFrom},
+    path::Path,
+    pin::Pin,
+    task::{Context, Poll, ready},
+};
+
+use futures::io::{AsyncSeek, AsyncWrite};
+use js_sys::{Number, Promise, Uint8Array};
+use wasm_bindgen_futures::{JsFuture, spawn_local};
+use web_sys::FileSystemWritableFileStream;
+
+use super::{
+    File,
+    file::{DEFAULT_MAX_BUF_SIZE, FileLockGuard},
+    opfs::{CreateFileMode, create_writable, lock_and_handle, meta_store, opfs_err, virtualize},
+};
+
+/// A file written through a `FileSystemWritableFileStream` and only put in place once closed.
+///
+/// Writes go to the browser's swap file for the stream, so large files can be written piece by
+/// piece without holding a `SyncAccessHandle`. They replace the old contents atomically when the
+/// writer is closed with `close()` (or `shutdown()` with the `tokio-io` feature). Dropping the
+/// writer without closing it discards everything written.
+///
+/// The writer holds the exclusive lock of the file until it is closed or dropped, like
+/// [`write_atomic`](super::write_atomic) does while it runs: opening a [`File`], [`read`](super::read),
+/// [`write`](super::write) or [`remove_file`](super::remove_file) on the same path wait until then,
+/// and so does creating the writer while a [`File`] is open on the path. Links are followed, so
+/// every hard link to the file sees the new contents.
+#[derive(Debug)]
+pub struct WritableFile {
+    stream: FileSystemWritableFileStream,
+    /// Released once the stream is closed or aborted.
+    lock: Option<FileLockGuard>,
+    /// The last operation sent to the stream. The stream runs operations in order, and a failed
+    /// one fails every later one, so only the last needs to be awaited.
+    pending: Option<JsFuture>,
+    /// Cursor of the stream.
+    pos: u64,
+    /// Size of the data written so far.
+    len: u64,
+    closing: Option<JsFuture>,
+    closed: bool,
+}
+
+impl WritableFile {
+    /// Starts writing a new version of the file at `path`, which is created if it does not
+    /// exist.
+    pub async fn create(path: impl AsRef<Path>) -> io::Result<WritableFile> {
+        let virt = virtualize(&path)?;
+        meta_store::ensure_writable(&virt).await?;
+        let (lock, _, handle) = lock_and_handle(&path, None, CreateFileMode::Create).await?;
+        let stream = create_writable(&handle).await?;
+        Ok(WritableFile {
+            stream,
+            lock: Some(lock),
+            pending: None,
+            pos: 0,
+            len: 0,
+            closing: None,
+            closed: false,
+        })
+    }
+
+    /// Truncates or extends the data written so far to `size` bytes. The cursor moves back to
+    /// `size` if it was past it.
+    pub async fn set_len(&mut self, size: u64) -> io::Result<()> {
+        poll_fn(|cx| self.poll_pending(cx)).await?;
+        let promise = self
+            .stream
+            .truncate_with_f64(to_f64(size)?)
+            .map_err(opfs_err)?;
+        JsFuture::from(promise).await.map_err(opfs_err)?;
+        self.len = size;
+        self.pos = self.pos.min(size);
+        Ok(())
+    }
+
+    fn ensure_open(&self) -> io::Result<()> {
+        if self.closed || self.closing.is_some() {
+            return Err(io::Error::other("the writable file is closed"));
+        }
+        Ok(())
+    }
+
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if let Some(pending) = self.pending.as_mut() {
+            let res = ready!(Pin::new(pending).poll(cx));
+            self.pending = None;
+            res.map_err(opfs_err)?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn seek_to(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.ensure_open()?;
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        let promise = self
+            .stream
+            .seek_with_f64(to_f64(target)?)
+            .map_err(opfs_err)?;
+        // Queued behind any pending write, so awaiting it covers both.
+        self.pending = Some(JsFuture::from(promise));
+        self.pos = target;
+        Ok(target)
+    }
+}
+
+impl File {
+    /// Opens a [`WritableFile`] that replaces the file at `path` atomically once closed.
+    pub async fn create_streaming(path: impl AsRef<Path>) -> io::Result<WritableFile> {
+        WritableFile::create(path).await
+    }
+}
+
+fn to_f64(n: u64) -> io::Result<f64> {
+    const MAX_SAFE_INT: u64 = Number::MAX_SAFE_INTEGER as _;
+    if n > MAX_SAFE_INT {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("offset {n} too large, max allowed is {MAX_SAFE_INT}"),
+        ));
+    }
+    Ok(n as f64)
+}
+
+impl Drop for WritableFile {
+    fn drop(&mut self) {
+        if self.closed || self.closing.is_some() {
+            return;
+        }
+        // Discards the swap file, leaving the old contents in place.
+        let abort: Promise = self.stream.abort();
+        let lock = self.lock.take();
+        spawn_local(async move {
+            let _ = JsFuture::from(abort).await;
+            drop(lock);
+        });
+    }
+}
+
+impl AsyncWrite for WritableFile {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        this.ensure_open()?;
+        // One write in flight at a time keeps memory use bounded.
+        ready!(this.poll_pending(cx))?;
+
+        let n = buf.len().min(DEFAULT_MAX_BUF_SIZE);
+        // Copied, since the stream reads the data after this returns.
+        let data = Uint8Array::from(&buf[..n]);
+        let promise = this
+            .stream
+            .write_with_buffer_source(&data)
+            .map_err(opfs_err)?;
+        this.pending = Some(JsFuture::from(promise));
+        this.pos += n as u64;
+        this.len = this.len.max(this.pos);
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_pending(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.closed {
+            return Poll::Ready(Ok(()));
+        }
+        if this.closing.is_none() {
+            if let Err(e) = ready!(this.poll_pending(cx)) {
+                let _ = this.stream.abort();
+                this.lock = None;
+                this.closed = true;
+                return Poll::Ready(Err(e));
+            }
+            this.closing = Some(JsFuture::from(this.stream.close()));
+        }
+
+        let closing = this.closing.as_mut().expect("set above");
+        let res = ready!(Pin::new(closing).poll(cx));
+        this.closing = None;
+        this.lock = None;
+        this.closed = true;
+        res.map_err(opfs_err)?;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for WritableFile {
+    fn poll_seek(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        pos: SeekFrom,
+    ) -> Poll<io::Result<u64>> {
+        Poll::Ready(self.get_mut().seek_to(pos))
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncWrite for WritableFile {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(self, cx)
+    }
+}
+
+#[cfg(feature = "tokio-io")]
+impl tokio::io::AsyncSeek for WritableFile {
+    // Seeks are queued on the stream, so they complete right away.
+    fn start_seek(self: Pin<&mut Self>, position: SeekFrom) -> io::Result<()> {
+        self.get_mut().seek_to(position).map(|_| ())
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        Poll::Ready(Ok(self.pos))
+    }
+}
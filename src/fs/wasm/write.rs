@@ -1,13 +1,14 @@
 use std::{io, path::Path};
 
 use js_sys::Uint8Array;
-use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
-use web_sys::{FileSystemReadWriteOptions, FileSystemWritableFileStream};
+use web_sys::FileSystemReadWriteOptions;
 
-use super::opfs::{CreateFileMode, opfs_err};
+use super::opfs::{CreateFileMode, create_writable, meta_store, opfs_err, virtualize};
 
 pub async fn write(path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()> {
+    let virt = virtualize(&path)?;
+    meta_store::ensure_writable(&virt).await?;
     let (guard, sync_handle, file_handle) = super::opfs::lock_and_handle(
         path,
         Some(super::opfs::SyncAccessMode::Readwrite),
@@ -32,14 +33,12 @@ pub async fn write(path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Res
         }
 
         sync_access.flush().map_err(opfs_err)?;
+        super::file::invalidate_read_ahead(&guard.path);
 
         drop(guard);
         Ok(())
     } else {
-        let stream: FileSystemWritableFileStream = JsFuture::from(file_handle.create_writable())
-            .await
-            .map_err(opfs_err)?
-            .unchecked_into();
+        let stream = create_writable(&file_handle).await?;
 
         // Create a fresh Uint8Array tailored for the JS side.
         // This performs a copy, which ensures safety even if the source is backed by SharedArrayBuffer
@@ -0,0 +1,42 @@
+use std::{io, path::Path};
+
+use js_sys::Uint8Array;
+use wasm_bindgen_futures::JsFuture;
+
+use super::opfs::{
+    CreateFileMode, create_writable, lock_and_handle, meta_store, opfs_err, virtualize,
+};
+
+/// Writes `contents` to a file atomically, creating it if it does not exist.
+///
+/// Unlike [`write`](super::write), readers, including ones in other contexts, see either the old
+/// or the new contents, even if the tab crashes halfway. The data is written to the swap file of
+/// a `createWritable()` stream, which the browser only puts in place once it is complete.
+///
+/// OPFS cannot swap a file that has a `SyncAccessHandle` open, so like
+/// [`remove_file`](super::remove_file) this waits until every [`File`](super::File) open on the
+/// path is dropped.
+///
+/// If `path` is a symbolic link, the file it points to is written and the link is kept, like on
+/// native. Hard links share their data here, so every link to the file sees the new contents;
+/// on native they keep the old contents instead.
+pub async fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let virt = virtualize(&path)?;
+    meta_store::ensure_writable(&virt).await?;
+    let (_guard, _, file_handle) = lock_and_handle(&path, None, CreateFileMode::Create).await?;
+
+    let stream = create_writable(&file_handle).await?;
+    let content = Uint8Array::from(contents.as_ref());
+    let written = match stream.write_with_buffer_source(&content) {
+        Ok(promise) => JsFuture::from(promise).await,
+        Err(err) => Err(err),
+    };
+    if let Err(err) = written {
+        // Discards the swap file, leaving the old contents in place.
+        let _ = JsFuture::from(stream.abort()).await;
+        return Err(opfs_err(err));
+    }
+    JsFuture::from(stream.close()).await.map_err(opfs_err)?;
+
+    Ok(())
+}
@@ -5,7 +5,7 @@ mod test_utils;
 use std::{io, path::PathBuf, str, sync::LazyLock};
 
 use futures::{
-    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
     stream::TryStreamExt,
 };
 use tokio_fs_ext::*;
@@ -144,6 +144,123 @@ async fn test_file_create_write_read() {
     .await;
 }
 
+#[tokio::test]
+async fn test_write_atomic() {
+    run_test("write_atomic", |base_path| async move {
+        let path = base_path.join("atomic.txt");
+        write_atomic(&path, b"first").await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"first");
+
+        write_atomic(&path, b"second, longer").await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"second, longer");
+        write_atomic(&path, b"").await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"");
+
+        // No temporary files are left behind.
+        let mut entries = read_dir(&base_path).await.unwrap();
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            names.push(entry.file_name());
+        }
+        assert_eq!(names, ["atomic.txt"]);
+
+        // A symlink is followed, not replaced.
+        let link = base_path.join("link.txt");
+        symlink("atomic.txt", &link).await.unwrap();
+        write_atomic(&link, b"through link").await.unwrap();
+        assert!(symlink_metadata(&link).await.unwrap().is_symlink());
+        assert_eq!(read(&path).await.unwrap(), b"through link");
+
+        // Other hard links keep the data of the replaced file.
+        let hard = base_path.join("hard.txt");
+        write(&hard, b"old").await.unwrap();
+        let other = base_path.join("other.txt");
+        hard_link(&hard, &other).await.unwrap();
+        write_atomic(&hard, b"new").await.unwrap();
+        assert_eq!(read(&other).await.unwrap(), b"old");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_writable_file() {
+    run_test("writable_file", |base_path| async move {
+        let path = base_path.join("streamed.txt");
+        write(&path, b"old").await.unwrap();
+
+        let mut writer = File::create_streaming(&path).await.unwrap();
+        writer.write_all(b"hello ").await.unwrap();
+        writer.write_all(b"world, and more").await.unwrap();
+        writer.seek(io::SeekFrom::Start(0)).await.unwrap();
+        writer.write_all(b"J").await.unwrap();
+        writer.set_len(11).await.unwrap();
+        writer.flush().await.unwrap();
+
+        // Nothing is visible before the writer is closed.
+        assert_eq!(read(&path).await.unwrap(), b"old");
+        writer.close().await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"Jello world");
+
+        // Dropping a writer discards what it wrote.
+        let mut writer = WritableFile::create(&path).await.unwrap();
+        writer.write_all(b"discarded").await.unwrap();
+        writer.flush().await.unwrap();
+        drop(writer);
+        assert_eq!(read(&path).await.unwrap(), b"Jello world");
+
+        let mut entries = read_dir(&base_path).await.unwrap();
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            names.push(entry.file_name());
+        }
+        assert_eq!(names, ["streamed.txt"]);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_blob_reader() {
+    run_test("blob_reader", |base_path| async move {
+        let path = base_path.join("streamed.txt");
+        let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        write(&path, &data).await.unwrap();
+
+        let mut reader = File::open_streaming(&path).await.unwrap();
+        assert_eq!(reader.len(), data.len() as u64);
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, data);
+
+        reader.seek(io::SeekFrom::Start(1000)).await.unwrap();
+        let mut chunk = [0; 16];
+        reader.read_exact(&mut chunk).await.unwrap();
+        assert_eq!(chunk, data[1000..1016]);
+        reader.seek(io::SeekFrom::Current(-8)).await.unwrap();
+        reader.read_exact(&mut chunk).await.unwrap();
+        assert_eq!(chunk, data[1008..1024]);
+        assert_eq!(
+            reader.seek(io::SeekFrom::End(-4)).await.unwrap(),
+            data.len() as u64 - 4
+        );
+        buf.clear();
+        reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, data[data.len() - 4..]);
+
+        let lines_path = base_path.join("lines.txt");
+        write(&lines_path, b"first\nsecond\n").await.unwrap();
+        let mut reader = BlobReader::open(&lines_path).await.unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "first\n");
+        assert_eq!(reader.fill_buf().await.unwrap(), b"second\n");
+
+        // The reader holds no handle, so the file can still be opened for writing.
+        let mut file = File::create(&lines_path).await.unwrap();
+        file.write_all(b"replaced").await.unwrap();
+    })
+    .await;
+}
+
 #[tokio::test]
 #[allow(clippy::uninlined_format_args)]
 async fn test_file_copy() {
@@ -179,6 +296,70 @@ async fn test_file_rename() {
     .await;
 }
 
+#[tokio::test]
+async fn test_file_rename_overwrite() {
+    run_test("file_rename_overwrite", |base_path| async move {
+        let from = base_path.join("from.txt");
+        let to = base_path.join("to.txt");
+
+        write(&from, "new").await.unwrap();
+        write(&to, "old content").await.unwrap();
+        rename(&from, &to).await.unwrap();
+
+        assert!(!try_exists(&from).await.unwrap());
+        assert_eq!(read(&to).await.unwrap(), b"new");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_dir_rename() {
+    run_test("dir_rename", |base_path| async move {
+        let from = base_path.join("from");
+        let to = base_path.join("to");
+
+        create_dir_all(from.join("nested")).await.unwrap();
+        write(from.join("a.txt"), "a").await.unwrap();
+        write(from.join("nested/b.txt"), "b").await.unwrap();
+
+        rename(&from, &to).await.unwrap();
+
+        assert!(!try_exists(&from).await.unwrap());
+        assert_eq!(read(to.join("a.txt")).await.unwrap(), b"a");
+        assert_eq!(read(to.join("nested/b.txt")).await.unwrap(), b"b");
+
+        // The old location must not resolve through stale cached handles.
+        assert!(!try_exists(from.join("nested")).await.unwrap());
+        create_dir(&from).await.unwrap();
+        assert!(!try_exists(from.join("a.txt")).await.unwrap());
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_dir_rename_errors() {
+    run_test("dir_rename_errors", |base_path| async move {
+        let dir = base_path.join("dir");
+        let non_empty = base_path.join("non_empty");
+        let file = base_path.join("file.txt");
+
+        create_dir(&dir).await.unwrap();
+        create_dir(&non_empty).await.unwrap();
+        write(non_empty.join("keep.txt"), "keep").await.unwrap();
+        write(&file, "file").await.unwrap();
+
+        assert!(rename(&dir, &non_empty).await.is_err());
+        assert!(rename(&dir, &file).await.is_err());
+        assert!(rename(&file, &non_empty).await.is_err());
+        assert!(rename(&dir, dir.join("inner")).await.is_err());
+
+        assert!(try_exists(&dir).await.unwrap());
+        assert_eq!(read(non_empty.join("keep.txt")).await.unwrap(), b"keep");
+        assert_eq!(read(&file).await.unwrap(), b"file");
+    })
+    .await;
+}
+
 #[tokio::test]
 async fn test_file_read_to_string() {
     run_test("file_read_to_string", |base_path| async move {
@@ -417,6 +598,231 @@ async fn test_open_options_append() {
     .await;
 }
 
+#[tokio::test]
+async fn test_open_options_append_shared() {
+    run_test("open_options_append_shared", |base_path| async move {
+        let path = base_path.join("log.txt");
+        write(&path, b"start\n").await.unwrap();
+
+        let mut first = OpenOptions::new().append(true).open(&path).await.unwrap();
+        let mut second = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&path)
+            .await
+            .unwrap();
+
+        // Each write lands at the end, wherever the other one left it and
+        // wherever the cursor was moved to.
+        first.write_all(b"one\n").await.unwrap();
+        first.flush().await.unwrap();
+        second.seek(io::SeekFrom::Start(0)).await.unwrap();
+        second.write_all(b"two\n").await.unwrap();
+        second.flush().await.unwrap();
+        first.write_all(b"three\n").await.unwrap();
+        first.flush().await.unwrap();
+        assert_eq!(second.stream_position().await.unwrap(), 14);
+
+        second.seek(io::SeekFrom::Start(0)).await.unwrap();
+        let mut content = String::new();
+        second.read_to_string(&mut content).await.unwrap();
+        assert_eq!(content, "start\none\ntwo\nthree\n");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_open_options_validation() {
+    run_test("open_options_validation", |base_path| async move {
+        use backend::Backend;
+
+        let path = base_path.join("file.txt");
+        write(&path, b"data").await.unwrap();
+        let memory = backend::InMemoryFs::new();
+        memory.write("/file.txt", b"data").await.unwrap();
+
+        // Every invalid combination fails like it does with `std`.
+        for bits in 0..64u32 {
+            let set = |bit: u32| bits & (1 << bit) != 0;
+            let mut std_options = std::fs::OpenOptions::new();
+            std_options
+                .read(set(0))
+                .write(set(1))
+                .append(set(2))
+                .truncate(set(3))
+                .create(set(4))
+                .create_new(set(5));
+            let Err(expected) = std_options.open(&path) else {
+                continue;
+            };
+            if expected.kind() != io::ErrorKind::InvalidInput {
+                continue;
+            }
+
+            let mut options = OpenOptions::new();
+            options
+                .read(set(0))
+                .write(set(1))
+                .append(set(2))
+                .truncate(set(3))
+                .create(set(4))
+                .create_new(set(5));
+            for err in [
+                options.open(&path).await.unwrap_err(),
+                memory.open_with("/file.txt", &options).await.unwrap_err(),
+            ] {
+                assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+                assert_eq!(err.to_string(), expected.to_string());
+            }
+        }
+    })
+    .await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_open_options_mode() {
+    run_test("open_options_mode", |base_path| async move {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = base_path.join("private.txt");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .custom_flags(0)
+            .open(&path)
+            .await
+            .unwrap();
+        file.write_all(b"secret").await.unwrap();
+        file.flush().await.unwrap();
+        let mode = metadata(&path).await.unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    })
+    .await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlink_file() {
+    run_test("symlink_file", |base_path| async move {
+        let target = base_path.join("target.txt");
+        let link = base_path.join("link.txt");
+
+        write(&target, "content").await.unwrap();
+        symlink("target.txt", &link).await.unwrap();
+
+        assert_eq!(read(&link).await.unwrap(), b"content");
+        assert_eq!(read_link(&link).await.unwrap(), PathBuf::from("target.txt"));
+        assert!(symlink_metadata(&link).await.unwrap().is_symlink());
+        assert!(metadata(&link).await.unwrap().is_file());
+        assert_eq!(
+            symlink("target.txt", &link).await.unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        write(&link, "through link").await.unwrap();
+        assert_eq!(read(&target).await.unwrap(), b"through link");
+
+        remove_file(&link).await.unwrap();
+        assert!(!try_exists(&link).await.unwrap());
+        assert!(try_exists(&target).await.unwrap());
+    })
+    .await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlink_dir() {
+    run_test("symlink_dir", |base_path| async move {
+        let dir = base_path.join("dir");
+        let link = base_path.join("link");
+
+        create_dir_all(dir.join("sub")).await.unwrap();
+        write(dir.join("sub/file.txt"), "nested").await.unwrap();
+        symlink(&dir, &link).await.unwrap();
+
+        assert_eq!(read(link.join("sub/file.txt")).await.unwrap(), b"nested");
+        assert!(metadata(&link).await.unwrap().is_dir());
+        assert_eq!(
+            canonicalize(link.join("sub/file.txt")).await.unwrap(),
+            canonicalize(dir.join("sub/file.txt")).await.unwrap()
+        );
+
+        let mut rd = read_dir(&base_path).await.unwrap();
+        let mut found = false;
+        while let Some(entry) = rd.next_entry().await.unwrap() {
+            if entry.file_name() == "link" {
+                assert!(entry.file_type().await.unwrap().is_symlink());
+                found = true;
+            }
+        }
+        assert!(found);
+
+        remove_file(&link).await.unwrap();
+        assert!(try_exists(dir.join("sub/file.txt")).await.unwrap());
+    })
+    .await;
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_symlink_loop() {
+    run_test("symlink_loop", |base_path| async move {
+        let a = base_path.join("a");
+        let b = base_path.join("b");
+
+        symlink(&b, &a).await.unwrap();
+        symlink(&a, &b).await.unwrap();
+
+        assert!(read(&a).await.is_err());
+        assert!(canonicalize(&a).await.is_err());
+        assert!(!try_exists(&a).await.unwrap_or(false));
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_hard_link() {
+    run_test("hard_link", |base_path| async move {
+        let original = base_path.join("original.txt");
+        let link = base_path.join("link.txt");
+        write(&original, b"shared").await.unwrap();
+
+        hard_link(&original, &link).await.unwrap();
+        assert_eq!(read(&link).await.unwrap(), b"shared");
+        assert!(symlink_metadata(&link).await.unwrap().is_file());
+
+        // Writes through one link are visible through the other.
+        write(&link, b"changed").await.unwrap();
+        assert_eq!(read(&original).await.unwrap(), b"changed");
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&original)
+            .await
+            .unwrap();
+        file.write_all(b"!").await.unwrap();
+        file.flush().await.unwrap();
+        drop(file);
+        assert_eq!(read(&link).await.unwrap(), b"changed!");
+
+        let err = hard_link(&original, &link).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = hard_link(base_path.join("missing"), base_path.join("other"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        // The data outlives any single link.
+        remove_file(&original).await.unwrap();
+        assert!(!try_exists(&original).await.unwrap());
+        assert_eq!(read(&link).await.unwrap(), b"changed!");
+        remove_file(&link).await.unwrap();
+        assert!(!try_exists(&link).await.unwrap());
+    })
+    .await;
+}
+
 #[tokio::test]
 async fn test_metadata_not_found() {
     run_test("metadata_not_found", |base_path| async move {
@@ -453,6 +859,67 @@ async fn test_metadata_is_file_and_len() {
     .await;
 }
 
+#[tokio::test]
+async fn test_set_permissions_readonly() {
+    run_test("set_permissions_readonly", |base_path| async move {
+        let path = base_path.join("readonly.txt");
+        write(&path, b"locked").await.unwrap();
+
+        let original = metadata(&path).await.unwrap().permissions();
+        let mut perms = original.clone();
+        perms.set_readonly(true);
+        set_permissions(&path, perms).await.unwrap();
+        assert!(metadata(&path).await.unwrap().permissions().readonly());
+
+        set_permissions(&path, original).await.unwrap();
+        assert!(!metadata(&path).await.unwrap().permissions().readonly());
+
+        let perms = metadata(&path).await.unwrap().permissions();
+        let err = set_permissions(base_path.join("missing"), perms)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_storage() {
+    run_test("storage", |base_path| async move {
+        let estimate = storage::estimate().await.unwrap();
+        assert!(estimate.quota() >= estimate.usage());
+        assert_eq!(estimate.available(), estimate.quota() - estimate.usage());
+        assert!(storage::persist().await.unwrap());
+        assert!(storage::persisted().await.unwrap());
+
+        write(base_path.join("a.txt"), b"12345").await.unwrap();
+        create_dir_all(base_path.join("sub/deeper")).await.unwrap();
+        write(base_path.join("sub/b.txt"), b"123").await.unwrap();
+        write(base_path.join("sub/deeper/c.txt"), b"1")
+            .await
+            .unwrap();
+        // Links are not followed.
+        #[cfg(unix)]
+        symlink(base_path.join("sub"), base_path.join("link"))
+            .await
+            .unwrap();
+        assert_eq!(storage::disk_usage(&base_path).await.unwrap(), 9);
+        assert_eq!(storage::disk_usage(base_path.join("sub")).await.unwrap(), 4);
+        assert_eq!(
+            storage::disk_usage(base_path.join("a.txt")).await.unwrap(),
+            5
+        );
+        assert_eq!(
+            storage::disk_usage(base_path.join("missing"))
+                .await
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+    })
+    .await;
+}
+
 #[tokio::test]
 async fn test_async_seek() {
     run_test("async_seek", |base_path| async move {
@@ -504,6 +971,155 @@ async fn test_async_seek() {
     .await;
 }
 
+#[tokio::test]
+async fn test_file_positional_io() {
+    run_test("file_positional_io", |base_path| async move {
+        let path = base_path.join("positional.bin");
+        write(&path, b"0123456789").await.unwrap();
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .await
+            .unwrap();
+        file.seek(io::SeekFrom::Start(2)).await.unwrap();
+
+        let mut buf = [0; 3];
+        assert_eq!(file.read_at(&mut buf, 5).await.unwrap(), 3);
+        assert_eq!(&buf, b"567");
+        assert_eq!(file.write_at(b"ab", 8).await.unwrap(), 2);
+        file.write_all_at(b"XYZ", 10).await.unwrap();
+
+        let mut buf = [0; 13];
+        file.read_exact_at(&mut buf, 0).await.unwrap();
+        assert_eq!(&buf, b"01234567abXYZ");
+        let err = file.read_exact_at(&mut buf, 1).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(file.read_at(&mut buf, 100).await.unwrap(), 0);
+
+        // The cursor is left where it was.
+        assert_eq!(file.stream_position().await.unwrap(), 2);
+        let mut buf = [0; 2];
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"23");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_file_try_clone() {
+    run_test("file_try_clone", |base_path| async move {
+        let path = base_path.join("clone.txt");
+        write(&path, b"0123456789").await.unwrap();
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .await
+            .unwrap();
+        let mut clone = file.try_clone().await.unwrap();
+        clone.set_max_buf_size(4);
+
+        // Both share the cursor of the underlying file descriptor.
+        file.seek(io::SeekFrom::Start(6)).await.unwrap();
+        let mut buf = [0; 4];
+        clone.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"6789");
+        clone.write_all(b"ab").await.unwrap();
+        clone.flush().await.unwrap();
+        drop(clone);
+
+        let std_file = file.into_std().await;
+        let file = File::from_std(std_file);
+        assert_eq!(file.into_inner().metadata().await.unwrap().len(), 12);
+        assert_eq!(read(&path).await.unwrap(), b"0123456789ab");
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_file_lock() {
+    run_test("file_lock", |base_path| async move {
+        let path = base_path.join("lockfile");
+        write(&path, b"").await.unwrap();
+        let a = File::open(&path).await.unwrap();
+        let b = File::open(&path).await.unwrap();
+
+        a.lock().await.unwrap();
+        assert!(matches!(b.try_lock().await, Err(TryLockError::WouldBlock)));
+        assert!(matches!(
+            b.try_lock_shared().await,
+            Err(TryLockError::WouldBlock)
+        ));
+
+        // `b` gets the lock once `a` lets go of it.
+        let (locked, unlocked) = futures::join!(b.lock(), a.unlock());
+        locked.unwrap();
+        unlocked.unwrap();
+        assert!(matches!(a.try_lock().await, Err(TryLockError::WouldBlock)));
+
+        // Shared locks coexist but exclude exclusive ones.
+        b.unlock().await.unwrap();
+        a.lock_shared().await.unwrap();
+        b.try_lock_shared().await.unwrap();
+        assert!(matches!(a.try_lock().await, Err(TryLockError::WouldBlock)));
+
+        // Dropping a file releases its lock, and a lock can be upgraded.
+        drop(b);
+        a.try_lock().await.unwrap();
+        let c = File::open(&path).await.unwrap();
+        assert!(matches!(
+            c.try_lock_shared().await,
+            Err(TryLockError::WouldBlock)
+        ));
+        drop(a);
+        c.try_lock().await.unwrap();
+
+        // Clones share the lock of the file they were made from.
+        let d = c.try_clone().await.unwrap();
+        d.try_lock().await.unwrap();
+        d.unlock().await.unwrap();
+        let e = File::open(&path).await.unwrap();
+        e.try_lock().await.unwrap();
+    })
+    .await;
+}
+
+#[tokio::test]
+#[cfg(feature = "tokio-io")]
+async fn test_file_tokio_io() {
+    run_test("file_tokio_io", |base_path| async move {
+        use tokio::io::{AsyncBufReadExt, AsyncSeekExt, AsyncWriteExt};
+
+        let path = base_path.join("tokio_io.txt");
+        let mut file = File::create(&path).await.unwrap();
+        AsyncWriteExt::write_all(&mut file, b"first\nsecond\n")
+            .await
+            .unwrap();
+        AsyncWriteExt::flush(&mut file).await.unwrap();
+        drop(file);
+
+        let mut file = File::open(&path).await.unwrap();
+        AsyncSeekExt::seek(&mut file, io::SeekFrom::Start(6))
+            .await
+            .unwrap();
+        let mut copied = Vec::new();
+        tokio::io::copy(&mut file, &mut copied).await.unwrap();
+        assert_eq!(copied, b"second\n");
+
+        AsyncSeekExt::seek(&mut file, io::SeekFrom::Start(0))
+            .await
+            .unwrap();
+        let mut lines = tokio::io::BufReader::new(file).lines();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "first");
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "second");
+        assert_eq!(lines.next_line().await.unwrap(), None);
+    })
+    .await;
+}
+
 #[tokio::test]
 async fn test_current_dir() {
     run_test("current_dir", |base_path| async move {
@@ -579,3 +1195,204 @@ async fn test_mix_file_lock_and_async_api() {
     })
     .await;
 }
+
+async fn exercise_backend<B: backend::Backend>(fs: &B, base_path: &std::path::Path) {
+    use backend::{FsDirEntry, FsMetadata};
+
+    let dir = base_path.join("dir");
+    let file = dir.join("file.txt");
+
+    fs.create_dir_all(&dir).await.unwrap();
+    fs.write(&file, b"hello").await.unwrap();
+    assert_eq!(fs.read_to_string(&file).await.unwrap(), "hello");
+    assert_eq!(fs.metadata(&file).await.unwrap().len(), 5);
+    assert!(fs.metadata(&dir).await.unwrap().is_dir());
+
+    {
+        let mut f = fs
+            .open_with(&file, OpenOptions::new().append(true))
+            .await
+            .unwrap();
+        f.write_all(b" world").await.unwrap();
+    }
+    let mut buf = String::new();
+    fs.open(&file)
+        .await
+        .unwrap()
+        .read_to_string(&mut buf)
+        .await
+        .unwrap();
+    assert_eq!(buf, "hello world");
+
+    let renamed = dir.join("renamed.txt");
+    fs.rename(&file, &renamed).await.unwrap();
+    assert!(!fs.try_exists(&file).await.unwrap());
+
+    let names = fs
+        .read_dir(&dir)
+        .await
+        .unwrap()
+        .map_ok(|e| e.file_name().to_string_lossy().to_string())
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(names, vec!["renamed.txt".to_string()]);
+
+    let link = dir.join("link.txt");
+    fs.hard_link(&renamed, &link).await.unwrap();
+    fs.write(&link, b"linked").await.unwrap();
+    assert_eq!(fs.read(&renamed).await.unwrap(), b"linked");
+    assert!(!fs.symlink_metadata(&link).await.unwrap().is_symlink());
+    assert_eq!(
+        fs.hard_link(&renamed, &link).await.unwrap_err().kind(),
+        io::ErrorKind::AlreadyExists
+    );
+
+    // Any `Permissions` value can serve as a template.
+    let original = std::fs::metadata(env!("CARGO_MANIFEST_DIR"))
+        .unwrap()
+        .permissions();
+    let mut perm = original.clone();
+    perm.set_readonly(true);
+    fs.set_permissions(&link, perm).await.unwrap();
+    assert!(fs.metadata(&renamed).await.unwrap().readonly());
+    fs.set_permissions(&link, original).await.unwrap();
+    assert!(!fs.metadata(&renamed).await.unwrap().readonly());
+
+    assert_eq!(
+        fs.remove_dir(&dir).await.unwrap_err().kind(),
+        io::ErrorKind::DirectoryNotEmpty
+    );
+    fs.remove_dir_all(&dir).await.unwrap();
+    assert!(!fs.try_exists(&dir).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_backend_default_fs() {
+    run_test("backend_default_fs", |base_path| async move {
+        exercise_backend(&backend::DefaultFs, &base_path).await;
+    })
+    .await;
+}
+
+#[tokio::test]
+async fn test_backend_in_memory_fs() {
+    let fs = backend::InMemoryFs::new();
+    exercise_backend(&fs, std::path::Path::new("/base")).await;
+
+    use backend::{Backend, FsMetadata};
+    assert_eq!(
+        fs.read("/missing").await.unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+    assert_eq!(
+        fs.create_dir("/a/b").await.unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+    assert_eq!(
+        fs.canonicalize("base/../base").await.unwrap(),
+        PathBuf::from("/base")
+    );
+
+    fs.write("/file.txt", b"data").await.unwrap();
+    fs.hard_link("/file.txt", "/link.txt").await.unwrap();
+    assert_eq!(
+        fs.hard_link("/base", "/dir_link").await.unwrap_err().kind(),
+        io::ErrorKind::PermissionDenied
+    );
+    assert_eq!(
+        fs.read_link("/file.txt").await.unwrap_err().kind(),
+        io::ErrorKind::InvalidInput
+    );
+
+    // Symlinks are followed in every component, relative to the link's directory.
+    fs.symlink("../file.txt", "/base/symlink").await.unwrap();
+    fs.symlink("/base", "/base_link").await.unwrap();
+    assert_eq!(fs.read("/base_link/symlink").await.unwrap(), b"data");
+    assert_eq!(
+        fs.read_link("/base/symlink").await.unwrap(),
+        PathBuf::from("../file.txt")
+    );
+    assert!(
+        fs.symlink_metadata("/base_link")
+            .await
+            .unwrap()
+            .is_symlink()
+    );
+    assert!(fs.metadata("/base_link").await.unwrap().is_dir());
+    assert_eq!(
+        fs.canonicalize("/base_link/symlink").await.unwrap(),
+        PathBuf::from("/file.txt")
+    );
+    assert_eq!(
+        fs.symlink("/file.txt", "/base_link")
+            .await
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::AlreadyExists
+    );
+    fs.symlink("/missing", "/dangling").await.unwrap();
+    assert!(!fs.try_exists("/dangling").await.unwrap());
+    fs.symlink("/loop", "/loop").await.unwrap();
+    assert!(fs.read("/loop").await.is_err());
+    fs.remove_file("/base/symlink").await.unwrap();
+    assert_eq!(fs.read("/file.txt").await.unwrap(), b"data");
+    fs.remove_dir_all("/base_link").await.unwrap();
+    assert!(fs.try_exists("/base").await.unwrap());
+
+    // A read-only file refuses writes through any of its links.
+    let mut perm = std::fs::metadata(env!("CARGO_MANIFEST_DIR"))
+        .unwrap()
+        .permissions();
+    perm.set_readonly(true);
+    fs.set_permissions("/file.txt", perm).await.unwrap();
+    let denied = io::ErrorKind::PermissionDenied;
+    assert_eq!(
+        fs.write("/link.txt", b"x").await.unwrap_err().kind(),
+        denied
+    );
+    assert_eq!(fs.create("/link.txt").await.unwrap_err().kind(), denied);
+    assert_eq!(
+        fs.remove_file("/file.txt").await.unwrap_err().kind(),
+        denied
+    );
+    assert_eq!(fs.read("/link.txt").await.unwrap(), b"data");
+}
+
+#[tokio::test]
+async fn test_backend_dyn() {
+    run_test("backend_dyn", |base_path| async move {
+        use backend::DynBackend;
+
+        let backends: Vec<(Box<dyn DynBackend>, PathBuf)> = vec![
+            (Box::new(backend::DefaultFs), base_path.clone()),
+            (Box::new(backend::InMemoryFs::new()), PathBuf::from("/base")),
+        ];
+        for (fs, base) in &backends {
+            // The box is itself a `Backend`.
+            exercise_backend(fs, base).await;
+
+            let fs: &dyn DynBackend = &**fs;
+            let file = base.join("dyn.txt");
+            fs.create_dir_all(base).await.unwrap();
+            let mut created = fs.create(&file).await.unwrap();
+            created.write_all(b"boxed").await.unwrap();
+            // `tokio::fs::File` finishes writes in the background until flushed.
+            created.flush().await.unwrap();
+            drop(created);
+            assert_eq!(fs.read_to_string(&file).await.unwrap(), "boxed");
+            assert_eq!(fs.metadata(&file).await.unwrap().len(), 5);
+            let entries = fs
+                .read_dir(base)
+                .await
+                .unwrap()
+                .try_collect::<Vec<_>>()
+                .await
+                .unwrap();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].path(), file);
+            fs.remove_file(&file).await.unwrap();
+        }
+    })
+    .await;
+}
@@ -6,7 +6,7 @@ use std::{io, path::PathBuf, str};
 
 use futures::{
     TryStreamExt,
-    io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncSeekExt, AsyncWriteExt},
 };
 use tokio_fs_ext::*;
 use wasm_bindgen_test::{wasm_bindgen_test_configure, *};
@@ -117,6 +117,43 @@ async fn test_dir_read_dir_stream() {
     .await;
 }
 
+#[wasm_bindgen_test]
+async fn test_dir_read_dir_many_entries() {
+    run_test("dir_read_dir_many_entries", |base_path| async move {
+        let count = 200;
+        for i in 0..count {
+            write(base_path.join(format!("file_{i}")), "")
+                .await
+                .unwrap();
+        }
+
+        let mut rd = read_dir(&base_path).await.unwrap();
+        let mut names = Vec::new();
+        while let Some(e) = rd.next_entry().await.unwrap() {
+            names.push(e.file_name().to_string_lossy().to_string());
+        }
+        assert!(rd.next_entry().await.unwrap().is_none());
+
+        let streamed = ReadDirStream::new(read_dir(&base_path).await.unwrap())
+            .map_ok(|e| e.file_name().to_string_lossy().to_string())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(streamed, names);
+
+        // A lazily read directory cannot be iterated synchronously.
+        let mut lazy = read_dir(&base_path).await.unwrap();
+        let err = lazy.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        assert!(lazy.next().is_none());
+
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), count);
+    })
+    .await;
+}
+
 #[wasm_bindgen_test]
 async fn test_dir_non_existent_path() {
     // run_test manages its own dir. We test a path OUTSIDE of it.
@@ -142,6 +179,125 @@ async fn test_file_create_write_read() {
     .await;
 }
 
+#[wasm_bindgen_test]
+async fn test_write_atomic() {
+    run_test("write_atomic", |base_path| async move {
+        let path = base_path.join("atomic.txt");
+        write_atomic(&path, b"first").await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"first");
+
+        write_atomic(&path, b"second, longer").await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"second, longer");
+        write_atomic(&path, b"").await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"");
+
+        // No temporary files are left behind.
+        let mut entries = read_dir(&base_path).await.unwrap();
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            names.push(entry.file_name());
+        }
+        assert_eq!(names, ["atomic.txt"]);
+
+        // A symlink is followed, not replaced.
+        let link = base_path.join("link.txt");
+        symlink("atomic.txt", &link).await.unwrap();
+        write_atomic(&link, b"through link").await.unwrap();
+        assert!(symlink_metadata(&link).await.unwrap().is_symlink());
+        assert_eq!(read(&path).await.unwrap(), b"through link");
+
+        // Hard links share their data, so they see the new contents.
+        let hard = base_path.join("hard.txt");
+        write(&hard, b"old").await.unwrap();
+        let other = base_path.join("other.txt");
+        hard_link(&hard, &other).await.unwrap();
+        write_atomic(&hard, b"new").await.unwrap();
+        assert_eq!(read(&other).await.unwrap(), b"new");
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_writable_file() {
+    run_test("writable_file", |base_path| async move {
+        let path = base_path.join("streamed.txt");
+        write(&path, b"old").await.unwrap();
+
+        let mut writer = File::create_streaming(&path).await.unwrap();
+        writer.write_all(b"hello ").await.unwrap();
+        writer.write_all(b"world, and more").await.unwrap();
+        writer.seek(io::SeekFrom::Start(0)).await.unwrap();
+        writer.write_all(b"J").await.unwrap();
+        writer.set_len(11).await.unwrap();
+        writer.flush().await.unwrap();
+
+        // Nothing is visible before the writer is closed. `read` would wait
+        // for the writer's lock, so look at the size instead.
+        assert_eq!(metadata(&path).await.unwrap().len(), 3);
+        writer.close().await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"Jello world");
+        assert_eq!(read(&path).await.unwrap(), b"Jello world");
+
+        // Dropping a writer discards what it wrote.
+        let mut writer = WritableFile::create(&path).await.unwrap();
+        writer.write_all(b"discarded").await.unwrap();
+        writer.flush().await.unwrap();
+        drop(writer);
+        assert_eq!(read(&path).await.unwrap(), b"Jello world");
+
+        let mut entries = read_dir(&base_path).await.unwrap();
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            names.push(entry.file_name());
+        }
+        assert_eq!(names, ["streamed.txt"]);
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_blob_reader() {
+    run_test("blob_reader", |base_path| async move {
+        let path = base_path.join("streamed.txt");
+        let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+        write(&path, &data).await.unwrap();
+
+        let mut reader = File::open_streaming(&path).await.unwrap();
+        assert_eq!(reader.len(), data.len() as u64);
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, data);
+
+        reader.seek(io::SeekFrom::Start(1000)).await.unwrap();
+        let mut chunk = [0; 16];
+        reader.read_exact(&mut chunk).await.unwrap();
+        assert_eq!(chunk, data[1000..1016]);
+        reader.seek(io::SeekFrom::Current(-8)).await.unwrap();
+        reader.read_exact(&mut chunk).await.unwrap();
+        assert_eq!(chunk, data[1008..1024]);
+        assert_eq!(
+            reader.seek(io::SeekFrom::End(-4)).await.unwrap(),
+            data.len() as u64 - 4
+        );
+        buf.clear();
+        reader.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, data[data.len() - 4..]);
+
+        let lines_path = base_path.join("lines.txt");
+        write(&lines_path, b"first\nsecond\n").await.unwrap();
+        let mut reader = BlobReader::open(&lines_path).await.unwrap();
+        let mut line = String::new();
+        reader.read_line(&mut line).await.unwrap();
+        assert_eq!(line, "first\n");
+        assert_eq!(reader.fill_buf().await.unwrap(), b"second\n");
+
+        // The reader holds no handle, so the file can still be opened for writing.
+        let mut file = File::create(&lines_path).await.unwrap();
+        file.write_all(b"replaced").await.unwrap();
+    })
+    .await;
+}
+
 #[wasm_bindgen_test]
 #[allow(clippy::uninlined_format_args)]
 async fn test_file_copy() {
@@ -177,6 +333,139 @@ async fn test_file_rename() {
     .await;
 }
 
+#[wasm_bindgen_test]
+async fn test_file_rename_overwrite() {
+    run_test("file_rename_overwrite", |base_path| async move {
+        let from = base_path.join("from.txt");
+        let to = base_path.join("to.txt");
+
+        write(&from, "new").await.unwrap();
+        write(&to, "old content").await.unwrap();
+        rename(&from, &to).await.unwrap();
+
+        assert!(!try_exists(&from).await.unwrap());
+        assert_eq!(read(&to).await.unwrap(), b"new");
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_dir_rename() {
+    run_test("dir_rename", |base_path| async move {
+        let from = base_path.join("from");
+        let to = base_path.join("to");
+
+        create_dir_all(from.join("nested")).await.unwrap();
+        write(from.join("a.txt"), "a").await.unwrap();
+        write(from.join("nested/b.txt"), "b").await.unwrap();
+
+        rename(&from, &to).await.unwrap();
+
+        assert!(!try_exists(&from).await.unwrap());
+        assert_eq!(read(to.join("a.txt")).await.unwrap(), b"a");
+        assert_eq!(read(to.join("nested/b.txt")).await.unwrap(), b"b");
+
+        // The old location must not resolve through stale cached handles.
+        assert!(!try_exists(from.join("nested")).await.unwrap());
+        create_dir(&from).await.unwrap();
+        assert!(!try_exists(from.join("a.txt")).await.unwrap());
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_dir_rename_errors() {
+    run_test("dir_rename_errors", |base_path| async move {
+        let dir = base_path.join("dir");
+        let non_empty = base_path.join("non_empty");
+        let file = base_path.join("file.txt");
+
+        create_dir(&dir).await.unwrap();
+        create_dir(&non_empty).await.unwrap();
+        write(non_empty.join("keep.txt"), "keep").await.unwrap();
+        write(&file, "file").await.unwrap();
+
+        assert!(rename(&dir, &non_empty).await.is_err());
+        assert!(rename(&dir, &file).await.is_err());
+        assert!(rename(&file, &non_empty).await.is_err());
+        assert!(rename(&dir, dir.join("inner")).await.is_err());
+
+        assert!(try_exists(&dir).await.unwrap());
+        assert_eq!(read(non_empty.join("keep.txt")).await.unwrap(), b"keep");
+        assert_eq!(read(&file).await.unwrap(), b"file");
+    })
+    .await;
+}
+
+/// Replaces `class.prototype[name]` with `value`, returning a closure that restores it.
+fn override_method(
+    class: &str,
+    name: &str,
+    value: &wasm_bindgen::JsValue,
+) -> impl FnOnce() + use<> {
+    use js_sys::Reflect;
+    let class = Reflect::get(&js_sys::global(), &class.into()).unwrap();
+    let prototype = Reflect::get(&class, &"prototype".into()).unwrap();
+    let original = Reflect::get(&prototype, &name.into()).unwrap();
+    Reflect::set(&prototype, &name.into(), value).unwrap();
+    let name = name.to_string();
+    move || {
+        Reflect::set(&prototype, &name.into(), &original).unwrap();
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_dir_rename_rollback() {
+    run_test("dir_rename_rollback", |base_path| async move {
+        let from = base_path.join("from");
+        let to = base_path.join("to");
+        create_dir(&from).await.unwrap();
+        write(from.join("a.txt"), "a").await.unwrap();
+        create_dir(&to).await.unwrap();
+
+        let reject = js_sys::Function::new_no_args(
+            "return Promise.reject(new DOMException('injected', 'SecurityError'))",
+        );
+        let assert_untouched = async || {
+            assert_eq!(read(from.join("a.txt")).await.unwrap(), b"a");
+            assert!(metadata(&to).await.unwrap().is_dir());
+            assert!(
+                read_dir(&to)
+                    .await
+                    .unwrap()
+                    .next_entry()
+                    .await
+                    .unwrap()
+                    .is_none()
+            );
+        };
+
+        // `move()` fails after the empty `to` was removed.
+        let restore = override_method("FileSystemHandle", "move", &reject);
+        let res = rename(&from, &to).await;
+        restore();
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_untouched().await;
+
+        // Without `move()`, copying fails halfway.
+        let restore_move = override_method(
+            "FileSystemHandle",
+            "move",
+            &wasm_bindgen::JsValue::UNDEFINED,
+        );
+        let restore_writable = override_method("FileSystemFileHandle", "createWritable", &reject);
+        let res = rename(&from, &to).await;
+        restore_writable();
+        restore_move();
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        assert_untouched().await;
+
+        rename(&from, &to).await.unwrap();
+        assert_eq!(read(to.join("a.txt")).await.unwrap(), b"a");
+    })
+    .await;
+}
+
 #[wasm_bindgen_test]
 async fn test_file_read_to_string() {
     run_test("file_read_to_string", |base_path| async move {
@@ -408,6 +697,292 @@ async fn test_open_options_append() {
     .await;
 }
 
+#[wasm_bindgen_test]
+async fn test_open_options_append_shared() {
+    run_test("open_options_append_shared", |base_path| async move {
+        let path = base_path.join("log.txt");
+        write(&path, b"start\n").await.unwrap();
+
+        let mut first = OpenOptions::new().append(true).open(&path).await.unwrap();
+        let mut second = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&path)
+            .await
+            .unwrap();
+
+        // Each write lands at the end, wherever the other one left it and
+        // wherever the cursor was moved to.
+        first.write_all(b"one\n").await.unwrap();
+        first.flush().await.unwrap();
+        second.seek(io::SeekFrom::Start(0)).await.unwrap();
+        second.write_all(b"two\n").await.unwrap();
+        second.flush().await.unwrap();
+        first.write_all(b"three\n").await.unwrap();
+        first.flush().await.unwrap();
+        assert_eq!(second.stream_position().await.unwrap(), 14);
+
+        second.seek(io::SeekFrom::Start(0)).await.unwrap();
+        let mut content = String::new();
+        second.read_to_string(&mut content).await.unwrap();
+        assert_eq!(content, "start\none\ntwo\nthree\n");
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_open_options_validation() {
+    run_test("open_options_validation", |base_path| async move {
+        let path = base_path.join("file.txt");
+        write(&path, b"data").await.unwrap();
+
+        let no_access = "must specify at least one of read, write, or append access";
+        let no_write = "creating or truncating a file requires write or append access";
+        let err = OpenOptions::new().open(&path).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), no_access);
+        for options in [
+            OpenOptions::new().read(true).truncate(true),
+            OpenOptions::new().create(true),
+            OpenOptions::new().read(true).create_new(true),
+            OpenOptions::new().append(true).truncate(true),
+        ] {
+            let err = options.open(&path).await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+            assert_eq!(err.to_string(), no_write);
+        }
+
+        // Appending to a new file is fine, even with `truncate`.
+        OpenOptions::new()
+            .append(true)
+            .truncate(true)
+            .create_new(true)
+            .open(base_path.join("new.txt"))
+            .await
+            .unwrap();
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_open_options_mode() {
+    run_test("open_options_mode", |base_path| async move {
+        let path = base_path.join("readonly.txt");
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o444)
+            .custom_flags(0)
+            .open(&path)
+            .await
+            .unwrap();
+
+        // The file that created it can still write.
+        file.write_all(b"data").await.unwrap();
+        drop(file);
+        let perms = metadata(&path).await.unwrap().permissions();
+        assert_eq!(perms.mode(), 0o444);
+        assert!(perms.readonly());
+        let err = OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+
+        // Existing files keep their mode.
+        let other = base_path.join("other.txt");
+        write(&other, b"data").await.unwrap();
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .mode(0o600)
+            .open(&other)
+            .await
+            .unwrap();
+        assert_eq!(metadata(&other).await.unwrap().permissions().mode(), 0o666);
+
+        let mut perms = metadata(&path).await.unwrap().permissions();
+        perms.set_readonly(false);
+        assert_eq!(perms.mode(), 0o666);
+        set_permissions(&path, perms).await.unwrap();
+        write(&path, b"changed").await.unwrap();
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_reserved_names() {
+    run_test("reserved_names", |base_path| async move {
+        write(base_path.join("file.txt"), b"data").await.unwrap();
+        for name in [
+            "file.txt.__symlink__",
+            ".__hardlink__",
+            ".__tokio_fs_ext_blobs__",
+            ".__tokio_fs_ext_meta__",
+        ] {
+            let path = base_path.join(name);
+            let invalid = io::ErrorKind::InvalidInput;
+            assert_eq!(write(&path, b"x").await.unwrap_err().kind(), invalid);
+            assert_eq!(create_dir(&path).await.unwrap_err().kind(), invalid);
+            assert_eq!(File::create(&path).await.unwrap_err().kind(), invalid);
+            let original = base_path.join("file.txt");
+            assert_eq!(symlink(&original, &path).await.unwrap_err().kind(), invalid);
+            assert_eq!(
+                hard_link(&original, &path).await.unwrap_err().kind(),
+                invalid
+            );
+            assert_eq!(rename(&original, &path).await.unwrap_err().kind(), invalid);
+        }
+        assert_eq!(read(base_path.join("file.txt")).await.unwrap(), b"data");
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_symlink_file() {
+    run_test("symlink_file", |base_path| async move {
+        let target = base_path.join("target.txt");
+        let link = base_path.join("link.txt");
+
+        write(&target, "content").await.unwrap();
+        symlink("target.txt", &link).await.unwrap();
+
+        assert_eq!(read(&link).await.unwrap(), b"content");
+        assert_eq!(read_link(&link).await.unwrap(), PathBuf::from("target.txt"));
+        assert!(symlink_metadata(&link).await.unwrap().is_symlink());
+        assert!(metadata(&link).await.unwrap().is_file());
+        assert_eq!(
+            symlink("target.txt", &link).await.unwrap_err().kind(),
+            io::ErrorKind::AlreadyExists
+        );
+
+        write(&link, "through link").await.unwrap();
+        assert_eq!(read(&target).await.unwrap(), b"through link");
+
+        remove_file(&link).await.unwrap();
+        assert!(!try_exists(&link).await.unwrap());
+        assert!(try_exists(&target).await.unwrap());
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_symlink_dir() {
+    run_test("symlink_dir", |base_path| async move {
+        let dir = base_path.join("dir");
+        let link = base_path.join("link");
+
+        create_dir_all(dir.join("sub")).await.unwrap();
+        write(dir.join("sub/file.txt"), "nested").await.unwrap();
+        symlink(&dir, &link).await.unwrap();
+
+        assert_eq!(read(link.join("sub/file.txt")).await.unwrap(), b"nested");
+        assert!(metadata(&link).await.unwrap().is_dir());
+        assert_eq!(
+            canonicalize(link.join("sub/file.txt")).await.unwrap(),
+            canonicalize(dir.join("sub/file.txt")).await.unwrap()
+        );
+
+        let mut rd = read_dir(&base_path).await.unwrap();
+        let mut found = false;
+        while let Some(entry) = rd.next_entry().await.unwrap() {
+            if entry.file_name() == "link" {
+                assert!(entry.file_type().unwrap().is_symlink());
+                found = true;
+            }
+        }
+        assert!(found);
+
+        remove_file(&link).await.unwrap();
+        assert!(try_exists(dir.join("sub/file.txt")).await.unwrap());
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_symlink_loop() {
+    run_test("symlink_loop", |base_path| async move {
+        let a = base_path.join("a");
+        let b = base_path.join("b");
+
+        symlink(&b, &a).await.unwrap();
+        symlink(&a, &b).await.unwrap();
+
+        assert!(read(&a).await.is_err());
+        assert!(canonicalize(&a).await.is_err());
+        assert!(!try_exists(&a).await.unwrap_or(false));
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_hard_link() {
+    run_test("hard_link", |base_path| async move {
+        let original = base_path.join("original.txt");
+        let link = base_path.join("link.txt");
+        write(&original, b"shared").await.unwrap();
+
+        hard_link(&original, &link).await.unwrap();
+        assert_eq!(read(&link).await.unwrap(), b"shared");
+        assert!(symlink_metadata(&link).await.unwrap().is_file());
+
+        // Writes through one link are visible through the other.
+        write(&link, b"changed").await.unwrap();
+        assert_eq!(read(&original).await.unwrap(), b"changed");
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&original)
+            .await
+            .unwrap();
+        file.write_all(b"!").await.unwrap();
+        file.flush().await.unwrap();
+        drop(file);
+        assert_eq!(read(&link).await.unwrap(), b"changed!");
+
+        let err = hard_link(&original, &link).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = hard_link(base_path.join("missing"), base_path.join("other"))
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+
+        // The data outlives any single link.
+        remove_file(&original).await.unwrap();
+        assert!(!try_exists(&original).await.unwrap());
+        assert_eq!(read(&link).await.unwrap(), b"changed!");
+        remove_file(&link).await.unwrap();
+        assert!(!try_exists(&link).await.unwrap());
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_hard_link_in_removed_dir() {
+    run_test("hard_link_in_removed_dir", |base_path| async move {
+        let dir = base_path.join("dir");
+        let original = dir.join("original.txt");
+        let link = base_path.join("link.txt");
+        create_dir(&dir).await.unwrap();
+        write(&original, b"shared").await.unwrap();
+        hard_link(&original, &link).await.unwrap();
+        hard_link(&link, dir.join("second.txt")).await.unwrap();
+
+        let mut rd = read_dir(&dir).await.unwrap();
+        let mut count = 0;
+        while let Some(entry) = rd.next_entry().await.unwrap() {
+            assert!(entry.file_type().unwrap().is_file());
+            count += 1;
+        }
+        assert_eq!(count, 2);
+
+        remove_dir_all(&dir).await.unwrap();
+        assert_eq!(read(&link).await.unwrap(), b"shared");
+        remove_file(&link).await.unwrap();
+    })
+    .await;
+}
+
 #[wasm_bindgen_test]
 async fn test_metadata_not_found() {
     run_test("metadata_not_found", |base_path| async move {
@@ -470,6 +1045,135 @@ async fn test_metadata_modified_time() {
     .await;
 }
 
+#[wasm_bindgen_test]
+async fn test_metadata_std_accessors() {
+    run_test("metadata_std_accessors", |base_path| async move {
+        let dir_path = base_path.join("dir");
+        let file_path = dir_path.join("file.txt");
+
+        create_dir(&dir_path).await.unwrap();
+        let dir_meta = metadata(&dir_path).await.unwrap();
+        assert!(dir_meta.file_type().is_dir());
+        assert!(!dir_meta.permissions().readonly());
+        let dir_created = dir_meta.created().unwrap();
+        let dir_modified = dir_meta.modified().unwrap();
+
+        write(&file_path, b"created").await.unwrap();
+        let file_meta = metadata(&file_path).await.unwrap();
+        assert!(file_meta.file_type().is_file());
+        assert!(file_meta.created().unwrap() >= dir_created);
+        assert_eq!(file_meta.accessed().unwrap(), file_meta.modified().unwrap());
+
+        // Creating an entry modifies the directory it lives in.
+        let dir_meta = metadata(&dir_path).await.unwrap();
+        assert_eq!(dir_meta.created().unwrap(), dir_created);
+        assert!(dir_meta.modified().unwrap() >= dir_modified);
+
+        let file = File::open(&file_path).await.unwrap();
+        let open_meta = file.metadata().await.unwrap();
+        assert_eq!(open_meta.created().unwrap(), file_meta.created().unwrap());
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_set_permissions_readonly() {
+    run_test("set_permissions_readonly", |base_path| async move {
+        let path = base_path.join("readonly.txt");
+        write(&path, b"locked").await.unwrap();
+        let writer = OpenOptions::new().write(true).open(&path).await.unwrap();
+
+        let mut perms = metadata(&path).await.unwrap().permissions();
+        perms.set_readonly(true);
+        set_permissions(&path, perms.clone()).await.unwrap();
+        assert!(metadata(&path).await.unwrap().permissions().readonly());
+
+        let denied = |res: io::Result<()>| {
+            assert_eq!(res.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        };
+        denied(write(&path, b"changed").await);
+        denied(OpenOptions::new().append(true).open(&path).await.map(drop));
+        denied(File::create(&path).await.map(drop));
+        denied(writer.set_len(0).await);
+        drop(writer);
+        denied(remove_file(&path).await);
+
+        // Reading is still allowed.
+        assert_eq!(read(&path).await.unwrap(), b"locked");
+        File::open(&path).await.unwrap();
+
+        // The mode belongs to the file, whatever path leads to it.
+        let sym = base_path.join("sym.txt");
+        let hard = base_path.join("hard.txt");
+        symlink("readonly.txt", &sym).await.unwrap();
+        hard_link(&path, &hard).await.unwrap();
+        for link in [&sym, &hard] {
+            assert!(metadata(link).await.unwrap().permissions().readonly());
+            denied(write(link, b"changed").await);
+            denied(OpenOptions::new().write(true).open(link).await.map(drop));
+        }
+        assert!(
+            !symlink_metadata(&sym)
+                .await
+                .unwrap()
+                .permissions()
+                .readonly()
+        );
+        remove_file(&sym).await.unwrap();
+
+        perms.set_readonly(false);
+        set_permissions(&hard, perms).await.unwrap();
+        assert!(!metadata(&path).await.unwrap().permissions().readonly());
+        write(&path, b"changed").await.unwrap();
+        remove_file(&hard).await.unwrap();
+        remove_file(&path).await.unwrap();
+
+        let err = set_permissions(base_path.join("missing"), Permissions::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_storage() {
+    run_test("storage", |base_path| async move {
+        let estimate = storage::estimate().await.unwrap();
+        assert!(estimate.quota() >= estimate.usage());
+        assert_eq!(estimate.available(), estimate.quota() - estimate.usage());
+        storage::persisted().await.unwrap();
+        // Only windows can ask for persistent storage.
+        assert_eq!(
+            storage::persist().await.unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+
+        write(base_path.join("a.txt"), b"12345").await.unwrap();
+        create_dir_all(base_path.join("sub/deeper")).await.unwrap();
+        write(base_path.join("sub/b.txt"), b"123").await.unwrap();
+        write(base_path.join("sub/deeper/c.txt"), b"1")
+            .await
+            .unwrap();
+        // Links are not followed.
+        symlink("sub", base_path.join("link")).await.unwrap();
+        assert_eq!(storage::disk_usage(&base_path).await.unwrap(), 9);
+        assert_eq!(storage::disk_usage(base_path.join("sub")).await.unwrap(), 4);
+        assert_eq!(
+            storage::disk_usage(base_path.join("a.txt")).await.unwrap(),
+            5
+        );
+        assert_eq!(
+            storage::disk_usage(base_path.join("missing"))
+                .await
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+    })
+    .await;
+}
+
 #[wasm_bindgen_test]
 async fn test_async_seek() {
     run_test("async_seek", |base_path| async move {
@@ -508,6 +1212,324 @@ async fn test_async_seek() {
     .await;
 }
 
+#[wasm_bindgen_test]
+async fn test_file_positional_io() {
+    run_test("file_positional_io", |base_path| async move {
+        let path = base_path.join("positional.bin");
+        write(&path, b"0123456789").await.unwrap();
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .await
+            .unwrap();
+        file.seek(io::SeekFrom::Start(2)).await.unwrap();
+
+        let mut buf = [0; 3];
+        assert_eq!(file.read_at(&mut buf, 5).await.unwrap(), 3);
+        assert_eq!(&buf, b"567");
+        assert_eq!(file.write_at(b"ab", 8).await.unwrap(), 2);
+        file.write_all_at(b"XYZ", 10).await.unwrap();
+
+        let mut buf = [0; 13];
+        file.read_exact_at(&mut buf, 0).await.unwrap();
+        assert_eq!(&buf, b"01234567abXYZ");
+        let err = file.read_exact_at(&mut buf, 1).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(file.read_at(&mut buf, 100).await.unwrap(), 0);
+
+        // The cursor is left where it was.
+        assert_eq!(file.stream_position().await.unwrap(), 2);
+        let mut buf = [0; 2];
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"23");
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_file_try_clone() {
+    run_test("file_try_clone", |base_path| async move {
+        let path = base_path.join("clone.txt");
+        write(&path, b"0123456789").await.unwrap();
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .await
+            .unwrap();
+        file.seek(io::SeekFrom::Start(2)).await.unwrap();
+        let mut clone = file.try_clone().await.unwrap();
+        clone.set_max_buf_size(4);
+
+        // The clone starts at the same position but moves on its own.
+        let mut buf = [0; 8];
+        clone.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"23456789");
+        clone.write_all(b"ab").await.unwrap();
+        assert_eq!(file.stream_position().await.unwrap(), 2);
+        let mut buf = [0; 2];
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"23");
+
+        assert_eq!(file.sync_access_handle().get_size().unwrap(), 12.0);
+        assert_eq!(file.file_handle().name(), "clone.txt");
+
+        // The handle stays usable while either of them is open.
+        drop(file);
+        clone.seek(io::SeekFrom::Start(0)).await.unwrap();
+        let mut content = String::new();
+        clone.read_to_string(&mut content).await.unwrap();
+        assert_eq!(content, "0123456789ab");
+
+        // Writes larger than `max_buf_size` land once, in order.
+        clone.seek(io::SeekFrom::Start(0)).await.unwrap();
+        clone.write_all(b"ABCDEFGHIJ").await.unwrap();
+        assert_eq!(clone.stream_position().await.unwrap(), 10);
+        drop(clone);
+        assert_eq!(read(&path).await.unwrap(), b"ABCDEFGHIJab");
+
+        // Both locks were released.
+        remove_file(&path).await.unwrap();
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_file_buffered() {
+    run_test("file_buffered", |base_path| async move {
+        let path = base_path.join("buffered.txt");
+        write(&path, b"0123456789").await.unwrap();
+
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path)
+            .await
+            .unwrap();
+        file.set_buffer_capacity(4);
+        assert_eq!(file.buffer_capacity(), 4);
+        let other = File::open(&path).await.unwrap();
+
+        // Small writes are held until the buffer fills or the file is flushed.
+        file.write_all(b"ab").await.unwrap();
+        let mut buf = [0; 10];
+        other.read_exact_at(&mut buf, 0).await.unwrap();
+        assert_eq!(&buf, b"0123456789");
+        file.write_all(b"cd").await.unwrap();
+        file.write_all(b"e").await.unwrap();
+        other.read_exact_at(&mut buf, 0).await.unwrap();
+        assert_eq!(&buf, b"abcd456789");
+        file.flush().await.unwrap();
+        other.read_exact_at(&mut buf, 0).await.unwrap();
+        assert_eq!(&buf, b"abcde56789");
+
+        // Reads see the data read ahead dropped once another `File` writes.
+        let mut byte = [0; 1];
+        file.read_exact(&mut byte).await.unwrap();
+        assert_eq!(&byte, b"5");
+        let writer = OpenOptions::new().write(true).open(&path).await.unwrap();
+        writer.write_all_at(b"X", 6).await.unwrap();
+        file.read_exact(&mut byte).await.unwrap();
+        assert_eq!(&byte, b"X");
+
+        // Seeking back within the data read ahead, and writing over it.
+        file.seek(io::SeekFrom::Current(-2)).await.unwrap();
+        file.read_exact(&mut byte).await.unwrap();
+        assert_eq!(&byte, b"5");
+        file.write_all(b"Y").await.unwrap();
+        file.seek(io::SeekFrom::Start(5)).await.unwrap();
+        let mut rest = String::new();
+        file.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "5Y789");
+
+        // Resizing writes out what is buffered first.
+        file.seek(io::SeekFrom::Start(8)).await.unwrap();
+        file.write_all(b"zz").await.unwrap();
+        file.set_len(12).await.unwrap();
+        assert_eq!(file.size().unwrap(), 12);
+
+        // Dropping the file writes out what is buffered.
+        file.seek(io::SeekFrom::End(0)).await.unwrap();
+        file.write_all(b"!").await.unwrap();
+        drop(file);
+        other.read_exact_at(&mut buf, 3).await.unwrap();
+        assert_eq!(&buf, b"de5Y7zz\0\0!");
+
+        // A large read bypasses the buffer, so a later seek must not land in stale data.
+        let content: Vec<u8> = (0..=255).collect();
+        write(&path, &content).await.unwrap();
+        let mut file = File::open(&path).await.unwrap();
+        file.set_buffer_capacity(8);
+        assert_eq!(file.fill_buf().await.unwrap(), &content[..8]);
+        file.consume_unpin(8);
+        let mut large = [0; 100];
+        file.read_exact(&mut large).await.unwrap();
+        assert_eq!(large, content[8..108]);
+        file.seek(io::SeekFrom::Start(104)).await.unwrap();
+        let mut small = [0; 4];
+        file.read_exact(&mut small).await.unwrap();
+        assert_eq!(small, content[104..108]);
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_file_lock() {
+    run_test("file_lock", |base_path| async move {
+        let path = base_path.join("lockfile");
+        write(&path, b"").await.unwrap();
+        let a = File::open(&path).await.unwrap();
+        let b = File::open(&path).await.unwrap();
+
+        a.lock().await.unwrap();
+        assert!(matches!(b.try_lock().await, Err(TryLockError::WouldBlock)));
+        assert!(matches!(
+            b.try_lock_shared().await,
+            Err(TryLockError::WouldBlock)
+        ));
+
+        // `b` gets the lock once `a` lets go of it.
+        let (locked, unlocked) = futures::join!(b.lock(), a.unlock());
+        locked.unwrap();
+        unlocked.unwrap();
+        assert!(matches!(a.try_lock().await, Err(TryLockError::WouldBlock)));
+
+        // Shared locks coexist but exclude exclusive ones.
+        b.unlock().await.unwrap();
+        a.lock_shared().await.unwrap();
+        b.try_lock_shared().await.unwrap();
+        assert!(matches!(a.try_lock().await, Err(TryLockError::WouldBlock)));
+
+        // Dropping a file releases its lock, and a lock can be upgraded.
+        drop(b);
+        a.try_lock().await.unwrap();
+        let c = File::open(&path).await.unwrap();
+        assert!(matches!(
+            c.try_lock_shared().await,
+            Err(TryLockError::WouldBlock)
+        ));
+        drop(a);
+        c.try_lock().await.unwrap();
+
+        // Unlike on native, a clone has a lock of its own.
+        let d = c.try_clone().await.unwrap();
+        assert!(matches!(d.try_lock().await, Err(TryLockError::WouldBlock)));
+        c.unlock().await.unwrap();
+        d.try_lock().await.unwrap();
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_web_lock_mode() {
+    run_test("web_lock_mode", |base_path| async move {
+        set_lock_mode(LockMode::WebLocks {
+            timeout: Some(std::time::Duration::from_secs(5)),
+        });
+
+        let path = base_path.join("shared.txt");
+        write(&path, b"hello").await.unwrap();
+        let a = File::open(&path).await.unwrap();
+        let b = File::open(&path).await.unwrap();
+        assert_eq!(read(&path).await.unwrap(), b"hello");
+
+        // Exclusive access waits for the shared holders to go away.
+        let (removed, ()) = futures::join!(remove_file(&path), async move {
+            drop(a);
+            drop(b);
+        });
+        removed.unwrap();
+        assert!(!try_exists(&path).await.unwrap());
+
+        set_lock_mode(LockMode::Local);
+        assert_eq!(lock_mode(), LockMode::Local);
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_retry_busy_file() {
+    run_test("retry_busy_file", |base_path| async move {
+        use std::time::Duration;
+
+        use wasm_bindgen::JsCast;
+        use wasm_bindgen_futures::JsFuture;
+        use web_sys::{DedicatedWorkerGlobalScope, FileSystemSyncAccessHandle};
+
+        let path = base_path.join("busy.txt");
+        let file = File::create(&path).await.unwrap();
+        let handle = file.file_handle().clone();
+        drop(file);
+
+        // Stands in for another worker holding the file open.
+        let other: FileSystemSyncAccessHandle = JsFuture::from(handle.create_sync_access_handle())
+            .await
+            .unwrap()
+            .unchecked_into();
+
+        set_retry_policy(RetryPolicy::NONE);
+        let err = File::open(&path).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+        set_retry_policy(RetryPolicy {
+            max_attempts: 100,
+            initial_backoff: Duration::from_millis(5),
+            max_backoff: Duration::from_millis(20),
+            deadline: Some(Duration::from_secs(5)),
+        });
+        let release = async {
+            let timer = js_sys::Promise::new(&mut |resolve, _| {
+                DedicatedWorkerGlobalScope::from(wasm_bindgen::JsValue::from(js_sys::global()))
+                    .set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, 50)
+                    .unwrap();
+            });
+            JsFuture::from(timer).await.unwrap();
+            other.close();
+        };
+        let (opened, ()) = futures::join!(File::open(&path), release);
+        opened.unwrap();
+
+        set_retry_policy(RetryPolicy::default());
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+#[cfg(feature = "tokio-io")]
+async fn test_file_tokio_io() {
+    run_test("file_tokio_io", |base_path| async move {
+        use tokio::io::{AsyncBufReadExt, AsyncSeekExt, AsyncWriteExt};
+
+        let path = base_path.join("tokio_io.txt");
+        let mut file = File::create(&path).await.unwrap();
+        AsyncWriteExt::write_all(&mut file, b"first\nsecond\n")
+            .await
+            .unwrap();
+        AsyncWriteExt::flush(&mut file).await.unwrap();
+        drop(file);
+
+        let mut file = File::open(&path).await.unwrap();
+        AsyncSeekExt::seek(&mut file, io::SeekFrom::Start(6))
+            .await
+            .unwrap();
+        let mut copied = Vec::new();
+        tokio::io::copy(&mut file, &mut copied).await.unwrap();
+        assert_eq!(copied, b"second\n");
+
+        AsyncSeekExt::seek(&mut file, io::SeekFrom::Start(0))
+            .await
+            .unwrap();
+        let mut lines = tokio::io::BufReader::new(file).lines();
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "first");
+        assert_eq!(lines.next_line().await.unwrap().unwrap(), "second");
+        assert_eq!(lines.next_line().await.unwrap(), None);
+    })
+    .await;
+}
+
 #[wasm_bindgen_test]
 async fn test_current_dir() {
     // Current dir is global, so we need to be careful with run_test isolation for CWD tests.
@@ -716,3 +1738,380 @@ async fn test_watch_rename_event() {
     })
     .await;
 }
+
+async fn exercise_backend<B: backend::Backend>(fs: &B, base_path: &std::path::Path) {
+    use backend::{FsDirEntry, FsMetadata};
+
+    let dir = base_path.join("dir");
+    let file = dir.join("file.txt");
+
+    fs.create_dir_all(&dir).await.unwrap();
+    fs.write(&file, b"hello").await.unwrap();
+    assert_eq!(fs.read_to_string(&file).await.unwrap(), "hello");
+    assert_eq!(fs.metadata(&file).await.unwrap().len(), 5);
+    assert!(fs.metadata(&dir).await.unwrap().is_dir());
+
+    {
+        let mut f = fs
+            .open_with(&file, OpenOptions::new().append(true))
+            .await
+            .unwrap();
+        f.write_all(b" world").await.unwrap();
+    }
+    let mut buf = String::new();
+    fs.open(&file)
+        .await
+        .unwrap()
+        .read_to_string(&mut buf)
+        .await
+        .unwrap();
+    assert_eq!(buf, "hello world");
+
+    let renamed = dir.join("renamed.txt");
+    fs.rename(&file, &renamed).await.unwrap();
+    assert!(!fs.try_exists(&file).await.unwrap());
+
+    let names = fs
+        .read_dir(&dir)
+        .await
+        .unwrap()
+        .map_ok(|e| e.file_name().to_string_lossy().to_string())
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(names, vec!["renamed.txt".to_string()]);
+
+    assert_eq!(
+        fs.remove_dir(&dir).await.unwrap_err().kind(),
+        io::ErrorKind::DirectoryNotEmpty
+    );
+    fs.remove_dir_all(&dir).await.unwrap();
+    assert!(!fs.try_exists(&dir).await.unwrap());
+}
+
+#[wasm_bindgen_test]
+async fn test_backend_default_fs() {
+    run_test("backend_default_fs", |base_path| async move {
+        exercise_backend(&backend::DefaultFs, &base_path).await;
+    })
+    .await;
+}
+
+#[wasm_bindgen_test]
+async fn test_backend_in_memory_fs() {
+    let fs = backend::InMemoryFs::new();
+    exercise_backend(&fs, std::path::Path::new("/base")).await;
+
+    use backend::Backend;
+    assert_eq!(
+        fs.read("/missing").await.unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+    assert_eq!(
+        fs.create_dir("/a/b").await.unwrap_err().kind(),
+        io::ErrorKind::NotFound
+    );
+    assert_eq!(
+        fs.canonicalize("base/../base").await.unwrap(),
+        PathBuf::from("/base")
+    );
+}
+
+#[cfg(feature = "opfs_offload")]
+#[wasm_bindgen_test]
+async fn test_offload_remote_file() {
+    run_test("offload_remote_file", |base_path| async move {
+        let (mut server, client) = offload::split();
+        wasm_bindgen_futures::spawn_local(async move { server.serve_default().await });
+
+        let path = base_path.join("remote.txt");
+        let mut file = client.create(&path).await.unwrap();
+        file.write_all(b"hello world").await.unwrap();
+        file.seek(io::SeekFrom::Start(6)).await.unwrap();
+        file.write_all(b"there").await.unwrap();
+        file.set_len(8).await.unwrap();
+        file.flush().await.unwrap();
+        drop(file);
+        assert_eq!(client.read_to_string(&path).await.unwrap(), "hello th");
+
+        let mut file = client
+            .open_with(&path, OpenOptions::new().read(true).write(true))
+            .await
+            .unwrap();
+        let mut buf = [0; 5];
+        file.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+        assert_eq!(file.stream_position().await.unwrap(), 5);
+        file.write_all(b"!").await.unwrap();
+        let mut rest = String::new();
+        file.read_to_string(&mut rest).await.unwrap();
+        assert_eq!(rest, "th");
+        drop(file);
+
+        let renamed = base_path.join("renamed.txt");
+        client.rename(&path, &renamed).await.unwrap();
+        assert!(!client.try_exists(&path).await.unwrap());
+        assert_eq!(client.read(&renamed).await.unwrap(), b"hello!th");
+        assert_eq!(
+            client.open(&path).await.unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+    })
+    .await;
+}
+
+#[cfg(feature = "opfs_offload")]
+#[wasm_bindgen_test]
+async fn test_offload_shared_ring() {
+    use futures::{FutureExt, StreamExt};
+    run_test("offload_shared_ring", |base_path| async move {
+        let ring = offload::SharedRing::new(4, 4);
+        let (mut server, client) = offload::split_shared(ring.clone());
+        wasm_bindgen_futures::spawn_local(async move { server.serve_default().await });
+
+        let path = base_path.join("shared.txt");
+        let mut buf = ring.alloc(10).await.unwrap();
+        assert_eq!(buf.chunks().map(<[u8]>::len).collect::<Vec<_>>(), [4, 4, 2]);
+        buf.copy_from_slice(b"0123456789");
+        client
+            .write_shared(&path, futures::stream::iter([buf]))
+            .await
+            .unwrap();
+        assert_eq!(client.read(&path).await.unwrap(), b"0123456789");
+
+        // Three slabs are taken, so a payload needing two waits for them.
+        let buf = ring.alloc(10).await.unwrap();
+        assert!(ring.alloc(8).now_or_never().is_none());
+        assert!(ring.try_alloc(8).unwrap().is_none());
+        drop(buf);
+        ring.alloc(16).await.unwrap();
+        assert_eq!(
+            ring.alloc(17).await.unwrap_err().kind(),
+            io::ErrorKind::InvalidInput
+        );
+
+        // Files larger than the ring go through it one slab at a time.
+        let content: Vec<u8> = (0..=100).collect();
+        let chunks = futures::stream::iter(content.chunks(4)).then(|data| {
+            let ring = ring.clone();
+            async move {
+                let mut buf = ring.alloc(data.len()).await.unwrap();
+                buf.copy_from_slice(data);
+                buf
+            }
+        });
+        client.write_shared(&path, chunks).await.unwrap();
+        assert_eq!(client.read(&path).await.unwrap(), content);
+
+        let mut reader = client.read_shared(&path).unwrap();
+        let mut read = Vec::new();
+        while let Some(chunk) = reader.next_chunk().await.unwrap() {
+            assert!(chunk.len() <= ring.slab_size());
+            read.extend(chunk.to_vec());
+        }
+        assert_eq!(read, content);
+
+        // Dropping a reader that did not take every chunk frees the ring.
+        let mut reader = client.read_shared(&path).unwrap();
+        reader.next_chunk().await.unwrap().unwrap();
+        drop(reader);
+        ring.alloc(16).await.unwrap();
+
+        let mut reader = client.read_shared(base_path.join("missing")).unwrap();
+        assert_eq!(
+            reader.next_chunk().await.unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(reader.next_chunk().await.unwrap().is_none());
+
+        let (mut server, client) = offload::split();
+        wasm_bindgen_futures::spawn_local(async move { server.serve_default().await });
+        assert_eq!(
+            client.read_shared(&path).unwrap_err().kind(),
+            io::ErrorKind::Unsupported
+        );
+    })
+    .await;
+}
+
+#[cfg(feature = "opfs_offload")]
+#[wasm_bindgen_test]
+async fn test_offload_builder() {
+    use std::time::Duration;
+    run_test("offload_builder", |base_path| async move {
+        let path = base_path.join("file.txt");
+        write(&path, "hello").await.unwrap();
+
+        // The deadline has passed once the server takes the request.
+        let (mut server, client) = offload::Builder::new().timeout(Duration::ZERO).build();
+        wasm_bindgen_futures::spawn_local(async move { server.serve_default().await });
+        assert_eq!(
+            client.read(&path).await.unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+
+        // Requests the server does not take time out too.
+        let (_server, client) = offload::Builder::new()
+            .timeout(Duration::from_millis(10))
+            .build();
+        assert_eq!(
+            client.read(&path).await.unwrap_err().kind(),
+            io::ErrorKind::TimedOut
+        );
+
+        // The server is busy with a removal waiting for `file` to be dropped.
+        let file = File::open(&path).await.unwrap();
+        let (mut server, client) = offload::Builder::new()
+            .max_in_flight(1)
+            .timeout(Duration::from_millis(100))
+            .build();
+        wasm_bindgen_futures::spawn_local(async move { server.serve_default().await });
+        let (removed, read) = futures::join!(
+            client.remove_file(&path),
+            client.read(base_path.join("other.txt"))
+        );
+        assert_eq!(removed.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(read.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        drop(file);
+
+        let (mut server, client) = offload::Builder::new()
+            .queue_depth(1)
+            .max_in_flight(1)
+            .timeout(Duration::from_secs(60))
+            .build();
+        let mut first = Box::pin(client.read(&path));
+        let mut second = Box::pin(client.read_to_string(&path));
+        assert!(futures::poll!(first.as_mut()).is_pending());
+        // The queue is full until the server takes the first request.
+        assert!(futures::poll!(second.as_mut()).is_pending());
+
+        wasm_bindgen_futures::spawn_local(async move { server.serve_default().await });
+        assert_eq!(first.await.unwrap(), b"hello");
+        assert_eq!(second.await.unwrap(), "hello");
+
+        // Operations on a `RemoteFile`, and closing it, wait for room in the queue too.
+        let other = base_path.join("other.txt");
+        write(&other, "other").await.unwrap();
+        let (mut server, client) = offload::Builder::new().queue_depth(1).build();
+        wasm_bindgen_futures::spawn_local(async move { server.serve_default().await });
+        let mut remote = client.open(&other).await.unwrap();
+        let mut first = Box::pin(client.read(&path));
+        assert!(futures::poll!(first.as_mut()).is_pending());
+        let mut content = String::new();
+        let mut read = Box::pin(remote.read_to_string(&mut content));
+        assert!(futures::poll!(read.as_mut()).is_pending());
+        assert_eq!(first.await.unwrap(), b"hello");
+        read.await.unwrap();
+        assert_eq!(content, "other");
+
+        let mut first = Box::pin(client.read(&path));
+        assert!(futures::poll!(first.as_mut()).is_pending());
+        drop(remote);
+        assert_eq!(first.await.unwrap(), b"hello");
+        // Waits for the server to close the file.
+        remove_file(&other).await.unwrap();
+    })
+    .await;
+}
+
+#[cfg(feature = "opfs_offload")]
+#[wasm_bindgen_test]
+async fn test_offload_path_order() {
+    run_test("offload_path_order", |base_path| async move {
+        let (mut server, client) = offload::split();
+        wasm_bindgen_futures::spawn_local(async move { server.serve_default().await });
+
+        let dir = base_path.join("a/b");
+        let file = dir.join("file.txt");
+        let other = base_path.join("other.txt");
+        for round in 0..5 {
+            let content = format!("round {round}");
+            let (created, written, read, written_other) = futures::join!(
+                client.create_dir_all(&dir),
+                client.write(&file, &content),
+                client.read_to_string(&file),
+                client.write(&other, &content),
+            );
+            created.unwrap();
+            written.unwrap();
+            written_other.unwrap();
+            assert_eq!(read.unwrap(), content);
+
+            let (removed, exists) = futures::join!(
+                client.remove_dir_all(base_path.join("a")),
+                client.try_exists(&file)
+            );
+            removed.unwrap();
+            assert!(!exists.unwrap());
+        }
+
+        // The entries arrive collected, so the `ReadDir` can be iterated.
+        client.write(&other, "").await.unwrap();
+        let names = client
+            .read_dir(&base_path)
+            .await
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["other.txt"]);
+    })
+    .await;
+}
+
+#[cfg(feature = "opfs_offload")]
+#[wasm_bindgen_test]
+async fn test_offload_batch() {
+    use offload::{BatchOutput, OnError};
+    run_test("offload_batch", |base_path| async move {
+        let (mut server, client) = offload::split();
+        wasm_bindgen_futures::spawn_local(async move { server.serve_default().await });
+
+        let dir = base_path.join("dir");
+        let mut batch = client.batch();
+        batch
+            .create_dir_all(dir.join("sub"))
+            .write(dir.join("sub/a.txt"), "a")
+            .copy(dir.join("sub/a.txt"), dir.join("b.txt"))
+            .read(base_path.join("missing.txt"))
+            .read(dir.join("b.txt"));
+        assert_eq!(batch.len(), 5);
+        let results = batch.send().await.unwrap();
+        assert_eq!(*results[0].as_ref().unwrap(), BatchOutput::Done);
+        assert_eq!(*results[2].as_ref().unwrap(), BatchOutput::Copied(1));
+        assert_eq!(
+            results[3].as_ref().unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            *results[4].as_ref().unwrap(),
+            BatchOutput::Read(b"a".to_vec())
+        );
+
+        let mut batch = client.batch();
+        batch
+            .on_error(OnError::Rollback)
+            .create_dir_all(base_path.join("new/deep"))
+            .write(base_path.join("new/deep/file.txt"), "x")
+            .write(dir.join("b.txt"), "overwritten")
+            .rename(dir.join("sub/a.txt"), dir.join("c.txt"))
+            .write(base_path.join("missing/file.txt"), "x")
+            .remove_dir_all(&dir);
+        let results = batch.send().await.unwrap();
+        assert!(results[..4].iter().all(Result::is_ok));
+        assert_eq!(
+            results[4].as_ref().unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert!(results[5].is_err());
+
+        assert!(!client.try_exists(base_path.join("new")).await.unwrap());
+        assert!(client.try_exists(dir.join("sub/a.txt")).await.unwrap());
+        assert!(!client.try_exists(dir.join("c.txt")).await.unwrap());
+        // Overwrites are not undone.
+        assert_eq!(
+            client.read_to_string(dir.join("b.txt")).await.unwrap(),
+            "overwritten"
+        );
+    })
+    .await;
+}